@@ -0,0 +1,121 @@
+//! Scores and solves with each backend, to compare them and catch
+//! regressions: `cargo bench`.
+//!
+//! Unlike the `bench` command, which prints a single score to compare
+//! machines, these measure each operation with statistics, and compare them
+//! with the previous run.
+
+use std::hint::black_box;
+use std::num::NonZeroUsize;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mastermind::batch::CodeBatch;
+use mastermind::packed::PackedCode;
+use mastermind::rules::Rules;
+use mastermind::solver::{Backend, Solver, Strategy};
+use mastermind::table::TableScorer;
+use mastermind::testutil::DeterministicCodeMaker;
+use mastermind::{Code, Game, Scorer};
+
+const BACKENDS: [(&str, Backend); 4] = [
+    ("scorer", Backend::Scorer),
+    ("packed", Backend::Packed),
+    ("table", Backend::Table),
+    ("batch", Backend::Batch),
+];
+
+/// The secrets each solver breaks, spread over all the codes.
+const SOLVE_STEP: usize = 97;
+
+fn code(code: &str) -> Code {
+    code.parse().unwrap()
+}
+
+fn single_score(c: &mut Criterion) {
+    let (secret, guess) = (code("ABCD"), code("BADC"));
+    let mut group = c.benchmark_group("score");
+    let scorer = Scorer::new(secret);
+    group.bench_function("scorer", |b| b.iter(|| scorer.score(black_box(guess))));
+    let (packed_secret, packed_guess) = (PackedCode::from(secret), PackedCode::from(guess));
+    group.bench_function("packed", |b| {
+        b.iter(|| packed_secret.score(black_box(packed_guess)))
+    });
+    let table = TableScorer::shared();
+    group.bench_function("table", |b| {
+        b.iter(|| table.score(secret, black_box(guess)))
+    });
+    group.finish();
+}
+
+fn all_pairs(c: &mut Criterion) {
+    let codes = Code::all();
+    let mut group = c.benchmark_group("score all pairs");
+    group.throughput(Throughput::Elements((codes.len() * codes.len()) as u64));
+    group.bench_function("scorer", |b| {
+        b.iter(|| {
+            for &secret in &codes {
+                let scorer = Scorer::new(secret);
+                for &guess in &codes {
+                    black_box(scorer.score(black_box(guess)));
+                }
+            }
+        })
+    });
+    let packed: Vec<PackedCode> = codes.iter().map(|&code| code.into()).collect();
+    group.bench_function("packed", |b| {
+        b.iter(|| {
+            for secret in &packed {
+                for &guess in &packed {
+                    black_box(secret.score(black_box(guess)));
+                }
+            }
+        })
+    });
+    let table = TableScorer::shared();
+    group.bench_function("table", |b| {
+        b.iter(|| {
+            for &secret in &codes {
+                for &guess in &codes {
+                    black_box(table.score(secret, black_box(guess)));
+                }
+            }
+        })
+    });
+    let batch = CodeBatch::new(codes.iter().copied());
+    group.bench_function("batch", |b| {
+        b.iter(|| {
+            for &guess in &codes {
+                for score in batch.scores(black_box(guess)) {
+                    black_box(score);
+                }
+            }
+        })
+    });
+    group.finish();
+}
+
+fn solve(c: &mut Criterion) {
+    let secrets: Vec<Code> = Code::all().into_iter().step_by(SOLVE_STEP).collect();
+    let mut group = c.benchmark_group("solve");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(secrets.len() as u64));
+    for strategy in Strategy::ALL {
+        for (name, backend) in BACKENDS {
+            let id = BenchmarkId::new(format!("{:?}", strategy), name);
+            group.bench_function(id, |b| {
+                b.iter(|| {
+                    for &secret in &secrets {
+                        let code_maker = DeterministicCodeMaker::new(secret);
+                        let mut solver = Solver::with_backend(Rules::default(), strategy, backend);
+                        Game::new(NonZeroUsize::new(10).unwrap(), &code_maker, &mut solver).play();
+                        black_box(solver.candidates());
+                    }
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, single_score, all_pairs, solve);
+criterion_main!(benches);
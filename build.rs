@@ -0,0 +1,16 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    grpc();
+}
+
+/// Generates the gRPC service, with protox so that protoc is not needed.
+#[cfg(feature = "grpc")]
+fn grpc() {
+    println!("cargo:rerun-if-changed=proto/mastermind.proto");
+    let descriptors = protox::compile(["mastermind.proto"], ["proto"])
+        .unwrap_or_else(|err| panic!("proto/mastermind.proto: {}", err));
+    tonic_build::configure()
+        .compile_fds(descriptors)
+        .unwrap_or_else(|err| panic!("cannot generate the gRPC service: {}", err));
+}
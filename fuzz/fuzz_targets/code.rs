@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mastermind::Code;
+
+// A code read from any text is written back as text which reads the same.
+fuzz_target!(|text: &str| {
+    if let Ok(code) = text.parse::<Code>() {
+        assert_eq!(code.to_string().parse::<Code>(), Ok(code));
+    }
+});
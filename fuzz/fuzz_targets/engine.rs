@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mastermind::engine::Command;
+
+// Commands come from the GUI on stdin: reading them, and writing back what
+// was read, must not panic.
+fuzz_target!(|line: &str| {
+    if let Ok(command) = line.parse::<Command>() {
+        let _ = command.to_string();
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mastermind::net::Message;
+
+// Lines from the other players may be anything: reading them, and writing
+// back what was read, must not panic.
+fuzz_target!(|line: &str| {
+    if let Ok(message) = line.parse::<Message>() {
+        let _ = message.to_string();
+    }
+});
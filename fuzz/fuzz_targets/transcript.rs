@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mastermind::store::{parse_transcript, transcript};
+
+// Transcripts come from files and uploads. The rounds read from one are
+// written back as a transcript which reads the same.
+fuzz_target!(|text: &str| {
+    if let Ok(rounds) = parse_transcript(text) {
+        assert_eq!(parse_transcript(&transcript(&rounds)), Ok(rounds));
+    }
+});
@@ -0,0 +1,751 @@
+//! How well the best possible code breaker plays, to tell how far a solver
+//! is from it, and how hard each code is to break.
+//!
+//! The search for the best strategy is exact: it tries every guess allowed
+//! by the rules, and cuts the branches which cannot beat the best strategy
+//! found so far. Small rules take milliseconds, the classic game over half
+//! an hour in release builds.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::rules::Rules;
+use crate::solver::{Solver, Strategy};
+use crate::{Code, CodeBreaker, ParseCodeError, Score, Scorer, SIZE};
+
+/// The games of the best code breakers, against each code allowed by the
+/// rules.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Optimal {
+    /// The number of codes broken in one guess, two guesses, and so on,
+    /// by a strategy which needs the fewest guesses on average.
+    pub distribution: Vec<usize>,
+    /// The fewest guesses which break every code, with a strategy
+    /// minimizing them, which may need more on average.
+    pub worst_case: usize,
+}
+
+impl Optimal {
+    /// The number of guesses needed to break every code once, at best.
+    pub fn total(&self) -> usize {
+        self.distribution
+            .iter()
+            .enumerate()
+            .map(|(i, codes)| (i + 1) * codes)
+            .sum()
+    }
+
+    /// The fewest guesses needed on average, e.g. 5625 / 1296 ≈ 4.340 in
+    /// the classic game.
+    pub fn expected(&self) -> f64 {
+        self.total() as f64 / self.distribution.iter().sum::<usize>() as f64
+    }
+}
+
+/// Works out the best strategies under `rules`, in the average and in the
+/// worst case.
+///
+/// Returns `None` if the search finds no strategy, which it always does
+/// without a bound on the number of guesses.
+pub fn optimal(rules: Rules) -> Option<Optimal> {
+    let mut search = Search::new(rules);
+    let codes: Vec<u16> = (0..search.codes.len() as u16).collect();
+    let distribution = search.best(&codes, usize::MAX, true)?;
+    let mut worst_case = 1;
+    while !search.fits(&codes, worst_case, true) {
+        worst_case += 1;
+    }
+    Some(Optimal {
+        distribution,
+        worst_case,
+    })
+}
+
+const SCORES: usize = (SIZE + 1) * (SIZE + 1);
+const WIN: u8 = (SIZE * (SIZE + 1)) as u8;
+
+/// The scores a guess may get, but the winning one.
+fn branches() -> usize {
+    (0..SCORES)
+        .filter(|&index| Score::from_counts(index / (SIZE + 1), index % (SIZE + 1)).is_some())
+        .count()
+        - 1
+}
+
+/// The codes of the rules, by their position in `codes`, and what is known
+/// of their sets so far.
+struct Search {
+    codes: Vec<Code>,
+    /// The score of the `i`-th code against the `j`-th, at `i * len + j`.
+    scores: Vec<u8>,
+    /// The number of codes told apart from each other by one guess at
+    /// most, for the bounds.
+    branches: usize,
+    /// The `lower_bound` of each number of codes, looked up for every guess.
+    lower_bounds: Vec<usize>,
+    /// The distribution of the best strategy for a set of codes.
+    best: HashMap<Vec<u16>, Vec<usize>>,
+    /// A number of guesses which no strategy beats for a set of codes.
+    lower: HashMap<Vec<u16>, usize>,
+    /// The worst cases a set of codes fits, and does not fit, in.
+    fits: HashMap<Vec<u16>, (usize, usize)>,
+}
+
+impl Search {
+    fn new(rules: Rules) -> Self {
+        let codes = rules.codes();
+        let scores = codes
+            .iter()
+            .flat_map(|&guess| {
+                let scorer = Scorer::new(guess);
+                codes
+                    .iter()
+                    .map(move |&code| scorer.score(code).index() as u8)
+            })
+            .collect();
+        let branches = branches();
+        let lower_bounds = (0..=codes.len())
+            .map(|mut codes| {
+                let (mut total, mut depth, mut width) = (0, 1, 1);
+                while codes > 0 {
+                    let broken = codes.min(width);
+                    total += depth * broken;
+                    codes -= broken;
+                    depth += 1;
+                    width *= branches;
+                }
+                total
+            })
+            .collect();
+        Search {
+            codes,
+            scores,
+            branches,
+            lower_bounds,
+            best: HashMap::new(),
+            lower: HashMap::new(),
+            fits: HashMap::new(),
+        }
+    }
+
+    /// The codes of `set` split by the score `guess` gets against them.
+    fn partition(&self, set: &[u16], guess: usize) -> Vec<Vec<u16>> {
+        let mut partition = vec![Vec::new(); SCORES];
+        let scores = &self.scores[guess * self.codes.len()..];
+        for &code in set {
+            partition[scores[code as usize] as usize].push(code);
+        }
+        partition
+    }
+
+    /// The number of codes of `set` which get each score against `guess`,
+    /// cheaper than their partition, which is only worked out for the
+    /// guesses which the bounds do not rule out.
+    fn counts(&self, set: &[u16], guess: usize) -> [u16; SCORES] {
+        let mut counts = [0; SCORES];
+        let scores = &self.scores[guess * self.codes.len()..];
+        for &code in set {
+            counts[scores[code as usize] as usize] += 1;
+        }
+        counts
+    }
+
+    /// The guesses which tell some codes of `set` apart, with the number of
+    /// codes getting each score.
+    ///
+    /// At the start, with every code left, guesses which only differ by
+    /// their colors or by the order of their pegs are tried only once.
+    fn guesses(&self, set: &[u16], first: bool) -> Vec<(usize, [u16; SCORES])> {
+        let mut shapes = Vec::new();
+        (0..self.codes.len())
+            .filter(|&guess| {
+                if !first {
+                    return true;
+                }
+                let mut counts = [0; SIZE];
+                for peg in self.codes[guess].pegs() {
+                    counts[self.codes[guess]
+                        .pegs()
+                        .iter()
+                        .filter(|&&p| p == peg)
+                        .count()
+                        - 1] += 1;
+                }
+                let new = !shapes.contains(&counts);
+                shapes.push(counts);
+                new
+            })
+            .map(|guess| (guess, self.counts(set, guess)))
+            .filter(|(_, counts)| counts.iter().all(|&codes| (codes as usize) < set.len()))
+            .collect()
+    }
+
+    /// The fewest guesses breaking `codes` codes in all, if each guess told
+    /// apart as many codes as possible.
+    fn lower_bound(&self, codes: usize) -> usize {
+        self.lower_bounds[codes]
+    }
+
+    /// The distribution of the strategy breaking `set` in the fewest
+    /// guesses, if it needs fewer than `bound` in all.
+    fn best(&mut self, set: &[u16], bound: usize, first: bool) -> Option<Vec<usize>> {
+        match set.len() {
+            0 => return Some(Vec::new()),
+            1 => return (bound > 1).then(|| vec![1]),
+            2 => return (bound > 3).then(|| vec![1, 1]),
+            _ => {}
+        }
+        if let Some(best) = self.best.get(set) {
+            return (total(best) < bound).then(|| best.clone());
+        }
+        let lower = self
+            .lower
+            .get(set)
+            .copied()
+            .unwrap_or_else(|| self.lower_bound(set.len()));
+        if lower >= bound {
+            return None;
+        }
+        // No strategy beats a code of the set telling the others apart.
+        if set.iter().any(|&guess| {
+            self.counts(set, guess as usize)
+                .iter()
+                .all(|&codes| codes <= 1)
+        }) {
+            let best = vec![1, set.len() - 1];
+            self.best.insert(set.to_vec(), best.clone());
+            return (total(&best) < bound).then_some(best);
+        }
+
+        let mut guesses: Vec<(usize, usize)> = self
+            .guesses(set, first)
+            .into_iter()
+            .map(|(guess, counts)| {
+                let lower = set.len()
+                    + counts
+                        .iter()
+                        .enumerate()
+                        .filter(|&(score, _)| score != WIN as usize)
+                        .map(|(_, &codes)| self.lower_bound(codes as usize))
+                        .sum::<usize>();
+                (lower, guess)
+            })
+            .filter(|&(lower, _)| lower < bound)
+            .collect();
+        guesses.sort_unstable();
+
+        let mut best: Option<Vec<usize>> = None;
+        let mut bound = bound;
+        for (lower, guess) in guesses {
+            if lower >= bound {
+                break;
+            }
+            let partition = self.partition(set, guess);
+            if let Some(distribution) = self.split(set.len(), partition, lower, bound) {
+                bound = total(&distribution);
+                best = Some(distribution);
+            }
+        }
+        match &best {
+            Some(best) => {
+                self.best.insert(set.to_vec(), best.clone());
+            }
+            None => {
+                self.lower.insert(set.to_vec(), bound);
+            }
+        }
+        best
+    }
+
+    /// The distribution of the best strategy after a guess splitting `len`
+    /// codes into `partition`, if it needs fewer than `bound` guesses in
+    /// all, knowing it needs at least `lower`.
+    fn split(
+        &mut self,
+        len: usize,
+        partition: Vec<Vec<u16>>,
+        mut lower: usize,
+        bound: usize,
+    ) -> Option<Vec<usize>> {
+        let mut distribution = vec![0];
+        for (score, codes) in partition.into_iter().enumerate() {
+            if score == WIN as usize {
+                distribution[0] += codes.len();
+                continue;
+            }
+            if codes.is_empty() {
+                continue;
+            }
+            let own_lower = self.lower_bound(codes.len());
+            // The other sets need at least their own lower bound.
+            let own_bound = bound - (lower - own_lower);
+            let own = self.best(&codes, own_bound, false)?;
+            lower += total(&own) - own_lower;
+            if distribution.len() < own.len() + 1 {
+                distribution.resize(own.len() + 1, 0);
+            }
+            for (depth, count) in own.into_iter().enumerate() {
+                distribution[depth + 1] += count;
+            }
+        }
+        debug_assert_eq!(distribution.iter().sum::<usize>(), len);
+        (lower < bound).then_some(distribution)
+    }
+
+    /// Whether some strategy breaks every code of `set` within `depth`
+    /// guesses.
+    fn fits(&mut self, set: &[u16], depth: usize, first: bool) -> bool {
+        match set.len() {
+            0 => return true,
+            1 => return depth >= 1,
+            2 => return depth >= 2,
+            len if len > self.capacity(depth) => return false,
+            _ => {}
+        }
+        let (fits, fails) = self.fits.get(set).copied().unwrap_or((usize::MAX, 0));
+        if depth >= fits {
+            return true;
+        }
+        if depth <= fails {
+            return false;
+        }
+
+        let capacity = self.capacity(depth - 1);
+        let mut guesses: Vec<(u16, usize)> = self
+            .guesses(set, first)
+            .into_iter()
+            .filter(|(_, counts)| {
+                counts
+                    .iter()
+                    .enumerate()
+                    .all(|(score, &codes)| score == WIN as usize || codes as usize <= capacity)
+            })
+            .map(|(guess, counts)| (counts.iter().copied().max().unwrap_or(0), guess))
+            .collect();
+        guesses.sort();
+        let found = guesses.into_iter().any(|(_, guess)| {
+            self.partition(set, guess)
+                .iter()
+                .enumerate()
+                .all(|(score, codes)| score == WIN as usize || self.fits(codes, depth - 1, false))
+        });
+
+        let known = self.fits.entry(set.to_vec()).or_insert((usize::MAX, 0));
+        if found {
+            known.0 = known.0.min(depth);
+        } else {
+            known.1 = known.1.max(depth);
+        }
+        found
+    }
+
+    /// The most codes which `depth` guesses can break.
+    fn capacity(&self, depth: usize) -> usize {
+        (0..depth)
+            .map(|i| self.branches.saturating_pow(i as u32))
+            .fold(0, usize::saturating_add)
+    }
+}
+
+/// The number of guesses of a distribution.
+fn total(distribution: &[usize]) -> usize {
+    distribution
+        .iter()
+        .enumerate()
+        .map(|(i, codes)| (i + 1) * codes)
+        .sum()
+}
+
+/// How hard a code is for a solver to break, e.g. to pick puzzles or to
+/// match players with codes of their level.
+///
+/// It is written on one line, the code first, then the guesses and the
+/// entropy before each guess:
+///
+/// ```text
+/// FDCA 5 10.34 8.00 5.36 2.58 0.00
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Difficulty {
+    pub code: Code,
+    /// The guesses the solver needs.
+    pub guesses: usize,
+    /// The bits of information the solver lacks before each guess, i.e.
+    /// the base 2 logarithm of the number of codes still possible.
+    pub entropy: Vec<f64>,
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.code, self.guesses)?;
+        for bits in &self.entropy {
+            write!(f, " {:.2}", bits)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ParseDifficultyError {
+    MissingCode,
+    Code(ParseCodeError),
+    MissingGuesses,
+    Guesses(String),
+    Entropy(String),
+    /// The entropy is not given before each of the guesses.
+    EntropyCount(usize),
+}
+
+impl fmt::Display for ParseDifficultyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseDifficultyError::MissingCode => write!(f, "expected the code"),
+            ParseDifficultyError::Code(err) => write!(f, "{}", err),
+            ParseDifficultyError::MissingGuesses => write!(f, "expected the number of guesses"),
+            ParseDifficultyError::Guesses(guesses) => {
+                write!(f, "invalid number of guesses '{}'", guesses)
+            }
+            ParseDifficultyError::Entropy(bits) => write!(f, "invalid entropy '{}'", bits),
+            ParseDifficultyError::EntropyCount(guesses) => write!(
+                f,
+                "expected the entropy before each of the {} guesses",
+                guesses
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseDifficultyError {}
+
+impl FromStr for Difficulty {
+    type Err = ParseDifficultyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let code = words
+            .next()
+            .ok_or(ParseDifficultyError::MissingCode)?
+            .parse()
+            .map_err(ParseDifficultyError::Code)?;
+        let guesses = words.next().ok_or(ParseDifficultyError::MissingGuesses)?;
+        let guesses = guesses
+            .parse()
+            .map_err(|_| ParseDifficultyError::Guesses(guesses.to_string()))?;
+        let entropy = words
+            .map(|bits| {
+                bits.parse()
+                    .map_err(|_| ParseDifficultyError::Entropy(bits.to_string()))
+            })
+            .collect::<Result<Vec<f64>, _>>()?;
+        if entropy.len() != guesses {
+            return Err(ParseDifficultyError::EntropyCount(guesses));
+        }
+        Ok(Difficulty {
+            code,
+            guesses,
+            entropy,
+        })
+    }
+}
+
+/// Rates every code allowed by `rules` by how the solver playing `strategy`
+/// breaks it, in the order of `Rules::codes`.
+pub fn difficulties(rules: Rules, strategy: Strategy) -> Vec<Difficulty> {
+    rules
+        .codes()
+        .into_iter()
+        .map(|code| rate(rules, strategy, code))
+        .collect()
+}
+
+fn rate(rules: Rules, strategy: Strategy, code: Code) -> Difficulty {
+    let scorer = Scorer::new(code);
+    let mut solver = Solver::with_rules(rules, strategy);
+    let mut entropy = Vec::new();
+    loop {
+        entropy.push((solver.candidates().len() as f64).log2());
+        let score = scorer.score(solver.guess_code());
+        if score.matches() == SIZE {
+            break;
+        }
+        solver.set_score(score);
+    }
+    Difficulty {
+        code,
+        guesses: entropy.len(),
+        entropy,
+    }
+}
+
+/// How a solver fares against every code allowed by the rules, e.g. for
+/// dashboards following the solvers from one version to the next.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub rules: Rules,
+    pub strategy: Strategy,
+    /// How the solver breaks each code, in the order of `Rules::codes`.
+    pub codes: Vec<Difficulty>,
+    /// How long the solver took to break each code, in the same order.
+    pub times: Vec<Duration>,
+}
+
+impl Report {
+    /// The guesses the solver needs on average.
+    pub fn average(&self) -> f64 {
+        let guesses: usize = self.codes.iter().map(|code| code.guesses).sum();
+        guesses as f64 / self.codes.len() as f64
+    }
+
+    /// The most guesses the solver needs.
+    pub fn worst_case(&self) -> usize {
+        self.codes
+            .iter()
+            .map(|code| code.guesses)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The codes which need the most guesses.
+    pub fn hardest(&self) -> Vec<Code> {
+        let worst_case = self.worst_case();
+        self.codes
+            .iter()
+            .filter(|code| code.guesses == worst_case)
+            .map(|code| code.code)
+            .collect()
+    }
+
+    /// How long the solver took to break every code.
+    pub fn elapsed(&self) -> Duration {
+        self.times.iter().sum()
+    }
+
+    /// Writes the report as a JSON object, with the `json` feature:
+    ///
+    /// ```text
+    /// {"average":4.476,"codes":[{"code":"AAAA","entropy":[10.34,...],
+    /// "guesses":4,"seconds":0.001},...],"hardest":["AAEF",...],
+    /// "rules":"6 duplicates","seconds":1.2,"strategy":"minimax",
+    /// "worst_case":5}
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        let codes: Vec<serde_json::Value> = self
+            .codes
+            .iter()
+            .zip(&self.times)
+            .map(|(code, time)| {
+                serde_json::json!({
+                    "code": code.code.to_string(),
+                    "guesses": code.guesses,
+                    "seconds": time.as_secs_f64(),
+                    "entropy": code.entropy,
+                })
+            })
+            .collect();
+        let hardest: Vec<String> = self.hardest().iter().map(Code::to_string).collect();
+        serde_json::json!({
+            "rules": self.rules.to_string(),
+            "strategy": match self.strategy {
+                Strategy::FirstCandidate => "first-candidate",
+                Strategy::Minimax => "minimax",
+            },
+            "average": self.average(),
+            "worst_case": self.worst_case(),
+            "hardest": hardest,
+            "seconds": self.elapsed().as_secs_f64(),
+            "codes": codes,
+        })
+        .to_string()
+    }
+}
+
+/// Plays the solver playing `strategy` against every code allowed by
+/// `rules`, timing it.
+pub fn report(rules: Rules, strategy: Strategy) -> Report {
+    let (codes, times) = rules
+        .codes()
+        .into_iter()
+        .map(|code| {
+            let start = Instant::now();
+            let difficulty = rate(rules, strategy, code);
+            (difficulty, start.elapsed())
+        })
+        .unzip();
+    Report {
+        rules,
+        strategy,
+        codes,
+        times,
+    }
+}
+
+#[cfg(test)]
+mod test_analysis {
+    use super::*;
+    use crate::testutil::DeterministicCodeMaker;
+    use crate::{Game, GameOutcome};
+
+    /// The guesses the solver needs against each code of the rules.
+    fn solver_guesses(rules: Rules, strategy: Strategy) -> Vec<usize> {
+        rules
+            .codes()
+            .into_iter()
+            .map(|code| {
+                let mut solver = Solver::with_rules(rules, strategy);
+                let outcome = Game::unlimited(&DeterministicCodeMaker::new(code), &mut solver)
+                    .with_rules(rules)
+                    .play();
+                match outcome {
+                    GameOutcome::Won(rounds) => rounds,
+                    outcome => panic!("{:?}", outcome),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn beats_the_solver() {
+        for rules in [
+            Rules::new(2, true).unwrap(),
+            Rules::new(3, true).unwrap(),
+            Rules::new(5, false).unwrap(),
+        ] {
+            let optimal = optimal(rules).unwrap();
+            let codes = rules.codes().len();
+            assert_eq!(optimal.distribution.iter().sum::<usize>(), codes);
+            assert!(optimal.distribution.len() >= optimal.worst_case);
+            for strategy in [Strategy::FirstCandidate, Strategy::Minimax] {
+                let guesses = solver_guesses(rules, strategy);
+                assert!(optimal.total() <= guesses.iter().sum());
+                assert!(optimal.worst_case <= *guesses.iter().max().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn two_colors() {
+        // AABB scores all but AAAA and BBBB apart.
+        let optimal = optimal(Rules::new(2, true).unwrap()).unwrap();
+        assert_eq!(optimal.total(), 44);
+        assert_eq!(optimal.worst_case, 4);
+        assert!(optimal.expected() > 2.0);
+    }
+
+    /// The optimum of rules small enough to check on every run, found by a
+    /// separate exhaustive search.
+    #[test]
+    fn small_rules() {
+        let three_colors = optimal(Rules::new(3, true).unwrap()).unwrap();
+        assert_eq!(three_colors.distribution, [1, 8, 59, 13]);
+        assert_eq!(three_colors.total(), 246);
+        assert_eq!(three_colors.worst_case, 4);
+
+        let unique = optimal(Rules::new(4, false).unwrap()).unwrap();
+        assert_eq!(unique.total(), 86);
+        assert_eq!(unique.worst_case, 5);
+    }
+
+    /// The optimum of the classic game, which `optimal` takes over a
+    /// minute to find in release: 5625 guesses in all, as found by Koyama
+    /// and Lai.
+    const CLASSIC: [usize; 6] = [1, 8, 91, 647, 547, 2];
+
+    /// The parts of the classic optimum which the search finds quickly.
+    #[test]
+    fn classic_bounds() {
+        assert_eq!(
+            CLASSIC.iter().sum::<usize>(),
+            Rules::default().codes().len()
+        );
+        assert_eq!(total(&CLASSIC), 5625);
+        let mut search = Search::new(Rules::default());
+        let codes: Vec<u16> = (0..search.codes.len() as u16).collect();
+        assert!(search.lower_bound(codes.len()) <= total(&CLASSIC));
+        // Some strategy breaks every code within 5 guesses, although the
+        // one needing the fewest on average takes 6 for 2 of them.
+        assert!(!search.fits(&codes, 4, true));
+        assert!(search.fits(&codes, 5, true));
+    }
+
+    /// Run by CI in release, with `--ignored`.
+    #[test]
+    #[ignore = "takes over a minute in release"]
+    fn classic() {
+        let classic = optimal(Rules::default()).unwrap();
+        assert_eq!(classic.distribution, CLASSIC);
+        assert_eq!(classic.worst_case, 5);
+    }
+
+    #[test]
+    fn rates_every_code() {
+        let rules = Rules::new(4, false).unwrap();
+        let table = difficulties(rules, Strategy::FirstCandidate);
+        assert_eq!(table.len(), rules.codes().len());
+        // The solver opens with the first code.
+        assert_eq!(table[0].code, rules.codes()[0]);
+        assert_eq!(table[0].guesses, 1);
+        for difficulty in &table {
+            assert_eq!(difficulty.entropy[0], (rules.codes().len() as f64).log2());
+            assert!(difficulty.entropy.windows(2).all(|bits| bits[0] > bits[1]));
+            let line = difficulty.to_string();
+            let read: Difficulty = line.parse().unwrap();
+            assert_eq!(
+                (read.code, read.guesses),
+                (difficulty.code, difficulty.guesses)
+            );
+            assert_eq!(read.to_string(), line);
+        }
+    }
+
+    #[test]
+    fn reports() {
+        let rules = Rules::new(4, false).unwrap();
+        let report = report(rules, Strategy::Minimax);
+        assert_eq!(report.codes, difficulties(rules, Strategy::Minimax));
+        assert_eq!(report.times.len(), report.codes.len());
+        let worst_case = report.worst_case();
+        assert!(report.average() <= worst_case as f64);
+        assert!(report.hardest().iter().all(|&code| report
+            .codes
+            .iter()
+            .any(|d| d.code == code && d.guesses == worst_case)));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn reports_json() {
+        let report = report(Rules::new(4, false).unwrap(), Strategy::FirstCandidate);
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["rules"], "4 unique");
+        assert_eq!(json["strategy"], "first-candidate");
+        assert_eq!(json["worst_case"], report.worst_case());
+        assert_eq!(json["codes"].as_array().unwrap().len(), 24);
+        assert_eq!(json["codes"][0]["code"], "ABCD");
+        assert_eq!(json["codes"][0]["guesses"], 1);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            "".parse::<Difficulty>(),
+            Err(ParseDifficultyError::MissingCode)
+        );
+        assert_eq!(
+            "ABCG 1 0.00".parse::<Difficulty>(),
+            Err(ParseDifficultyError::Code(ParseCodeError::InvalidPeg('G')))
+        );
+        assert_eq!(
+            "ABCD 2 3.00".parse::<Difficulty>(),
+            Err(ParseDifficultyError::EntropyCount(2))
+        );
+        assert_eq!(
+            "ABCD 1 x".parse::<Difficulty>(),
+            Err(ParseDifficultyError::Entropy("x".to_string()))
+        );
+        assert!("ABCD 1 0.00".parse::<Difficulty>().is_ok());
+    }
+}
@@ -0,0 +1,200 @@
+//! Tokens proving that a message comes from one of the players of a game.
+//!
+//! The server gives each player a token when their game starts. Requests
+//! which do not come from the connection the player joined with, or which
+//! only some players may send, carry the token, and are allowed if its
+//! grant has the capability they need.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::lobby::SessionId;
+use crate::net::Role;
+
+/// A random 128-bit secret, written as 32 hexadecimal digits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Token(u128);
+
+impl Token {
+    pub fn random() -> Self {
+        Token(rand::random())
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParseTokenError;
+
+impl fmt::Display for ParseTokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a token is 32 hexadecimal digits")
+    }
+}
+
+impl std::error::Error for ParseTokenError {}
+
+impl FromStr for Token {
+    type Err = ParseTokenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 32 {
+            return Err(ParseTokenError);
+        }
+        u128::from_str_radix(s, 16)
+            .map(Token)
+            .map_err(|_| ParseTokenError)
+    }
+}
+
+/// What a player may do in their game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Capability {
+    /// Make the code or guess it.
+    Play,
+    /// Watch the game, even if it is private.
+    Observe,
+    /// Give up, losing the game.
+    Resign,
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Capability::Play => write!(f, "play"),
+            Capability::Observe => write!(f, "observe"),
+            Capability::Resign => write!(f, "resign"),
+        }
+    }
+}
+
+impl Capability {
+    /// The capabilities of the players of each role: only code breakers
+    /// can resign, since code makers have nothing left to do once the code
+    /// is made.
+    pub fn of(role: Role) -> &'static [Capability] {
+        match role {
+            Role::Maker => &[Capability::Play, Capability::Observe],
+            Role::Breaker => &[Capability::Play, Capability::Observe, Capability::Resign],
+        }
+    }
+}
+
+/// The player a token was given to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grant {
+    pub session: SessionId,
+    pub role: Role,
+    pub capabilities: Vec<Capability>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum AuthError {
+    UnknownToken,
+    /// The token was given for another game.
+    WrongSession(SessionId),
+    Forbidden(Capability),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuthError::UnknownToken => write!(f, "unknown token"),
+            AuthError::WrongSession(session) => {
+                write!(f, "the token is not for game {}", session)
+            }
+            AuthError::Forbidden(capability) => write!(f, "the player may not {}", capability),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// The tokens given to the players of the games being played.
+#[derive(Default)]
+pub struct Tokens {
+    grants: HashMap<Token, Grant>,
+}
+
+impl Tokens {
+    pub fn new() -> Self {
+        Tokens::default()
+    }
+
+    /// Gives a new token to the player of `role` in `session`, with the
+    /// capabilities of the role.
+    pub fn issue(&mut self, session: SessionId, role: Role) -> Token {
+        let grant = Grant {
+            session,
+            role,
+            capabilities: Capability::of(role).to_vec(),
+        };
+        self.issue_grant(grant)
+    }
+
+    pub fn issue_grant(&mut self, grant: Grant) -> Token {
+        let token = Token::random();
+        self.grants.insert(token, grant);
+        token
+    }
+
+    /// Checks that `token` lets its player do `capability` in `session`,
+    /// and returns the role of the player.
+    pub fn authorize(
+        &self,
+        token: Token,
+        session: SessionId,
+        capability: Capability,
+    ) -> Result<Role, AuthError> {
+        let grant = self.grants.get(&token).ok_or(AuthError::UnknownToken)?;
+        if grant.session != session {
+            return Err(AuthError::WrongSession(session));
+        }
+        if !grant.capabilities.contains(&capability) {
+            return Err(AuthError::Forbidden(capability));
+        }
+        Ok(grant.role)
+    }
+
+    /// Forgets the tokens of a game once it is over.
+    pub fn revoke(&mut self, session: SessionId) {
+        self.grants.retain(|_, grant| grant.session != session);
+    }
+}
+
+#[cfg(test)]
+mod test_auth {
+    use super::*;
+
+    #[test]
+    fn authorizes() {
+        let mut tokens = Tokens::new();
+        let maker = tokens.issue(SessionId(1), Role::Maker);
+        let breaker = tokens.issue(SessionId(1), Role::Breaker);
+        let other = tokens.issue(SessionId(2), Role::Breaker);
+        assert_eq!(maker.to_string().parse(), Ok(maker));
+        assert!("xyz".parse::<Token>().is_err());
+
+        let resign = |token| tokens.authorize(token, SessionId(1), Capability::Resign);
+        assert_eq!(resign(breaker), Ok(Role::Breaker));
+        assert_eq!(resign(maker), Err(AuthError::Forbidden(Capability::Resign)));
+        assert_eq!(resign(other), Err(AuthError::WrongSession(SessionId(1))));
+        assert_eq!(
+            tokens.authorize(maker, SessionId(1), Capability::Observe),
+            Ok(Role::Maker)
+        );
+
+        tokens.revoke(SessionId(1));
+        let observe = tokens.authorize(breaker, SessionId(1), Capability::Observe);
+        assert_eq!(observe, Err(AuthError::UnknownToken));
+        assert!(tokens
+            .authorize(other, SessionId(2), Capability::Play)
+            .is_ok());
+    }
+}
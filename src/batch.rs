@@ -0,0 +1,244 @@
+//! Codes laid out to score a guess against many of them at once, for the
+//! solver to weigh many guesses (see `solver::Backend`).
+//!
+//! A `CodeBatch` keeps a byte per code for each peg position and for each
+//! color count, so that a guess is scored against `LANES` codes with a few
+//! vector operations: the matches are the positions where the bytes are
+//! equal, and the pegs in common are the sums over the colors of the smaller
+//! counts. With the `simd` feature, x86-64 processors use SSE2 or AVX2
+//! instructions, and other processors the plain loop.
+
+use crate::{Code, CodePeg, Score, SIZE};
+
+const COLORS: usize = CodePeg::ALL.len();
+
+/// The number of codes scored at once.
+const LANES: usize = 32;
+
+pub struct CodeBatch {
+    len: usize,
+    /// `pegs[i][j]` is the color of the `i`-th peg of the `j`-th code.
+    pegs: [Vec<u8>; SIZE],
+    /// `colors[c][j]` is the number of pegs of color `c` in the `j`-th code.
+    colors: [Vec<u8>; COLORS],
+}
+
+impl CodeBatch {
+    pub fn new(codes: impl IntoIterator<Item = Code>) -> Self {
+        let mut batch = CodeBatch {
+            len: 0,
+            pegs: Default::default(),
+            colors: Default::default(),
+        };
+        for code in codes {
+            let code = Pegs::new(code);
+            for (lane, peg) in batch.pegs.iter_mut().zip(code.pegs) {
+                lane.push(peg);
+            }
+            for (lane, count) in batch.colors.iter_mut().zip(code.colors) {
+                lane.push(count);
+            }
+            batch.len += 1;
+        }
+        // Whole chunks of codes are scored, so the lanes are padded.
+        let padded = batch.len.next_multiple_of(LANES);
+        for lane in batch.pegs.iter_mut().chain(&mut batch.colors) {
+            lane.resize(padded, 0);
+        }
+        batch
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The score of `guess` for each code, in order, like `Scorer::score`.
+    pub fn scores(&self, guess: Code) -> impl Iterator<Item = Score> + '_ {
+        self.score_indices(guess).map(Score::from_index)
+    }
+
+    /// The index of the score of `guess` for each code, as `Score::index`
+    /// gives.
+    pub(crate) fn score_indices(&self, guess: Code) -> impl Iterator<Item = usize> + '_ {
+        self.score_indices_with(guess, kernel())
+    }
+
+    fn score_indices_with(&self, guess: Code, kernel: Kernel) -> impl Iterator<Item = usize> + '_ {
+        let guess = Pegs::new(guess);
+        (0..self.len)
+            .step_by(LANES)
+            .flat_map(move |start| kernel(self, &guess, start))
+            .take(self.len)
+            .map(usize::from)
+    }
+}
+
+/// A code's pegs and color counts, as laid out in a batch.
+struct Pegs {
+    pegs: [u8; SIZE],
+    colors: [u8; COLORS],
+}
+
+impl Pegs {
+    fn new(code: Code) -> Self {
+        let mut pegs = Pegs {
+            pegs: [0; SIZE],
+            colors: [0; COLORS],
+        };
+        for (i, peg) in code.pegs().into_iter().enumerate() {
+            pegs.pegs[i] = peg as u8;
+            pegs.colors[peg as usize] += 1;
+        }
+        pegs
+    }
+}
+
+/// Scores a guess against the `LANES` codes from `start`, giving the index
+/// of each score: `matches * (SIZE + 1) + presents`, which is also
+/// `matches * SIZE + common`.
+type Kernel = fn(&CodeBatch, &Pegs, usize) -> [u8; LANES];
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+fn kernel() -> Kernel {
+    if is_x86_feature_detected!("avx2") {
+        x86::avx2
+    } else {
+        x86::sse2
+    }
+}
+
+#[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+fn kernel() -> Kernel {
+    scalar
+}
+
+#[cfg_attr(all(feature = "simd", target_arch = "x86_64"), allow(dead_code))]
+fn scalar(batch: &CodeBatch, guess: &Pegs, start: usize) -> [u8; LANES] {
+    let mut indices = [0; LANES];
+    for (j, index) in indices.iter_mut().enumerate() {
+        let code = start + j;
+        let matches = batch
+            .pegs
+            .iter()
+            .zip(guess.pegs)
+            .filter(|(lane, peg)| lane[code] == *peg)
+            .count() as u8;
+        let common: u8 = batch
+            .colors
+            .iter()
+            .zip(guess.colors)
+            .map(|(lane, count)| lane[code].min(count))
+            .sum();
+        *index = matches * SIZE as u8 + common;
+    }
+    indices
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    use super::{CodeBatch, Pegs, LANES, SIZE};
+
+    pub fn sse2(batch: &CodeBatch, guess: &Pegs, start: usize) -> [u8; LANES] {
+        let mut indices = [0; LANES];
+        for half in (0..LANES).step_by(16) {
+            let range = start + half..start + half + 16;
+            // SAFETY: x86-64 processors all have SSE2, and the slices have
+            // the 16 bytes read or written.
+            unsafe {
+                let mut matches = _mm_setzero_si128();
+                for (lane, &peg) in batch.pegs.iter().zip(&guess.pegs) {
+                    let pegs = _mm_loadu_si128(lane[range.clone()].as_ptr().cast());
+                    // Equal bytes are set to -1.
+                    let equal = _mm_cmpeq_epi8(pegs, _mm_set1_epi8(peg as i8));
+                    matches = _mm_sub_epi8(matches, equal);
+                }
+                let mut common = _mm_setzero_si128();
+                for (lane, &count) in batch.colors.iter().zip(&guess.colors) {
+                    if count > 0 {
+                        let counts = _mm_loadu_si128(lane[range.clone()].as_ptr().cast());
+                        let counts = _mm_min_epu8(counts, _mm_set1_epi8(count as i8));
+                        common = _mm_add_epi8(common, counts);
+                    }
+                }
+                for _ in 0..SIZE {
+                    common = _mm_add_epi8(common, matches);
+                }
+                _mm_storeu_si128(indices[half..half + 16].as_mut_ptr().cast(), common);
+            }
+        }
+        indices
+    }
+
+    pub fn avx2(batch: &CodeBatch, guess: &Pegs, start: usize) -> [u8; LANES] {
+        // SAFETY: only used once AVX2 has been detected.
+        unsafe { avx2_unchecked(batch, guess, start) }
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn avx2_unchecked(batch: &CodeBatch, guess: &Pegs, start: usize) -> [u8; LANES] {
+        let range = start..start + LANES;
+        let mut matches = _mm256_setzero_si256();
+        for (lane, &peg) in batch.pegs.iter().zip(&guess.pegs) {
+            let pegs = _mm256_loadu_si256(lane[range.clone()].as_ptr().cast());
+            let equal = _mm256_cmpeq_epi8(pegs, _mm256_set1_epi8(peg as i8));
+            matches = _mm256_sub_epi8(matches, equal);
+        }
+        let mut common = _mm256_setzero_si256();
+        for (lane, &count) in batch.colors.iter().zip(&guess.colors) {
+            if count > 0 {
+                let counts = _mm256_loadu_si256(lane[range.clone()].as_ptr().cast());
+                let counts = _mm256_min_epu8(counts, _mm256_set1_epi8(count as i8));
+                common = _mm256_add_epi8(common, counts);
+            }
+        }
+        for _ in 0..SIZE {
+            common = _mm256_add_epi8(common, matches);
+        }
+        let mut indices = [0; LANES];
+        _mm256_storeu_si256(indices.as_mut_ptr().cast(), common);
+        indices
+    }
+}
+
+#[cfg(test)]
+mod test_batch {
+    use super::*;
+    use crate::Scorer;
+
+    #[test]
+    fn scores_like_the_scorer() {
+        let codes = Code::all();
+        let batch = CodeBatch::new(codes.iter().copied());
+        assert_eq!(batch.len(), codes.len());
+        #[allow(unused_mut)]
+        let mut kernels: Vec<Kernel> = vec![scalar, kernel()];
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        kernels.push(x86::sse2);
+        for &guess in codes.iter().step_by(7) {
+            let scores: Vec<Score> = codes
+                .iter()
+                .map(|&code| Scorer::new(code).score(guess))
+                .collect();
+            assert_eq!(batch.scores(guess).collect::<Vec<_>>(), scores);
+            let indices: Vec<usize> = scores.into_iter().map(|score| score.index()).collect();
+            for &kernel in &kernels {
+                let by_kernel: Vec<usize> = batch.score_indices_with(guess, kernel).collect();
+                assert_eq!(by_kernel, indices, "guess {}", guess);
+            }
+        }
+
+        // Fewer codes than lanes.
+        let codes = &codes[..3];
+        let batch = CodeBatch::new(codes.iter().copied());
+        let guess = codes[1];
+        let scores: Vec<Score> = batch.scores(guess).collect();
+        assert_eq!(scores.len(), 3);
+        assert_eq!(scores[1], Scorer::new(guess).score(guess));
+    }
+}
@@ -0,0 +1,39 @@
+//! Serves the gRPC service of `mastermind::grpc`.
+
+use std::net::SocketAddr;
+use std::process;
+
+use clap::Parser;
+use mastermind::ratelimit::{Limits, Quota};
+use tonic::transport::Server;
+
+#[derive(Parser)]
+#[command(about = "Serve mastermind games over gRPC")]
+struct Cli {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:50051")]
+    listen: SocketAddr,
+
+    /// Games each client may create per minute, unlimited by default
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    games_per_minute: Option<u32>,
+
+    /// Guesses each game may receive per minute, unlimited by default
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    guesses_per_minute: Option<u32>,
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let limits = Limits {
+        games: cli.games_per_minute.map(Quota::per_minute),
+        guesses: cli.guesses_per_minute.map(Quota::per_minute),
+    };
+    eprintln!("listening on {}", cli.listen);
+    let server = Server::builder().add_service(mastermind::grpc::limited_server(limits));
+    if let Err(err) = server.serve(cli.listen).await {
+        eprintln!("error: {}: {}", cli.listen, err);
+        process::exit(1);
+    }
+}
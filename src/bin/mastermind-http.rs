@@ -0,0 +1,53 @@
+//! Serves the REST API of `mastermind::http`.
+
+use std::net::SocketAddr;
+use std::process;
+use std::time::Duration;
+
+use clap::Parser;
+use mastermind::ratelimit::{Limits, Quota};
+use tokio::net::TcpListener;
+
+#[derive(Parser)]
+#[command(about = "Serve mastermind games over a REST API")]
+struct Cli {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: String,
+
+    /// Minutes after which unplayed games are dropped
+    #[arg(long, value_name = "MINUTES")]
+    idle_timeout: Option<u64>,
+
+    /// Games each client may create per minute, unlimited by default
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    games_per_minute: Option<u32>,
+
+    /// Guesses each game may receive per minute, unlimited by default
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    guesses_per_minute: Option<u32>,
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let listener = TcpListener::bind(&cli.listen).await.unwrap_or_else(|err| {
+        eprintln!("error: {}: {}", cli.listen, err);
+        process::exit(1);
+    });
+    eprintln!("listening on {}", cli.listen);
+    let limits = Limits {
+        games: cli.games_per_minute.map(Quota::per_minute),
+        guesses: cli.guesses_per_minute.map(Quota::per_minute),
+    };
+    let idle_timeout = cli
+        .idle_timeout
+        .map(|minutes| Duration::from_secs(minutes * 60));
+    let router = mastermind::http::limited_router(limits, idle_timeout);
+    // The address of each client tells them apart for the limits.
+    let service = router.into_make_service_with_connect_info::<SocketAddr>();
+    if let Err(err) = axum::serve(listener, service).await {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,218 @@
+//! The health check and the admin API of the server, with the `http`
+//! feature, for operators to manage it while it runs:
+//!
+//! | Request                        | Response                          |
+//! |--------------------------------|-----------------------------------|
+//! | `GET /health`                  | the status of the server          |
+//! | `GET /admin/games`             | the games being played            |
+//! | `GET /admin/games/{id}`        | a game, with its rounds so far    |
+//! | `DELETE /admin/games/{id}`     | aborts the game                   |
+//!
+//! The admin routes need an `Authorization: Bearer <token>` header. When
+//! games are recorded, the replays are served too (see `replays`), only to
+//! operators on private servers.
+
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::extract::{Path, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use mastermind::lobby::SessionId;
+use mastermind::spectate::Event;
+use serde::Serialize;
+
+use crate::{Games, Hosted};
+
+#[derive(Clone)]
+struct Admin {
+    games: Games,
+    token: Arc<str>,
+    started: Instant,
+}
+
+#[derive(Serialize)]
+struct Health {
+    status: &'static str,
+    uptime: u64,
+    games: usize,
+}
+
+#[derive(Serialize)]
+struct RoundView {
+    guess: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matched: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    present: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct GameView {
+    id: u64,
+    maker: String,
+    breaker: String,
+    max_rounds: usize,
+    colors: usize,
+    duplicates: bool,
+    started_at: u64,
+    spectators: usize,
+    /// Only when a single game is asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rounds: Option<Vec<RoundView>>,
+}
+
+impl GameView {
+    fn new(SessionId(id): SessionId, hosted: &Hosted) -> Self {
+        GameView {
+            id,
+            maker: hosted.maker.clone(),
+            breaker: hosted.breaker.clone(),
+            max_rounds: hosted.setup.max_round.get(),
+            colors: hosted.setup.rules.colors(),
+            duplicates: hosted.setup.rules.duplicates(),
+            started_at: hosted.started_at,
+            spectators: hosted.broadcast.spectators(),
+            rounds: None,
+        }
+    }
+
+    /// The guesses and scores broadcast so far, the last guess being
+    /// unscored while the code maker's answer is awaited.
+    fn with_rounds(mut self, events: Vec<Event>) -> Self {
+        let mut rounds: Vec<RoundView> = Vec::new();
+        for event in events {
+            match event {
+                Event::Guess(guess) => rounds.push(RoundView {
+                    guess: guess.to_string(),
+                    matched: None,
+                    present: None,
+                }),
+                Event::Score(score) => {
+                    if let Some(round) = rounds.last_mut() {
+                        round.matched = Some(score.matches());
+                        round.present = Some(score.presents());
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.rounds = Some(rounds);
+        self
+    }
+}
+
+fn not_found(SessionId(id): SessionId) -> Response {
+    (StatusCode::NOT_FOUND, format!("no game {}\n", id)).into_response()
+}
+
+async fn authorize(State(admin): State<Admin>, request: Request, next: Next) -> Response {
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if !bearer.is_some_and(|bearer| is_token(bearer.as_bytes(), admin.token.as_bytes())) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    next.run(request).await
+}
+
+/// Whether `given` is `token`, comparing every byte whatever the first one
+/// to differ, so that the time taken does not tell how much of a guess is
+/// right.
+fn is_token(given: &[u8], token: &[u8]) -> bool {
+    given.len() == token.len()
+        && given
+            .iter()
+            .zip(token)
+            .fold(0, |diff, (given, token)| diff | (given ^ token))
+            == 0
+}
+
+async fn health(State(admin): State<Admin>) -> Json<Health> {
+    Json(Health {
+        status: "ok",
+        uptime: admin.started.elapsed().as_secs(),
+        games: admin.games.hosted.lock().unwrap().len(),
+    })
+}
+
+async fn list_games(State(admin): State<Admin>) -> Json<Vec<GameView>> {
+    let hosted = admin.games.hosted.lock().unwrap();
+    let mut games: Vec<GameView> = hosted
+        .iter()
+        .map(|(&session, hosted)| GameView::new(session, hosted))
+        .collect();
+    games.sort_by_key(|game| game.id);
+    Json(games)
+}
+
+async fn get_game(State(admin): State<Admin>, Path(id): Path<u64>) -> Response {
+    let hosted = admin.games.hosted.lock().unwrap();
+    match hosted.get(&SessionId(id)) {
+        Some(game) => {
+            let view = GameView::new(SessionId(id), game).with_rounds(game.broadcast.events());
+            Json(view).into_response()
+        }
+        None => not_found(SessionId(id)),
+    }
+}
+
+async fn end_game(State(admin): State<Admin>, Path(id): Path<u64>) -> Response {
+    if admin.games.abort(SessionId(id)) {
+        eprintln!("game {} ended by an admin", id);
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        not_found(SessionId(id))
+    }
+}
+
+fn router(admin: Admin) -> Router {
+    let authorized = middleware::from_fn_with_state(admin.clone(), authorize);
+    #[cfg_attr(not(feature = "store"), allow(unused_mut))]
+    let mut games = Router::new()
+        .route("/admin/games", get(list_games))
+        .route("/admin/games/{id}", get(get_game).delete(end_game));
+    #[cfg_attr(not(feature = "store"), allow(unused_mut))]
+    let mut public = Router::new();
+    #[cfg(feature = "store")]
+    if let Some(store) = &admin.games.store {
+        games = games.merge(crate::replays::uploads(store.clone()));
+        let replays = crate::replays::router(store.clone());
+        if admin.games.private {
+            games = games.merge(replays);
+        } else {
+            public = public.merge(replays);
+        }
+    }
+    Router::new()
+        .route("/health", get(health))
+        .merge(games.route_layer(authorized))
+        .merge(public)
+        .with_state(admin)
+}
+
+/// Serves the API on `listener` until the server stops.
+pub fn serve(listener: TcpListener, token: String, games: Games) {
+    let admin = Admin {
+        games,
+        token: token.into(),
+        started: Instant::now(),
+    };
+    let runtime = tokio::runtime::Runtime::new().expect("the runtime starts");
+    runtime.block_on(async {
+        let result = async {
+            listener.set_nonblocking(true)?;
+            let listener = tokio::net::TcpListener::from_std(listener)?;
+            axum::serve(listener, router(admin)).await
+        };
+        if let Err(err) = result.await {
+            eprintln!("error: admin API: {}", err);
+        }
+    });
+}
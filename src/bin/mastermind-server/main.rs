@@ -0,0 +1,644 @@
+//! Hosts games between a code maker and a code breaker playing from other
+//! machines, with `mastermind connect`, or from browsers over WebSocket.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Shutdown, TcpListener, TcpStream};
+use std::num::NonZeroUsize;
+#[cfg(feature = "store")]
+use std::path::PathBuf;
+use std::process;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use clap::Parser;
+use mastermind::auth::{Capability, Token, Tokens};
+use mastermind::contain::Contained;
+use mastermind::lobby::{Lobby, Pairing, SessionId, Setup};
+use mastermind::net::{
+    self, Connection, Error, Feature, Message, Outcome, Rejoin, RemoteCodeBreaker, RemoteCodeMaker,
+    Resumption, Role,
+};
+use mastermind::ratelimit::{Quota, RateLimiter};
+use mastermind::rules::Rules;
+use mastermind::spectate::{Broadcast, Broadcasting, Event};
+#[cfg(feature = "store")]
+use mastermind::store::{self, GameRecord, Store};
+use mastermind::{Code, Game, Score};
+
+#[cfg(feature = "http")]
+mod admin;
+#[cfg(all(feature = "http", feature = "store"))]
+mod replays;
+
+#[derive(Parser)]
+#[command(about = "Host mastermind games played over the network")]
+struct Cli {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:4040")]
+    listen: String,
+
+    /// Address to also accept WebSocket players on, e.g. from browsers
+    #[cfg(feature = "ws")]
+    #[arg(long)]
+    websocket: Option<String>,
+
+    /// Number of guesses allowed to break the code, for players who do not
+    /// ask for other rules
+    #[arg(long, default_value_t = NonZeroUsize::new(10).unwrap())]
+    rounds: NonZeroUsize,
+
+    /// Most colors players may ask for, which is also the number of colors
+    /// of players who do not ask for other rules
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u8).range(1..=6))]
+    max_colors: u8,
+
+    /// Only host games whose codes may repeat colors
+    #[arg(long)]
+    no_unique: bool,
+
+    /// Games each address may join, watch or resume per minute, unlimited
+    /// by default
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    games_per_minute: Option<u32>,
+
+    /// Messages each code breaker may send per minute, unlimited by default
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    guesses_per_minute: Option<u32>,
+
+    /// Seconds a player may take to answer before the game is aborted
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    timeout: Option<u64>,
+
+    /// Seconds a player who lost their connection has to resume the game
+    /// before it is aborted
+    #[arg(long, default_value_t = 30)]
+    grace: u64,
+
+    /// Only let the players of a game watch it, with their token
+    #[arg(long)]
+    private: bool,
+
+    /// File to record the games and ratings of the players in, who are
+    /// known by their IP address: a SQLite database with the sqlite feature
+    /// if its extension is .db, or else a JSON file
+    #[cfg(feature = "store")]
+    #[arg(long)]
+    db: Option<PathBuf>,
+
+    /// Address to serve the health check, the admin API and, with --db, the
+    /// replays of the recorded games on
+    #[cfg(feature = "http")]
+    #[arg(long)]
+    admin: Option<String>,
+
+    /// Bearer token of the admin API, or else a random one, printed when
+    /// the server starts
+    #[cfg(feature = "http")]
+    #[arg(long, requires = "admin")]
+    admin_token: Option<String>,
+}
+
+/// A player who joined, and the game they asked for.
+struct Joined {
+    name: String,
+    role: Role,
+    setup: Option<Setup>,
+    connection: Connection,
+    /// The socket of the connection, to close it if the game is ended.
+    stream: TcpStream,
+}
+
+/// A game being played, described by the admin API.
+#[cfg_attr(not(feature = "http"), allow(dead_code))]
+struct Hosted {
+    maker: String,
+    breaker: String,
+    setup: Setup,
+    /// Seconds since the Unix epoch.
+    started_at: u64,
+    broadcast: Broadcast,
+    /// The sockets of the maker and the breaker.
+    streams: [TcpStream; 2],
+    /// Where the maker and the breaker resume the game.
+    rejoins: [Sender<Connection>; 2],
+}
+
+/// Where the connection of the player of `role` is kept, if the game has
+/// one for it.
+fn index(role: Role) -> Option<usize> {
+    match role {
+        Role::Maker => Some(0),
+        Role::Breaker => Some(1),
+        _ => None,
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+/// The games being played, for spectators to watch, players to resume and
+/// operators to manage, the tokens of their players, and where finished
+/// games are recorded.
+#[derive(Clone, Default)]
+struct Games {
+    hosted: Arc<Mutex<HashMap<SessionId, Hosted>>>,
+    tokens: Arc<Mutex<Tokens>>,
+    private: bool,
+    /// The rules the games are hosted with, and what players may ask for.
+    features: Vec<Feature>,
+    /// The time players have for each move, if limited.
+    timeout: Option<Duration>,
+    grace: Duration,
+    /// How often each address may connect, if limited.
+    connections: Option<Arc<RateLimiter<IpAddr>>>,
+    /// How often code breakers may guess, if limited.
+    guesses: Option<Quota>,
+    #[cfg(feature = "store")]
+    store: Option<Arc<Mutex<Box<dyn Store>>>>,
+}
+
+impl Games {
+    /// Starts broadcasting `session`, and returns the tokens of its code
+    /// maker and code breaker.
+    fn start(&self, session: SessionId, hosted: Hosted) -> (Broadcast, Token, Token) {
+        let broadcast = hosted.broadcast.clone();
+        self.hosted.lock().unwrap().insert(session, hosted);
+        let mut tokens = self.tokens.lock().unwrap();
+        let maker = tokens.issue(session, Role::Maker);
+        let breaker = tokens.issue(session, Role::Breaker);
+        (broadcast, maker, breaker)
+    }
+
+    fn end(&self, session: SessionId) {
+        if let Ok(mut hosted) = self.hosted.lock() {
+            hosted.remove(&session);
+        }
+        if let Ok(mut tokens) = self.tokens.lock() {
+            tokens.revoke(session);
+        }
+    }
+
+    /// The recorded rating of a player, if any.
+    #[cfg_attr(not(feature = "store"), allow(unused_variables))]
+    fn rating(&self, name: &str) -> Option<u32> {
+        #[cfg(feature = "store")]
+        if let Some(store) = &self.store {
+            match store.lock().unwrap().stats(name) {
+                Ok(stats) => return stats.map(|stats| stats.rating),
+                Err(err) => eprintln!("error: {}", err),
+            }
+        }
+        None
+    }
+
+    /// Records `session` with its transcript once it is over, unless both
+    /// players came from the same address.
+    #[cfg_attr(not(feature = "store"), allow(unused_variables))]
+    fn record(&self, session: SessionId, outcome: Outcome) {
+        #[cfg(feature = "store")]
+        if let Some(store) = &self.store {
+            let game = match self.hosted.lock().unwrap().get(&session) {
+                Some(hosted) if hosted.maker != hosted.breaker => GameRecord {
+                    id: 0,
+                    maker: hosted.maker.clone(),
+                    breaker: hosted.breaker.clone(),
+                    max_round: hosted.setup.max_round.get(),
+                    won_in: match outcome {
+                        Outcome::Won(rounds) => Some(rounds),
+                        _ => None,
+                    },
+                    finished_at: now(),
+                    transcript: store::transcript(&rounds(hosted.broadcast.events())),
+                },
+                _ => return,
+            };
+            match store.lock().unwrap().record(&game) {
+                Ok(id) => eprintln!("game {} recorded as replay {}", session, id),
+                Err(err) => eprintln!("error: {}", err),
+            }
+        }
+    }
+
+    /// The broadcast of `session`, if `token` lets the spectator watch it.
+    fn watch(&self, session: SessionId, token: Option<Token>) -> Result<Broadcast, String> {
+        if self.private {
+            let token = token.ok_or("the game is private")?;
+            self.tokens
+                .lock()
+                .unwrap()
+                .authorize(token, session, Capability::Observe)
+                .map_err(|err| err.to_string())?;
+        }
+        let hosted = self.hosted.lock().unwrap();
+        hosted
+            .get(&session)
+            .map(|hosted| hosted.broadcast.clone())
+            .ok_or_else(|| format!("no game {}", session))
+    }
+
+    /// Where the player with `token` resumes `session` on the connection of
+    /// `stream`, which replaces their previous one, closed in case the
+    /// server did not notice it was lost.
+    fn rejoin(
+        &self,
+        session: SessionId,
+        token: Token,
+        stream: TcpStream,
+    ) -> Result<Sender<Connection>, String> {
+        let role = self
+            .tokens
+            .lock()
+            .unwrap()
+            .authorize(token, session, Capability::Play)
+            .map_err(|err| err.to_string())?;
+        let mut hosted = self.hosted.lock().unwrap();
+        let hosted = hosted
+            .get_mut(&session)
+            .ok_or_else(|| format!("no game {}", session))?;
+        let index = index(role).ok_or_else(|| format!("no player {:?} in a game", role))?;
+        let previous = std::mem::replace(&mut hosted.streams[index], stream);
+        let _ = previous.shutdown(Shutdown::Both);
+        Ok(hosted.rejoins[index].clone())
+    }
+
+    /// How the player of `role` resumes `session`, told the commitment to
+    /// the code once it is set in `digest`.
+    fn resumable(
+        &self,
+        role: Role,
+        max_round: NonZeroUsize,
+        broadcast: &Broadcast,
+        digest: &Arc<OnceLock<String>>,
+        connections: Receiver<Connection>,
+    ) -> Rejoin {
+        let move_time = self.timeout;
+        let broadcast = broadcast.clone();
+        let digest = digest.clone();
+        Rejoin::new(connections, self.grace, move || Resumption {
+            role,
+            max_round,
+            move_time,
+            digest: digest.get().cloned(),
+            rounds: rounds(broadcast.events()),
+        })
+    }
+
+    /// Closes the connections of the players of `session`, which aborts the
+    /// game. Returns whether it was being played.
+    #[cfg_attr(not(feature = "http"), allow(dead_code))]
+    fn abort(&self, session: SessionId) -> bool {
+        let hosted = self.hosted.lock().unwrap();
+        let Some(hosted) = hosted.get(&session) else {
+            return false;
+        };
+        for stream in &hosted.streams {
+            // Already closed if the player left.
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        true
+    }
+}
+
+/// The scored rounds of a game, from its events.
+fn rounds(events: Vec<Event>) -> Vec<(Code, Score)> {
+    let mut rounds = Vec::new();
+    let mut guess = None;
+    for event in events {
+        match event {
+            Event::Guess(code) => guess = Some(code),
+            Event::Score(score) => rounds.extend(guess.take().map(|code| (code, score))),
+            _ => {}
+        }
+    }
+    rounds
+}
+
+/// Plays a game and tells both players and its spectators how it ended.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(%session)))]
+fn host(
+    session: SessionId,
+    hosted: Hosted,
+    mut maker: Connection,
+    mut breaker: Connection,
+    rejoined: [Receiver<Connection>; 2],
+    games: &Games,
+) -> Result<Outcome, Error> {
+    let Setup { max_round, rules } = hosted.setup;
+    let (broadcast, maker_token, breaker_token) = games.start(session, hosted);
+    maker.send(&Message::Start(max_round, session, maker_token))?;
+    breaker.send(&Message::Start(max_round, session, breaker_token))?;
+
+    let digest = Arc::new(OnceLock::new());
+    let [maker_rejoined, breaker_rejoined] = rejoined;
+    let rejoin = games.resumable(Role::Maker, max_round, &broadcast, &digest, maker_rejoined);
+    let code_maker = RemoteCodeMaker::new(maker).with_rejoin(rejoin);
+    let commitment = code_maker.commit()?;
+    let _ = digest.set(commitment.digest());
+    if let Err(err) = rules.check(commitment.code()) {
+        let mut maker = code_maker.into_connection();
+        maker.send(&Message::Error(err.to_string()))?;
+        return Err(Error::Protocol(err.to_string()));
+    }
+    breaker.send(&Message::Commit(commitment.digest()))?;
+    let rejoin = games.resumable(
+        Role::Breaker,
+        max_round,
+        &broadcast,
+        &digest,
+        breaker_rejoined,
+    );
+    let mut code_breaker = RemoteCodeBreaker::new(breaker)
+        .with_tokens(games.tokens.clone(), session)
+        .with_rejoin(rejoin);
+    if let Some(quota) = games.guesses {
+        code_breaker = code_breaker.with_rate_limit(quota);
+    }
+    // A code breaker whose connection fails for good forfeits.
+    let mut code_breaker =
+        Contained::new(Broadcasting::new(code_breaker, broadcast.clone()), rules);
+    Game::new(max_round, &code_maker, &mut code_breaker)
+        .with_rules(rules)
+        .play();
+    if code_breaker.panicked() {
+        eprintln!("game {}: the code breaker forfeited", session);
+    }
+    let mut code_breaker = code_breaker.into_inner().into_inner();
+    if let Some(err) = code_breaker.take_error() {
+        eprintln!("game {}: the code breaker forfeited: {}", session, err);
+    }
+    let outcome = match code_breaker.won_in() {
+        Some(rounds) => Outcome::Won(rounds),
+        None => Outcome::Lost(commitment.code()),
+    };
+    broadcast.publish(Event::Over(outcome));
+
+    code_maker
+        .into_connection()
+        .send(&Message::Result(outcome))?;
+    let mut breaker = code_breaker.into_connection();
+    breaker.send(&Message::Result(outcome))?;
+    breaker.send(&Message::Reveal(commitment.code(), commitment.salt()))?;
+    Ok(outcome)
+}
+
+/// Pairs the players through a lobby, with `default` as the setup of
+/// players who did not ask for one, and starts a game for each pair.
+fn match_players(default: Setup, players: Receiver<Joined>, games: Games) {
+    let mut lobby = Lobby::new();
+    for joined in players {
+        let player = lobby.register(&joined.name, games.rating(&joined.name));
+        let setup = joined.setup.unwrap_or(default);
+        let handle = (player, joined.connection, joined.stream);
+        let pairing = match lobby.request(player, joined.role, setup, handle) {
+            Ok(Some(pairing)) => pairing,
+            Ok(None) => continue,
+            Err(err) => unreachable!("players join once: {}", err),
+        };
+
+        let Pairing {
+            session,
+            setup,
+            maker: (maker, maker_connection, maker_stream),
+            breaker: (breaker, breaker_connection, breaker_stream),
+        } = pairing;
+        let maker_name = lobby.name(maker).unwrap_or_default().to_string();
+        let breaker_name = lobby.name(breaker).unwrap_or_default().to_string();
+        eprintln!(
+            "game {} started between {} and {}",
+            session, maker_name, breaker_name
+        );
+        // Each connection plays a single game.
+        lobby.unregister(maker);
+        lobby.unregister(breaker);
+        let (maker_rejoin, maker_rejoined) = mpsc::channel();
+        let (breaker_rejoin, breaker_rejoined) = mpsc::channel();
+        let hosted = Hosted {
+            maker: maker_name.clone(),
+            breaker: breaker_name.clone(),
+            setup,
+            started_at: now(),
+            broadcast: Broadcast::new(),
+            streams: [maker_stream, breaker_stream],
+            rejoins: [maker_rejoin, breaker_rejoin],
+        };
+        let games = games.clone();
+        thread::spawn(move || {
+            // Ending the game even if the host panics drops its broadcast,
+            // which ends the spectators' channels, and revokes its tokens.
+            struct Ended(Games, SessionId);
+            impl Drop for Ended {
+                fn drop(&mut self) {
+                    self.0.end(self.1);
+                }
+            }
+            let ended = Ended(games, session);
+            let outcome = match host(
+                session,
+                hosted,
+                maker_connection,
+                breaker_connection,
+                [maker_rejoined, breaker_rejoined],
+                &ended.0,
+            ) {
+                Ok(outcome) => outcome,
+                Err(err) => return eprintln!("game {} aborted: {}", session, err),
+            };
+            match outcome {
+                Outcome::Won(rounds) => eprintln!("game {} won in {} rounds", session, rounds),
+                Outcome::Lost(code) => eprintln!("game {} lost, the code was {}", session, code),
+                _ => eprintln!("game {} over", session),
+            }
+            ended.0.record(session, outcome);
+        });
+    }
+}
+
+/// Sends the events of a game to a spectator until it ends.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+fn spectate(mut connection: Connection, broadcast: Broadcast) -> Result<(), Error> {
+    let events = broadcast.subscribe();
+    // Not to keep the channel open once the game ends.
+    drop(broadcast);
+    for event in events {
+        connection.send(&event.into())?;
+    }
+    Ok(())
+}
+
+/// Accepts the players connecting to `listener`, with `accept` completing
+/// the connection, and sends them to the matchmaker once they joined.
+/// Spectators watch their game from the connection's thread.
+fn listen(
+    listener: TcpListener,
+    accept: fn(TcpStream) -> Result<Connection, Error>,
+    timeout: Option<Duration>,
+    players: Sender<Joined>,
+    games: Games,
+) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                continue;
+            }
+        };
+        let players = players.clone();
+        let games = games.clone();
+        // Wait for the player to join without blocking other connections.
+        thread::spawn(move || {
+            stream.set_read_timeout(timeout)?;
+            let address = stream.peer_addr()?.ip();
+            let name = address.to_string();
+            let handle = stream.try_clone()?;
+            #[cfg(feature = "tracing")]
+            let _span = tracing::info_span!("connection", peer = %name).entered();
+            let mut connection = accept(stream)?;
+            if let Some(limiter) = &games.connections {
+                if let Err(limited) = limiter.check(address, Instant::now()) {
+                    return connection.send(&Message::Error(limited.to_string()));
+                }
+            }
+            let mut message = connection.receive()?;
+            // Clients speaking version 1 skip the handshake.
+            if let Message::Hello(version, _) = message {
+                match net::negotiate(version) {
+                    Ok(version) => {
+                        connection.send(&Message::Hello(version, games.features.clone()))?
+                    }
+                    Err(err) => return connection.send(&Message::Error(err.to_string())),
+                }
+                message = connection.receive()?;
+            }
+            match message {
+                Message::Join(role, setup) => {
+                    if let Some(Err(err)) =
+                        setup.map(|setup| net::supports(&games.features, &setup))
+                    {
+                        return connection.send(&Message::Error(err.to_string()));
+                    }
+                    let joined = Joined {
+                        name,
+                        role,
+                        setup,
+                        connection,
+                        stream: handle,
+                    };
+                    players.send(joined).expect("the matchmaker never stops");
+                }
+                Message::Watch(session, token) => match games.watch(session, token) {
+                    Ok(broadcast) => spectate(connection, broadcast)?,
+                    Err(error) => connection.send(&Message::Error(error))?,
+                },
+                Message::Resume(session, token) => match games.rejoin(session, token, handle) {
+                    Ok(rejoin) => {
+                        eprintln!("{} resumed game {}", name, session);
+                        // Lost if the game just ended, which closes the
+                        // connection.
+                        let _ = rejoin.send(connection);
+                    }
+                    Err(error) => connection.send(&Message::Error(error))?,
+                },
+                message => {
+                    let error = format!("unexpected message '{}'", message);
+                    connection.send(&Message::Error(error))?;
+                }
+            }
+            Ok::<(), Error>(())
+        });
+    }
+}
+
+fn bind(address: &str) -> TcpListener {
+    let listener = TcpListener::bind(address).unwrap_or_else(|err| {
+        eprintln!("error: {}: {}", address, err);
+        process::exit(1);
+    });
+    eprintln!("listening on {}", address);
+    listener
+}
+
+fn main() {
+    let cli = Cli::parse();
+    #[cfg(feature = "tracing")]
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
+    let listener = bind(&cli.listen);
+    let (sender, receiver) = mpsc::channel();
+    let colors = usize::from(cli.max_colors);
+    let default = Setup {
+        max_round: cli.rounds,
+        rules: Rules::new(colors, true).expect("there are enough colors to repeat"),
+    };
+    let mut features = vec![
+        Feature::Duplicates,
+        Feature::Colors(colors),
+        Feature::Watch,
+        Feature::Resume,
+    ];
+    if !cli.no_unique {
+        features.push(Feature::Unique);
+    }
+    let timeout = cli.timeout.map(Duration::from_secs);
+    let games = Games {
+        private: cli.private,
+        features,
+        timeout,
+        grace: Duration::from_secs(cli.grace),
+        connections: cli
+            .games_per_minute
+            .map(|games| Arc::new(RateLimiter::new(Quota::per_minute(games)))),
+        guesses: cli.guesses_per_minute.map(Quota::per_minute),
+        #[cfg(feature = "store")]
+        store: cli.db.as_deref().map(|path| {
+            let store = store::open(path).unwrap_or_else(|err| {
+                eprintln!("error: {}: {}", path.display(), err);
+                process::exit(1);
+            });
+            Arc::new(Mutex::new(store))
+        }),
+        ..Games::default()
+    };
+    {
+        let games = games.clone();
+        thread::spawn(move || match_players(default, receiver, games));
+    }
+
+    #[cfg(feature = "http")]
+    if let Some(address) = &cli.admin {
+        let token = cli
+            .admin_token
+            .clone()
+            .unwrap_or_else(|| Token::random().to_string());
+        if cli.admin_token.is_none() {
+            eprintln!("admin token: {}", token);
+        }
+        let listener = bind(address);
+        let games = games.clone();
+        thread::spawn(move || admin::serve(listener, token, games));
+    }
+
+    #[cfg(feature = "ws")]
+    if let Some(address) = &cli.websocket {
+        let listener = bind(address);
+        let sender = sender.clone();
+        let games = games.clone();
+        thread::spawn(move || {
+            listen(
+                listener,
+                Connection::accept_websocket,
+                timeout,
+                sender,
+                games,
+            )
+        });
+    }
+    listen(listener, Connection::new, timeout, sender, games);
+}
@@ -0,0 +1,138 @@
+//! The replays of the recorded games, with the `http` and `store` features,
+//! for the replay viewer of the command line and for web pages:
+//!
+//! | Request                            | Response                            |
+//! |------------------------------------|-------------------------------------|
+//! | `GET /replays/{id}`                | a game, with its transcript         |
+//! | `GET /replays/{id}/transcript`     | the transcript, as text             |
+//! | `GET /players/{name}/replays`      | the last games of a player          |
+//! | `POST /admin/replays`              | records a game played elsewhere     |
+//!
+//! `?limit=<n>` sets how many games of a player are listed, 20 by default.
+//! Uploaded games are kept with the others but do not count in the ratings.
+
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use mastermind::store::{self, GameRecord, Store, StoreError};
+use mastermind::{Code, Scorer};
+use serde::{Deserialize, Serialize};
+
+type Shared = Arc<Mutex<Box<dyn Store>>>;
+
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 100;
+
+#[derive(Deserialize)]
+struct Listing {
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct Upload {
+    maker: String,
+    breaker: String,
+    max_round: usize,
+    transcript: String,
+}
+
+#[derive(Serialize)]
+struct Uploaded {
+    id: u64,
+}
+
+fn not_found(id: u64) -> Response {
+    (StatusCode::NOT_FOUND, format!("no replay {}\n", id)).into_response()
+}
+
+fn failed(err: StoreError) -> Response {
+    eprintln!("error: {}", err);
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
+
+/// Answers with `respond` to the game saved with `id`, if any.
+fn with_replay(store: &Shared, id: u64, respond: impl FnOnce(GameRecord) -> Response) -> Response {
+    let game = store.lock().unwrap().load_game(id);
+    match game {
+        Ok(Some(game)) => respond(game),
+        Ok(None) => not_found(id),
+        Err(err) => failed(err),
+    }
+}
+
+async fn get_replay(State(store): State<Shared>, Path(id): Path<u64>) -> Response {
+    with_replay(&store, id, |game| Json(game).into_response())
+}
+
+async fn get_transcript(State(store): State<Shared>, Path(id): Path<u64>) -> Response {
+    with_replay(&store, id, |game| game.transcript.into_response())
+}
+
+async fn list_replays(
+    State(store): State<Shared>,
+    Path(name): Path<String>,
+    Query(listing): Query<Listing>,
+) -> Response {
+    let limit = listing.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    match store.lock().unwrap().load_history(&name, limit) {
+        Ok(games) => Json(games).into_response(),
+        Err(err) => failed(err),
+    }
+}
+
+async fn upload(State(store): State<Shared>, Json(upload): Json<Upload>) -> Response {
+    let rounds = store::parse_transcript(&upload.transcript).and_then(|rounds| {
+        if rounds.len() > upload.max_round {
+            Err(format!("more than {} rounds", upload.max_round))
+        } else if rounds.len() > 1 && store::won_in(&rounds[..rounds.len() - 1]).is_some() {
+            Err("rounds after the code was broken".to_string())
+        } else if !Code::all().into_iter().any(|code| {
+            let scorer = Scorer::new(code);
+            rounds
+                .iter()
+                .all(|&(guess, score)| scorer.score(guess) == score)
+        }) {
+            Err("no code gets these scores".to_string())
+        } else {
+            Ok(rounds)
+        }
+    });
+    let rounds = match rounds {
+        Ok(rounds) => rounds,
+        Err(err) => return (StatusCode::UNPROCESSABLE_ENTITY, err + "\n").into_response(),
+    };
+    let game = GameRecord {
+        id: 0,
+        maker: upload.maker,
+        breaker: upload.breaker,
+        max_round: upload.max_round,
+        won_in: store::won_in(&rounds),
+        finished_at: crate::now(),
+        transcript: store::transcript(&rounds),
+    };
+    let saved = store.lock().unwrap().save_game(&game);
+    match saved {
+        Ok(id) => (StatusCode::CREATED, Json(Uploaded { id })).into_response(),
+        Err(err) => failed(err),
+    }
+}
+
+/// The routes to read the replays.
+pub fn router<S: Clone + Send + Sync + 'static>(store: Shared) -> Router<S> {
+    Router::new()
+        .route("/replays/{id}", get(get_replay))
+        .route("/replays/{id}/transcript", get(get_transcript))
+        .route("/players/{name}/replays", get(list_replays))
+        .with_state(store)
+}
+
+/// The route to upload replays, for operators only.
+pub fn uploads<S: Clone + Send + Sync + 'static>(store: Shared) -> Router<S> {
+    Router::new()
+        .route("/admin/replays", post(upload))
+        .with_state(store)
+}
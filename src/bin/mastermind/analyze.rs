@@ -0,0 +1,64 @@
+use mastermind::rules::Rules;
+use mastermind::solver::{Solver, Strategy};
+use mastermind::{CodeBreaker, SIZE};
+
+use crate::messages::tr;
+use crate::transcript::Round;
+
+/// Replays a transcript and prints, for each round, how many codes were
+/// still possible before and after the score, and how the guess compares
+/// with the minimax guess.
+pub fn run(rounds: &[Round], rules: Rules) {
+    let mut solver = Solver::with_rules(rules, Strategy::Minimax);
+    for (i, (guess, score)) in rounds.iter().enumerate() {
+        let before = solver.candidates().len();
+        let consistent = solver.candidates().contains(*guess);
+        let worst_case = solver.worst_case(*guess);
+        let best = solver.guess_code();
+        let best_worst_case = solver.worst_case(best);
+        solver.record(*guess, *score);
+
+        print!(
+            "{:>2}. {} {} {}  {}",
+            i + 1,
+            guess,
+            score.matches(),
+            score.presents(),
+            tr!(
+                "analyze-candidates",
+                before = before,
+                after = solver.candidates().len()
+            )
+        );
+        if score.matches() != SIZE {
+            if worst_case <= best_worst_case {
+                print!("  {}", tr!("analyze-optimal", worst_case = worst_case));
+            } else {
+                print!(
+                    "  {}",
+                    tr!(
+                        "analyze-suboptimal",
+                        worst_case = worst_case,
+                        best = best,
+                        best_worst_case = best_worst_case
+                    )
+                );
+            }
+        }
+        if !consistent {
+            print!("  {}", tr!("analyze-inconsistent"));
+        }
+        println!();
+
+        if solver.candidates().is_empty() {
+            println!("{}", tr!("contradictory-scores"));
+            return;
+        }
+    }
+
+    let candidates = solver.candidates();
+    match candidates.only() {
+        Some(code) => println!("{}", tr!("analyze-solved", code = code)),
+        None => println!("{}", tr!("analyze-open", count = candidates.len())),
+    }
+}
@@ -0,0 +1,53 @@
+use mastermind::rules::Rules;
+use mastermind::solver::{Solver, Strategy};
+use mastermind::{CodeBreaker, SIZE};
+
+use crate::input::read_line;
+use crate::messages::tr;
+use crate::transcript::{self, Round};
+
+/// Follows a game played elsewhere, e.g. on a physical board, and suggests
+/// the next guess after each round.
+pub fn run(rules: Rules, strategy: Strategy) {
+    println!("{}", tr!("assist-intro"));
+
+    let mut rounds: Vec<Round> = Vec::new();
+    let mut solver = Solver::with_rules(rules, strategy);
+    loop {
+        let candidates = solver.candidates();
+        match candidates.only() {
+            _ if candidates.is_empty() => println!("{}", tr!("contradictory-scores")),
+            Some(code) => println!("{}", tr!("analyze-solved", code = code)),
+            None => println!(
+                "{}",
+                tr!(
+                    "hint",
+                    count = candidates.len(),
+                    guess = solver.guess_code()
+                )
+            ),
+        }
+
+        let line = read_line("> ");
+        if line.trim() == "undo" {
+            rounds.pop();
+            solver = Solver::with_rules(rules, strategy);
+            for (guess, score) in &rounds {
+                solver.record(*guess, *score);
+            }
+            continue;
+        }
+
+        match transcript::parse_round(line.trim()) {
+            Ok((guess, score)) => {
+                rounds.push((guess, score));
+                solver.record(guess, score);
+                if score.matches() == SIZE {
+                    println!("{}", tr!("assist-solved", rounds = rounds.len()));
+                    return;
+                }
+            }
+            Err(err) => println!("{}", err),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::hint::black_box;
+use std::io::{self, Write};
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+use mastermind::batch::CodeBatch;
+use mastermind::packed::PackedCode;
+use mastermind::rules::Rules;
+use mastermind::solver::{Backend, Strategy};
+use mastermind::table::TableScorer;
+use mastermind::testutil::DeterministicCodeMaker;
+use mastermind::{Code, Scorer};
+
+use crate::messages::tr;
+use crate::selfplay::strategy_name;
+use crate::simulate;
+
+// Minimax is much slower than the other strategies, so it only plays every
+// MINIMAX_STEP-th code.
+const MINIMAX_STEP: usize = 16;
+
+fn print_row(name: &str, items: usize, elapsed: Duration) {
+    println!(
+        "{:<16} {:>9} {:>9.3} {:>12.0}",
+        name,
+        items,
+        elapsed.as_secs_f64(),
+        items as f64 / elapsed.as_secs_f64()
+    );
+    io::stdout().flush().expect("failed to flush stdout");
+}
+
+/// Times the scorer on every pair of codes, then each strategy on a fixed
+/// set of codes, and prints a score which only depends on the machine.
+///
+/// The workload never changes, so that scores can be compared across
+/// machines and versions.
+pub fn run() {
+    let codes = Code::all();
+    let mut total = Duration::ZERO;
+    println!("{}", tr!("bench-header"));
+
+    let start = Instant::now();
+    for &secret in &codes {
+        let scorer = Scorer::new(secret);
+        for &guess in &codes {
+            black_box(scorer.score(black_box(guess)));
+        }
+    }
+    let elapsed = start.elapsed();
+    total += elapsed;
+    print_row("scorer", codes.len() * codes.len(), elapsed);
+
+    // The other backends are not counted in the score, which stays
+    // comparable with the versions before them.
+    let packed: Vec<PackedCode> = codes.iter().map(|&code| code.into()).collect();
+    let start = Instant::now();
+    for secret in &packed {
+        for &guess in &packed {
+            black_box(secret.score(black_box(guess)));
+        }
+    }
+    print_row(
+        "packed scorer",
+        packed.len() * packed.len(),
+        start.elapsed(),
+    );
+    let table = TableScorer::new();
+    let start = Instant::now();
+    for &secret in &codes {
+        for &guess in &codes {
+            black_box(table.score(secret, black_box(guess)));
+        }
+    }
+    print_row("table scorer", codes.len() * codes.len(), start.elapsed());
+    let batch = CodeBatch::new(codes.iter().copied());
+    let start = Instant::now();
+    for &guess in &codes {
+        for score in batch.scores(black_box(guess)) {
+            black_box(score);
+        }
+    }
+    print_row("batch scorer", codes.len() * codes.len(), start.elapsed());
+
+    for strategy in Strategy::ALL {
+        let secrets: Vec<Code> = match strategy {
+            Strategy::Minimax => codes.iter().step_by(MINIMAX_STEP).copied().collect(),
+            Strategy::FirstCandidate => codes.clone(),
+        };
+        let start = Instant::now();
+        for &secret in &secrets {
+            // The number of rounds is large enough for any strategy to win.
+            black_box(simulate::play(
+                Rules::default(),
+                strategy,
+                Backend::default(),
+                NonZeroUsize::new(codes.len()).expect("there are codes"),
+                &DeterministicCodeMaker::new(secret),
+            ));
+        }
+        let elapsed = start.elapsed();
+        total += elapsed;
+        print_row(strategy_name(strategy), secrets.len(), elapsed);
+    }
+
+    println!();
+    println!(
+        "{}",
+        tr!(
+            "bench-score",
+            score = format!("{:.0}", 1000.0 / total.as_secs_f64())
+        )
+    );
+}
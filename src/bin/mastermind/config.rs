@@ -0,0 +1,166 @@
+//! Settings read from the configuration file.
+//!
+//! The file is `$XDG_CONFIG_HOME/mastermind/config.toml`, or
+//! `~/.config/mastermind/config.toml`. Its top-level settings apply to every
+//! command, and named profiles override them:
+//!
+//! ```toml
+//! rounds = 12
+//!
+//! [profiles.kids]
+//! rounds = 15
+//! colors = 4
+//! duplicates = false
+//! theme = "high-contrast"
+//! strategy = "first-candidate"
+//! ```
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::messages::tr;
+use crate::{StrategyArg, ThemeArg};
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    pub rounds: Option<NonZeroUsize>,
+    pub colors: Option<usize>,
+    pub duplicates: Option<bool>,
+    pub theme: Option<ThemeArg>,
+    pub strategy: Option<StrategyArg>,
+}
+
+impl Settings {
+    /// Settings of `self`, completed by those of `other`.
+    pub fn or(self, other: Settings) -> Settings {
+        Settings {
+            rounds: self.rounds.or(other.rounds),
+            colors: self.colors.or(other.colors),
+            duplicates: self.duplicates.or(other.duplicates),
+            theme: self.theme.or(other.theme),
+            strategy: self.strategy.or(other.strategy),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    rounds: Option<NonZeroUsize>,
+    colors: Option<usize>,
+    duplicates: Option<bool>,
+    theme: Option<ThemeArg>,
+    strategy: Option<StrategyArg>,
+    #[serde(default)]
+    profiles: HashMap<String, Settings>,
+}
+
+fn default_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_home.join("mastermind").join("config.toml"))
+}
+
+/// Where files kept between runs go: `$XDG_DATA_HOME/mastermind`, or
+/// `~/.local/share/mastermind`.
+pub fn data_dir() -> Option<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))?;
+    Some(data_home.join("mastermind"))
+}
+
+fn parse(content: &str) -> Result<Config, String> {
+    toml::from_str(content).map_err(|err| err.message().to_string())
+}
+
+/// Loads the settings of `profile`, from `path` or from the default file.
+///
+/// A missing default file is the same as an empty one.
+pub fn load(path: Option<&Path>, profile: Option<&str>) -> Result<Settings, String> {
+    let (path, required) = match path {
+        Some(path) => (Some(path.to_path_buf()), true),
+        None => (default_path(), false),
+    };
+
+    let config = match &path {
+        Some(path) => match fs::read_to_string(path) {
+            Ok(content) => parse(&content).map_err(|err| format!("{}: {}", path.display(), err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound && !required => Config::default(),
+            Err(err) => return Err(format!("{}: {}", path.display(), err)),
+        },
+        None => Config::default(),
+    };
+    config.settings(profile)
+}
+
+impl Config {
+    fn settings(mut self, profile: Option<&str>) -> Result<Settings, String> {
+        let defaults = Settings {
+            rounds: self.rounds,
+            colors: self.colors,
+            duplicates: self.duplicates,
+            theme: self.theme,
+            strategy: self.strategy,
+        };
+        match profile {
+            Some(name) => self
+                .profiles
+                .remove(name)
+                .map(|settings| settings.or(defaults))
+                .ok_or_else(|| tr!("unknown-profile", profile = name)),
+            None => Ok(defaults),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_config {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        rounds = 12
+        theme = "symbols"
+
+        [profiles.kids]
+        rounds = 15
+        colors = 4
+        duplicates = false
+        strategy = "minimax"
+    "#;
+
+    #[test]
+    fn profile_overrides_defaults() {
+        let settings = parse(CONFIG).unwrap().settings(Some("kids")).unwrap();
+        assert_eq!(settings.rounds, NonZeroUsize::new(15));
+        assert_eq!(settings.colors, Some(4));
+        assert_eq!(settings.duplicates, Some(false));
+        assert!(matches!(settings.theme, Some(ThemeArg::Symbols)));
+        assert!(matches!(settings.strategy, Some(StrategyArg::Minimax)));
+    }
+
+    #[test]
+    fn defaults_without_profile() {
+        let settings = parse(CONFIG).unwrap().settings(None).unwrap();
+        assert_eq!(settings.rounds, NonZeroUsize::new(12));
+        assert!(settings.strategy.is_none());
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse(CONFIG).unwrap().settings(Some("adults")).is_err());
+        assert!(parse("colour = \"red\"").is_err());
+        assert!(parse("theme = \"pink\"").is_err());
+        assert!(parse("rounds = 0").is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! The daily puzzle: the same code for every player on a given (UTC) day,
+//! with a result to share and a winning streak.
+//!
+//! Statistics are kept in `$XDG_DATA_HOME/mastermind/daily.toml`, or
+//! `~/.local/share/mastermind/daily.toml`.
+
+use std::fs;
+use std::io;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use mastermind::render::Theme;
+use mastermind::{Code, Score, SIZE};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::messages::tr;
+use crate::play;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Default, Serialize, Deserialize)]
+struct Streak {
+    // Days since the Unix epoch.
+    last_day: Option<u64>,
+    played: usize,
+    won: usize,
+    current: usize,
+    best: usize,
+}
+
+impl Streak {
+    fn record(&mut self, day: u64, won: bool) {
+        self.played += 1;
+        if won {
+            self.won += 1;
+            self.current = match self.last_day {
+                Some(last_day) if last_day + 1 == day => self.current + 1,
+                _ => 1,
+            };
+            self.best = self.best.max(self.current);
+        } else {
+            self.current = 0;
+        }
+        self.last_day = Some(day);
+    }
+}
+
+fn today() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("the system clock is before 1970");
+    now.as_secs() / SECONDS_PER_DAY
+}
+
+/// The date of a day since the Unix epoch, as `YYYY-MM-DD`.
+fn date(day: u64) -> String {
+    // Howard Hinnant's civil_from_days, with days counted from 0000-03-01.
+    let days = day + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The code of the day, among all the codes of the classic rules.
+fn code_of_day(day: u64) -> Code {
+    let mut rng = StdRng::seed_from_u64(day);
+    *Code::all().choose(&mut rng).unwrap()
+}
+
+/// One line of squares per round, which tells how it went without giving
+/// the code away.
+fn share_grid(scores: &[Score]) -> String {
+    scores
+        .iter()
+        .map(|score| {
+            let mut line = "🟩".repeat(score.matches());
+            line.push_str(&"🟨".repeat(score.presents()));
+            line.push_str(&"⬛".repeat(SIZE - score.matches() - score.presents()));
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn path() -> Option<PathBuf> {
+    Some(config::data_dir()?.join("daily.toml"))
+}
+
+fn load(path: &Path) -> Result<Streak, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => {
+            toml::from_str(&content).map_err(|err| format!("{}: {}", path.display(), err.message()))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Streak::default()),
+        Err(err) => Err(format!("{}: {}", path.display(), err)),
+    }
+}
+
+fn save(path: &Path, streak: &Streak) -> Result<(), String> {
+    let content = toml::to_string(streak).map_err(|err| err.to_string())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+    }
+    fs::write(path, content).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+fn print_streak(streak: &Streak) {
+    println!(
+        "{}",
+        tr!(
+            "daily-stats",
+            played = streak.played,
+            won = streak.won,
+            current = streak.current,
+            best = streak.best
+        )
+    );
+}
+
+/// Plays today's puzzle, once a day, with the classic rules.
+pub fn run(max_round: NonZeroUsize, theme: Theme) {
+    let day = today();
+    let path = path();
+    let mut streak = match path.as_deref().map(load).transpose() {
+        Ok(streak) => streak.unwrap_or_default(),
+        Err(err) => {
+            eprintln!("{}", tr!("error", error = err));
+            process::exit(1);
+        }
+    };
+    if streak.last_day == Some(day) {
+        println!("{}", tr!("daily-played"));
+        print_streak(&streak);
+        return;
+    }
+
+    println!("{}", tr!("daily-intro", date = date(day)));
+    let secret = code_of_day(day);
+    let rounds = play::break_code(max_round, theme, secret);
+    let won = rounds
+        .last()
+        .is_some_and(|(_, score)| score.matches() == SIZE);
+    if !won {
+        println!("{}", tr!("hotseat-code", code = secret));
+    }
+
+    streak.record(day, won);
+    if let Some(path) = &path {
+        if let Err(err) = save(path, &streak) {
+            eprintln!("{}", tr!("error", error = err));
+        }
+    }
+
+    let result = if won {
+        rounds.len().to_string()
+    } else {
+        "X".to_string()
+    };
+    let scores: Vec<Score> = rounds.iter().map(|&(_, score)| score).collect();
+    println!();
+    println!(
+        "{}",
+        tr!(
+            "daily-share",
+            date = date(day),
+            result = result,
+            max_round = max_round
+        )
+    );
+    println!("{}", share_grid(&scores));
+    println!();
+    print_streak(&streak);
+}
+
+#[cfg(test)]
+mod test_daily {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(11_016), "2000-02-29");
+        assert_eq!(date(20_742), "2026-10-16");
+    }
+
+    #[test]
+    fn same_code_all_day() {
+        assert_eq!(code_of_day(20_742), code_of_day(20_742));
+    }
+
+    #[test]
+    fn share() {
+        let scores = [
+            Score::from_counts(1, 2).unwrap(),
+            Score::from_counts(4, 0).unwrap(),
+        ];
+        assert_eq!(share_grid(&scores), "🟩🟨🟨⬛\n🟩🟩🟩🟩");
+    }
+
+    #[test]
+    fn streak() {
+        let mut streak = Streak::default();
+        streak.record(10, true);
+        streak.record(11, true);
+        assert_eq!((streak.current, streak.best), (2, 2));
+        // A day was skipped.
+        streak.record(13, true);
+        assert_eq!((streak.current, streak.best), (1, 2));
+        streak.record(14, false);
+        assert_eq!((streak.played, streak.won, streak.current), (4, 3, 0));
+    }
+}
@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use mastermind::rules::Rules;
+use mastermind::solver::{Solver, Strategy};
+use mastermind::CodeBreaker;
+
+use crate::messages::tr;
+use crate::transcript::{self, Round};
+
+/// Reads the rounds from the file or the URL `history` names, or from
+/// `history` itself with rounds separated by commas or semicolons, e.g.
+/// `AABB 1 0, CCDD 0 1`.
+pub fn read_history(history: &str) -> Result<Vec<Round>, String> {
+    let path = Path::new(history);
+    if path.is_file() || history.starts_with("http://") {
+        return transcript::read(path);
+    }
+    transcript::parse(&history.replace([',', ';'], "\n"))
+}
+
+/// Prints how many codes are consistent with the rounds, and lists up to
+/// `limit` of them if `list` is set.
+pub fn run(rounds: &[Round], rules: Rules, strategy: Strategy, list: bool, limit: usize) {
+    let mut solver = Solver::with_rules(rules, strategy);
+    for (guess, score) in rounds {
+        solver.record(*guess, *score);
+    }
+
+    let candidates = solver.candidates();
+    if candidates.is_empty() {
+        println!("{}", tr!("contradictory-scores"));
+        return;
+    }
+    println!(
+        "{}",
+        tr!(
+            "hint",
+            count = candidates.len(),
+            guess = solver.guess_code()
+        )
+    );
+
+    if list {
+        let shown: Vec<String> = candidates
+            .iter()
+            .take(limit)
+            .map(|code| code.to_string())
+            .collect();
+        print!("{}", shown.join(" "));
+        if candidates.len() > limit {
+            print!(" {}", tr!("hint-more", count = candidates.len() - limit));
+        }
+        println!();
+    }
+}
@@ -0,0 +1,85 @@
+use std::io::{self, BufRead, Write};
+use std::process;
+
+use mastermind::rules::{Rules, RulesError};
+use mastermind::{Code, CodePeg, ParseCodeError, Score, SIZE};
+
+use crate::messages::tr;
+
+/// Prints the prompt and reads a line from stdin, exiting on end of input.
+pub fn read_line(prompt: &str) -> String {
+    print!("{}", prompt);
+    io::stdout().flush().expect("failed to flush stdout");
+
+    let mut line = String::new();
+    let read = io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .expect("failed to read stdin");
+    if read == 0 {
+        println!();
+        process::exit(0);
+    }
+    line
+}
+
+/// Parses a score written as `<matched> <present>`, e.g. `1 2`.
+pub fn parse_score(line: &str) -> Result<Score, String> {
+    // Every count is checked, but only the first two are kept.
+    let mut counts = [0; 2];
+    let mut len = 0;
+    for count in line.split_whitespace() {
+        let count = count
+            .parse()
+            .map_err(|_| tr!("invalid-count", count = count))?;
+        if let Some(slot) = counts.get_mut(len) {
+            *slot = count;
+        }
+        len += 1;
+    }
+    match (len, counts) {
+        (2, [matches, presents]) => Score::from_counts(matches, presents)
+            .ok_or_else(|| tr!("impossible-score", matches = matches, presents = presents)),
+        _ => Err(tr!("expected-two-counts")),
+    }
+}
+
+pub fn parse_code(line: &str) -> Result<Code, String> {
+    line.parse().map_err(|err| match err {
+        ParseCodeError::WrongLength(len) => tr!("wrong-length", expected = SIZE, actual = len),
+        ParseCodeError::InvalidPeg(peg) => tr!("invalid-peg", peg = peg),
+        err => err.to_string(),
+    })
+}
+
+/// Parses a code and checks that the rules allow it.
+pub fn parse_allowed_code(line: &str, rules: Rules) -> Result<Code, String> {
+    let code = parse_code(line)?;
+    rules.check(code).map_err(rules_error)?;
+    Ok(code)
+}
+
+pub fn rules_error(err: RulesError) -> String {
+    match err {
+        RulesError::Colors(colors) => {
+            tr!("rules-colors", colors = colors, max = CodePeg::ALL.len())
+        }
+        RulesError::NotEnoughColors(colors) => {
+            tr!("rules-not-enough-colors", colors = colors, size = SIZE)
+        }
+        RulesError::Pegs(pegs) => tr!("rules-pegs", pegs = pegs, size = SIZE),
+        RulesError::UnusedPeg(peg) => tr!("peg-not-used", peg = peg),
+        RulesError::Duplicate(peg) => tr!("peg-repeated", peg = peg),
+        RulesError::Repeated(guess) => tr!("guess-repeated", guess = guess),
+        err => err.to_string(),
+    }
+}
+
+/// Prints the introduction `id` to a game under `rules`.
+pub fn print_intro(id: &str, rules: Rules) {
+    let last = rules.palette().last().unwrap();
+    println!("{}", tr!(id, size = SIZE, last = last));
+    if !rules.duplicates() {
+        println!("{}", tr!("no-duplicates"));
+    }
+}
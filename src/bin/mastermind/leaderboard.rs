@@ -0,0 +1,83 @@
+//! The ratings and statistics recorded by `mastermind-server --db`, with the
+//! `store` feature.
+
+use std::path::PathBuf;
+
+use mastermind::store::{self, PlayerStats, Store};
+
+use crate::config;
+use crate::messages::tr;
+
+/// The database at `db`, or the default one in the data directory.
+fn open(db: Option<PathBuf>) -> Result<Box<dyn Store>, String> {
+    let path = db
+        .or_else(|| Some(config::data_dir()?.join("games.json")))
+        .ok_or_else(|| tr!("no-data-dir"))?;
+    store::open(&path).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+fn print(rank: usize, player: &PlayerStats) {
+    println!(
+        "{}",
+        tr!(
+            "leaderboard-row",
+            rank = rank,
+            name = &player.name,
+            rating = player.rating,
+            played = player.played,
+            won = player.won,
+            streak = player.streak,
+            best = player.best_streak
+        )
+    );
+}
+
+/// Prints the `limit` best rated players.
+pub fn run(db: Option<PathBuf>, limit: usize) -> Result<(), String> {
+    let players = open(db)?
+        .leaderboard(limit)
+        .map_err(|err| err.to_string())?;
+    if players.is_empty() {
+        println!("{}", tr!("leaderboard-empty"));
+    }
+    for (rank, player) in players.iter().enumerate() {
+        print(rank + 1, player);
+    }
+    Ok(())
+}
+
+/// Prints the statistics and last games of a player.
+pub fn run_stats(db: Option<PathBuf>, name: &str, limit: usize) -> Result<(), String> {
+    let store = open(db)?;
+    let Some(player) = store.stats(name).map_err(|err| err.to_string())? else {
+        return Err(tr!("unknown-player", name = name));
+    };
+    let rank = store
+        .leaderboard(usize::MAX)
+        .map_err(|err| err.to_string())?
+        .iter()
+        .position(|p| p.name == name)
+        .map_or(0, |i| i + 1);
+    print(rank, &player);
+    for game in store
+        .load_history(name, limit)
+        .map_err(|err| err.to_string())?
+    {
+        let line = match game.won_in {
+            Some(rounds) => tr!(
+                "history-won",
+                breaker = &game.breaker,
+                maker = &game.maker,
+                rounds = rounds
+            ),
+            None => tr!(
+                "history-lost",
+                breaker = &game.breaker,
+                maker = &game.maker,
+                max_round = game.max_round
+            ),
+        };
+        println!("  {}", line);
+    }
+    Ok(())
+}
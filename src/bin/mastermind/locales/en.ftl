@@ -0,0 +1,129 @@
+# English messages, also used for any message missing from another catalog.
+# Placeholders are written { $name }.
+
+error = error: { $error }
+seed = seed: { $seed }
+
+invalid-count = invalid count '{ $count }'
+impossible-score = no guess can get { $matches } matched and { $presents } present
+expected-two-counts = expected two counts, e.g. 1 2
+wrong-length = expected { $expected } pegs, got { $actual }
+invalid-peg = invalid peg '{ $peg }'
+expected-round = expected '<guess> <matched> <present>'
+line-error = line { $line }: { $error }
+invalid-response = invalid HTTP response
+download-failed = { $status }: { $error }
+peg-not-used = peg '{ $peg }' is not used in this game
+peg-repeated = peg '{ $peg }' may not be repeated
+guess-repeated = { $guess } was already played
+rules-colors = { $colors } colors are not supported, expected 1 to { $max }
+rules-not-enough-colors = { $colors } colors are not enough for { $size } pegs without duplicates
+rules-pegs = { $pegs } pegs are not supported, only { $size }
+no-duplicates = No letter may appear twice in the code.
+
+play-intro = Guess the { $size } pegs code, using letters A to { $last } (e.g. ABCD).
+guess-prompt = [{ $round }/{ $max_round }] guess:
+player-wins = You cracked the code in { $rounds } rounds!
+player-loses = You lose!
+player-loses-with-code = You lose! The code was { $code }.
+
+solve-intro =
+    Think of a { $size } pegs code using letters A to { $last }, then score my guesses as
+    '<matched> <present>' (e.g. 1 2).
+score-prompt = [{ $round }/{ $max_round }] { $guess }?
+solver-wins = I found your code in { $rounds } rounds!
+solver-loses = I lose!
+not-solved = # not solved in { $rounds } rounds
+contradictory-scores = No code is consistent with the scores: one of them must be wrong.
+
+simulate-games = games:   { $games }
+simulate-lost = lost:    { $lost }
+simulate-average = average: { $average }
+simulate-std-dev = std dev: { $std_dev }
+simulate-worst = worst:   { $worst }
+simulate-hardest = hardest: { $codes }
+simulate-header = rounds  games
+
+analyze-candidates = candidates: { $before } -> { $after }
+analyze-optimal = worst case { $worst_case }, optimal
+analyze-suboptimal = worst case { $worst_case }, { $best } would leave at most { $best_worst_case }
+analyze-inconsistent = (guess could not be the code)
+analyze-solved = The code is { $code }.
+analyze-open = { $count } codes are still possible.
+
+tui-board = Board
+tui-history = History
+tui-game-over = Enter: new game, Esc: quit
+tui-guess = Your guess: ←→ select a peg, ↑↓ or A-F change it, Enter to play
+tui-score = Score of { $guess } as '<matched> <present>' (e.g. 1 2)
+tui-round = { $round }. { $guess }  { $matches } matched, { $presents } present  ({ $remaining } codes left)
+tui-mode-breaker = breaker
+tui-mode-maker = maker
+tui-status = { $mode } │ round { $round }/{ $max_round } │ Tab: assistance { $assist }
+tui-on = on
+tui-off = off
+hint = { $count } codes left, try { $guess }
+
+assist-intro =
+    Enter each round as '<guess> <matched> <present>' (e.g. AABB 1 0),
+    or 'undo' to take back the last round.
+assist-solved = Solved in { $rounds } rounds!
+
+replay-position = Round { $round } of { $total }
+replay-candidates = { $count } codes still possible
+replay-prompt = [n]ext, [p]revious, [j]ump <round>, [q]uit:
+replay-invalid = unknown command '{ $command }'
+
+progress = avg { $average }  ETA { $eta }
+
+selfplay-header = strategy          games   lost  average  worst
+
+unknown-profile = no profile named { $profile } in the configuration file
+
+hotseat-prompt = Code maker, enter your code (it will not be shown):
+hotseat-ready = Code recorded. Code breaker, your turn!
+hotseat-code = The code was { $code }.
+
+hint-more = … and { $count } more
+
+time-left = { $time } left
+time-up-move = Time is up for this move! The code was { $code }.
+time-up-total = Time is up for the game! The code was { $code }.
+
+daily-intro = Daily puzzle of { $date }: guess the 4 pegs code, using letters A to F.
+daily-played = You already played today's puzzle, come back tomorrow!
+daily-share = Mastermind { $date } { $result }/{ $max_round }
+daily-stats = played { $played }, won { $won }, streak { $current } (best { $best })
+
+bench-header = benchmark            items   seconds   per second
+bench-score = score: { $score } (higher is faster)
+
+tutorial-intro =
+    I chose a secret code of { $size } pegs, each a letter from A to F, and the same
+    letter may appear several times. After each guess, you get a score: how many
+    pegs are matched, i.e. the right letter at the right place, and how many are
+    present, i.e. a right letter at the wrong place. Each score rules out every
+    code which would have scored differently. Let's play a game together.
+tutorial-round = Round { $round }: type { $guess } to play it.
+tutorial-retype = Please type { $guess }.
+tutorial-score = { $guess } scores { $matches } matched and { $presents } present.
+tutorial-eliminated = This rules out { $eliminated } codes, leaving { $left }.
+tutorial-example = For instance, { $code } cannot be the code: { $guess } would have scored { $matches } { $presents } against it.
+tutorial-deduced = Only { $code } is left: it must be the code!
+tutorial-solved = Code broken in { $rounds } rounds! Now try a real game with 'mastermind play'.
+
+remote-waiting = Connected, waiting for an opponent…
+remote-started = Game { $session } started, your token is { $token }.
+remote-won = The code was broken in { $rounds } rounds.
+remote-lost = The code was not broken: it was { $code }.
+remote-over = The game is over.
+remote-reconnecting = Connection lost ({ $error }), resuming the game…
+remote-resumed = Game resumed after { $rounds } rounds.
+remote-move-time = You have { $seconds } seconds for each move.
+
+leaderboard-row = { $rank }. { $name }: rating { $rating }, played { $played }, won { $won }, streak { $streak } (best { $best })
+leaderboard-empty = No game was recorded yet.
+no-data-dir = no data directory: set XDG_DATA_HOME or HOME, or use --db
+unknown-player = no player named { $name }
+history-won = { $breaker } broke the code of { $maker } in { $rounds } rounds
+history-lost = { $breaker } did not break the code of { $maker } in { $max_round } rounds
@@ -0,0 +1,128 @@
+# Messages en français.
+
+error = erreur : { $error }
+seed = graine : { $seed }
+
+invalid-count = nombre invalide '{ $count }'
+impossible-score = aucune proposition ne peut avoir { $matches } bien placés et { $presents } mal placés
+expected-two-counts = deux nombres attendus, par exemple 1 2
+wrong-length = { $expected } pions attendus, { $actual } reçus
+invalid-peg = pion invalide '{ $peg }'
+expected-round = '<proposition> <bien placés> <mal placés>' attendu
+line-error = ligne { $line } : { $error }
+invalid-response = réponse HTTP invalide
+download-failed = { $status } : { $error }
+peg-not-used = le pion '{ $peg }' n'est pas utilisé dans cette partie
+peg-repeated = le pion '{ $peg }' ne peut pas être répété
+guess-repeated = { $guess } a déjà été joué
+rules-colors = { $colors } couleurs ne sont pas possibles, de 1 à { $max } attendues
+rules-not-enough-colors = { $colors } couleurs ne suffisent pas pour { $size } pions sans répétition
+rules-pegs = { $pegs } pions ne sont pas possibles, seulement { $size }
+no-duplicates = Aucune lettre n'apparaît deux fois dans le code.
+
+play-intro = Trouvez le code de { $size } pions, avec les lettres A à { $last } (par exemple ABCD).
+guess-prompt = [{ $round }/{ $max_round }] proposition :
+player-wins = Vous avez trouvé le code en { $rounds } coups !
+player-loses = Perdu !
+player-loses-with-code = Perdu ! Le code était { $code }.
+
+solve-intro =
+    Pensez à un code de { $size } pions avec les lettres A à { $last }, puis notez mes propositions
+    sous la forme '<bien placés> <mal placés>' (par exemple 1 2).
+score-prompt = [{ $round }/{ $max_round }] { $guess } ?
+solver-wins = J'ai trouvé votre code en { $rounds } coups !
+solver-loses = J'ai perdu !
+not-solved = # non résolu en { $rounds } coups
+contradictory-scores = Aucun code ne correspond aux notes : l'une d'elles doit être fausse.
+
+simulate-games = parties :  { $games }
+simulate-lost = perdues :  { $lost }
+simulate-average = moyenne :  { $average }
+simulate-std-dev = écart type : { $std_dev }
+simulate-worst = pire :     { $worst }
+simulate-hardest = plus durs : { $codes }
+simulate-header = coups   parties
+
+analyze-candidates = candidats : { $before } -> { $after }
+analyze-optimal = pire cas { $worst_case }, optimal
+analyze-suboptimal = pire cas { $worst_case }, { $best } laisserait au plus { $best_worst_case }
+analyze-inconsistent = (la proposition ne pouvait pas être le code)
+analyze-solved = Le code est { $code }.
+analyze-open = { $count } codes sont encore possibles.
+
+tui-board = Plateau
+tui-history = Historique
+tui-game-over = Entrée : nouvelle partie, Échap : quitter
+tui-guess = Votre proposition : ←→ choisir un pion, ↑↓ ou A-F le changer, Entrée pour jouer
+tui-score = Note de { $guess } sous la forme '<bien placés> <mal placés>' (par exemple 1 2)
+tui-round = { $round }. { $guess }  { $matches } bien placés, { $presents } mal placés  ({ $remaining } codes restants)
+tui-mode-breaker = décodeur
+tui-mode-maker = codeur
+tui-status = { $mode } │ coup { $round }/{ $max_round } │ Tab : aide { $assist }
+tui-on = activée
+tui-off = désactivée
+hint = { $count } codes restants, essayez { $guess }
+
+assist-intro =
+    Entrez chaque coup sous la forme '<proposition> <bien placés> <mal placés>' (par exemple AABB 1 0),
+    ou 'undo' pour annuler le dernier coup.
+assist-solved = Résolu en { $rounds } coups !
+
+replay-position = Coup { $round } sur { $total }
+replay-candidates = { $count } codes encore possibles
+replay-prompt = [n] suivant, [p] précédent, [j] aller au coup <n>, [q] quitter :
+replay-invalid = commande inconnue '{ $command }'
+
+progress = moy. { $average }  reste { $eta }
+
+selfplay-header = stratégie       parties perdues  moyenne   pire
+
+unknown-profile = aucun profil nommé { $profile } dans le fichier de configuration
+
+hotseat-prompt = Codeur, entrez votre code (il ne sera pas affiché) :
+hotseat-ready = Code enregistré. Décodeur, à vous de jouer !
+hotseat-code = Le code était { $code }.
+
+hint-more = … et { $count } autres
+
+time-left = reste { $time }
+time-up-move = Temps écoulé pour ce coup ! Le code était { $code }.
+time-up-total = Temps écoulé pour la partie ! Le code était { $code }.
+
+daily-intro = Défi du { $date } : trouvez le code de 4 pions, avec les lettres A à F.
+daily-played = Vous avez déjà joué le défi du jour, revenez demain !
+daily-share = Mastermind { $date } { $result }/{ $max_round }
+daily-stats = { $played } parties, { $won } gagnées, série de { $current } (record { $best })
+
+bench-header = mesure               unités  secondes  par seconde
+bench-score = score : { $score } (plus c'est haut, plus c'est rapide)
+
+tutorial-intro =
+    J'ai choisi un code secret de { $size } pions, chacun une lettre de A à F, et une
+    même lettre peut apparaître plusieurs fois. Après chaque proposition, vous
+    obtenez une note : le nombre de pions bien placés, la bonne lettre au bon
+    endroit, et de pions mal placés, une bonne lettre au mauvais endroit. Chaque
+    note élimine tous les codes qui auraient été notés autrement. Jouons une partie ensemble.
+tutorial-round = Coup { $round } : tapez { $guess } pour le jouer.
+tutorial-retype = Tapez { $guess }, s'il vous plaît.
+tutorial-score = { $guess } obtient { $matches } bien placés et { $presents } mal placés.
+tutorial-eliminated = Cela élimine { $eliminated } codes, il en reste { $left }.
+tutorial-example = Par exemple, { $code } ne peut pas être le code : { $guess } aurait obtenu { $matches } { $presents } contre lui.
+tutorial-deduced = Il ne reste que { $code } : c'est forcément le code !
+tutorial-solved = Code trouvé en { $rounds } coups ! Essayez maintenant une vraie partie avec 'mastermind play'.
+
+remote-waiting = Connecté, en attente d'un adversaire…
+remote-started = La partie { $session } commence, votre jeton est { $token }.
+remote-won = Le code a été trouvé en { $rounds } coups.
+remote-lost = Le code n'a pas été trouvé : c'était { $code }.
+remote-over = La partie est terminée.
+remote-reconnecting = Connexion perdue ({ $error }), reprise de la partie…
+remote-resumed = Partie reprise après { $rounds } coups.
+remote-move-time = Vous avez { $seconds } secondes par coup.
+
+leaderboard-row = { $rank }. { $name } : classement { $rating }, { $played } parties, { $won } gagnées, série de { $streak } (record { $best })
+leaderboard-empty = Aucune partie n'a encore été enregistrée.
+no-data-dir = pas de dossier de données : définissez XDG_DATA_HOME ou HOME, ou utilisez --db
+unknown-player = aucun joueur nommé { $name }
+history-won = { $breaker } a trouvé le code de { $maker } en { $rounds } coups
+history-lost = { $breaker } n'a pas trouvé le code de { $maker } en { $max_round } coups
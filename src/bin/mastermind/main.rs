@@ -0,0 +1,563 @@
+use std::io;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use mastermind::analysis;
+use mastermind::clock::Clock;
+use mastermind::engine;
+use mastermind::render::Theme;
+use mastermind::rules::{Rules, RulesError};
+use mastermind::solver::{Backend, Strategy};
+use mastermind::{Code, CodePeg, SIZE};
+use serde::Deserialize;
+
+use crate::config::Settings;
+use crate::messages::tr;
+
+mod analyze;
+mod assist;
+mod bench;
+mod config;
+mod daily;
+mod hint;
+mod input;
+#[cfg(feature = "store")]
+mod leaderboard;
+mod messages;
+mod play;
+mod progress;
+#[cfg(feature = "net")]
+mod remote;
+mod replay;
+mod script;
+mod selfplay;
+mod simulate;
+mod solve;
+mod transcript;
+#[cfg(feature = "tui")]
+mod tui;
+mod tutorial;
+
+#[derive(Parser)]
+#[command(about = "Play mastermind, or let the computer play it")]
+struct Cli {
+    #[command(flatten)]
+    rules: RulesArgs,
+
+    /// How pegs are drawn [default: color]
+    #[arg(long, global = true, value_enum)]
+    theme: Option<ThemeArg>,
+
+    /// How the solver plays [default: first-candidate]
+    #[arg(long, global = true, value_enum)]
+    strategy: Option<StrategyArg>,
+
+    /// Profile of the configuration file to use
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Configuration file [default: ~/.config/mastermind/config.toml]
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Draws the random codes, so that the same seed plays the same games
+    /// [default: random]
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// The number of guesses allowed when neither the command line nor the
+/// configuration file gives one.
+const DEFAULT_ROUNDS: NonZeroUsize = NonZeroUsize::new(10).unwrap();
+
+#[derive(Args)]
+struct RulesArgs {
+    /// Number of guesses allowed to break the code, at least 1 [default: 10]
+    #[arg(long, global = true)]
+    rounds: Option<NonZeroUsize>,
+
+    /// Number of colors, i.e. of letters from A, used by codes [default: 6]
+    #[arg(long, global = true)]
+    colors: Option<usize>,
+
+    /// Number of pegs in a code; only 4 is supported
+    #[arg(long, global = true)]
+    pegs: Option<usize>,
+
+    /// Forbid codes which repeat a color
+    #[arg(long, global = true)]
+    no_duplicates: bool,
+
+    /// Reject a guess played twice, which loses the games of the solvers
+    #[arg(long, global = true)]
+    strict: bool,
+}
+
+impl RulesArgs {
+    /// Validates the rules, completed by `settings`, against what the
+    /// solver supports.
+    fn rules(&self, settings: &Settings) -> Result<Rules, RulesError> {
+        if let Some(pegs) = self.pegs.filter(|&pegs| pegs != SIZE) {
+            return Err(RulesError::Pegs(pegs));
+        }
+        Rules::new(
+            settings.colors.unwrap_or(CodePeg::ALL.len()),
+            settings.duplicates.unwrap_or(true),
+        )
+        .map(|rules| rules.with_strict(self.strict))
+    }
+}
+
+#[derive(Args, Clone, Copy)]
+struct TimeArgs {
+    /// Seconds allowed for each guess; running out loses the game
+    #[arg(long, value_name = "SECONDS")]
+    move_time: Option<u64>,
+
+    /// Seconds allowed for the whole game; running out loses the game
+    #[arg(long, value_name = "SECONDS")]
+    total_time: Option<u64>,
+}
+
+impl TimeArgs {
+    /// A clock for the limits, or `None` if the game is not timed.
+    fn clock(&self) -> Option<Clock> {
+        (self.move_time.is_some() || self.total_time.is_some()).then(|| {
+            Clock::new(
+                self.move_time.map(Duration::from_secs),
+                self.total_time.map(Duration::from_secs),
+            )
+        })
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum ThemeArg {
+    /// Colored discs
+    Color,
+    /// A distinct shape per peg, without colors
+    Symbols,
+    /// Distinct shapes in bold bright colors
+    HighContrast,
+}
+
+impl From<ThemeArg> for Theme {
+    fn from(theme: ThemeArg) -> Self {
+        match theme {
+            ThemeArg::Color => Theme::Color,
+            ThemeArg::Symbols => Theme::Symbols,
+            ThemeArg::HighContrast => Theme::HighContrast,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum StrategyArg {
+    /// Play the first code consistent with the scores so far
+    FirstCandidate,
+    /// Minimize the number of codes left in the worst case (Knuth)
+    Minimax,
+}
+
+impl From<StrategyArg> for Strategy {
+    fn from(strategy: StrategyArg) -> Self {
+        match strategy {
+            StrategyArg::FirstCandidate => Strategy::FirstCandidate,
+            StrategyArg::Minimax => Strategy::Minimax,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum BackendArg {
+    /// Compute each score
+    Scorer,
+    /// Pack the codes in machine words, faster with minimax
+    Packed,
+    /// Look the scores up in a table of every pair of codes, built first
+    Table,
+    /// Score many codes at once, with SIMD instructions with the simd feature
+    Batch,
+}
+
+impl From<BackendArg> for Backend {
+    fn from(backend: BackendArg) -> Self {
+        match backend {
+            BackendArg::Scorer => Backend::Scorer,
+            BackendArg::Packed => Backend::Packed,
+            BackendArg::Table => Backend::Table,
+            BackendArg::Batch => Backend::Batch,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Break a random code
+    Play {
+        #[command(flatten)]
+        time: TimeArgs,
+        /// Write the moves of the game to this file, to play them again with
+        /// rerun
+        #[arg(long, value_name = "FILE")]
+        record: Option<PathBuf>,
+    },
+    /// Break the code of the day, the same for everyone, and share the result
+    Daily,
+    /// Learn to play with a guided game
+    Tutorial,
+    /// Let a player enter a hidden code for another player to break
+    Hotseat,
+    /// Print the solver's guesses for a code, or score them interactively
+    Solve {
+        /// The code to break, e.g. ABCD; if missing, you score the guesses
+        secret: Option<Code>,
+        /// Solve each code read from stdin, one per line
+        #[arg(long, conflicts_with = "secret")]
+        batch: bool,
+        /// Let an external engine, speaking the Universal Mastermind
+        /// Interface, break the code instead of the solver
+        #[arg(long, value_name = "COMMAND", requires = "secret")]
+        engine: Option<String>,
+    },
+    /// Answer the Universal Mastermind Interface on stdin and stdout, for
+    /// other programs to use the solver
+    Engine,
+    /// Run the solver against many codes and print statistics
+    Simulate {
+        /// Number of random codes to play; defaults to every possible code
+        #[arg(long)]
+        games: Option<usize>,
+        /// Do not show the progress bar
+        #[arg(long, short)]
+        quiet: bool,
+        /// How the solver scores codes, which only changes how fast it plays
+        #[arg(long, value_enum, default_value_t = BackendArg::Scorer)]
+        backend: BackendArg,
+    },
+    /// Print how many guesses the solver needs against each code, and the
+    /// entropy before each guess, one code per line
+    Difficulty {
+        /// Print a JSON report instead, with the times and the worst case
+        #[arg(long)]
+        json: bool,
+    },
+    /// Compare solver strategies against a code maker
+    Selfplay {
+        /// Strategies to compare
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            default_values_t = [StrategyArg::FirstCandidate, StrategyArg::Minimax]
+        )]
+        strategies: Vec<StrategyArg>,
+        #[arg(long, value_enum, default_value_t = selfplay::Maker::Random)]
+        maker: selfplay::Maker,
+        /// Number of games played by each strategy
+        #[arg(long, default_value_t = 100)]
+        games: usize,
+        /// Do not show the progress bar
+        #[arg(long, short)]
+        quiet: bool,
+    },
+    /// Annotate a transcript with the number of codes left after each round
+    Analyze {
+        /// File with one '<guess> <matched> <present>' round per line, or
+        /// an http:// URL to download it from
+        transcript: PathBuf,
+    },
+    /// Play the guesses of a script and print the game as JSON lines
+    Script {
+        /// File with one guess per line, or - for stdin
+        script: PathBuf,
+        /// The code to break; defaults to a random code
+        #[arg(long)]
+        secret: Option<Code>,
+    },
+    /// Step through a transcript round by round
+    Replay {
+        /// File with one '<guess> <matched> <present>' round per line, or
+        /// an http:// URL to download it from
+        transcript: PathBuf,
+        /// Also show how many codes were still possible
+        #[arg(long)]
+        candidates: bool,
+    },
+    /// Play the moves of a recorded game again and step through its rounds
+    Rerun {
+        /// File written by play --record
+        record: PathBuf,
+    },
+    /// Print how many codes are consistent with the rounds played so far
+    Hint {
+        /// Transcript file or http:// URL, or rounds separated by commas, e.g. "AABB 1 0, CCDD 0 1"
+        #[arg(long)]
+        history: String,
+        /// Also list the codes
+        #[arg(long)]
+        list: bool,
+        /// Maximum number of codes listed
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Time the scorer and the solvers on a fixed workload
+    Bench,
+    /// Suggest the next guess of a game played elsewhere, round after round
+    Assist,
+    /// Join a game hosted by mastermind-server on another machine
+    #[cfg(feature = "net")]
+    Connect {
+        /// Address of the server, e.g. 192.168.1.10:4040, or ws://192.168.1.10:4041
+        /// with the ws feature
+        address: String,
+        #[arg(long, value_enum, default_value_t = remote::RoleArg::Breaker)]
+        role: remote::RoleArg,
+        /// Let the solver break the code
+        #[arg(long)]
+        solver: bool,
+    },
+    /// Show the best rated players of the games recorded by mastermind-server
+    #[cfg(feature = "store")]
+    Leaderboard {
+        /// File of the games, a SQLite database with the sqlite feature if
+        /// its extension is .db [default: ~/.local/share/mastermind/games.json]
+        #[arg(long)]
+        db: Option<PathBuf>,
+        /// Number of players shown
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Show the rating, statistics and last games of a player
+    #[cfg(feature = "store")]
+    Stats {
+        /// Name of the player, their IP address for mastermind-server
+        player: String,
+        /// File of the games, a SQLite database with the sqlite feature if
+        /// its extension is .db [default: ~/.local/share/mastermind/games.json]
+        #[arg(long)]
+        db: Option<PathBuf>,
+        /// Number of games shown
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Play in a full-screen terminal interface
+    #[cfg(feature = "tui")]
+    Tui {
+        #[command(flatten)]
+        options: tui::Options,
+        #[command(flatten)]
+        time: TimeArgs,
+    },
+}
+
+impl Command {
+    /// Whether the command draws random codes from the seed.
+    fn draws_codes(&self) -> bool {
+        match self {
+            Command::Play { .. }
+            | Command::Simulate { .. }
+            | Command::Selfplay { .. }
+            | Command::Script { secret: None, .. } => true,
+            #[cfg(feature = "tui")]
+            Command::Tui { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let settings = match config::load(cli.config.as_deref(), cli.profile.as_deref()) {
+        Ok(settings) => settings,
+        Err(err) => {
+            eprintln!("{}", tr!("error", error = err));
+            process::exit(1);
+        }
+    };
+    let settings = Settings {
+        rounds: cli.rules.rounds,
+        colors: cli.rules.colors,
+        duplicates: cli.rules.no_duplicates.then_some(false),
+        theme: cli.theme,
+        strategy: cli.strategy,
+    }
+    .or(settings);
+    let max_round = settings.rounds.unwrap_or(DEFAULT_ROUNDS);
+    let rules = match cli.rules.rules(&settings) {
+        Ok(rules) => rules,
+        Err(err) => {
+            eprintln!("{}", tr!("error", error = input::rules_error(err)));
+            process::exit(1);
+        }
+    };
+    let theme = settings.theme.map_or(Theme::default(), Theme::from);
+    let strategy = settings
+        .strategy
+        .map_or(Strategy::default(), Strategy::from);
+    let seed = cli.seed.unwrap_or_else(rand::random);
+    if cli.seed.is_none() && cli.command.draws_codes() {
+        // So that the games can be played again with --seed.
+        eprintln!("{}", tr!("seed", seed = seed));
+    }
+
+    match cli.command {
+        Command::Play { time, record } => play::run(
+            max_round,
+            rules,
+            theme,
+            time.clock(),
+            seed,
+            record.as_deref(),
+        ),
+        Command::Daily => daily::run(max_round, theme),
+        Command::Tutorial => tutorial::run(theme),
+        Command::Hotseat => play::run_hotseat(max_round, rules, theme),
+        Command::Solve { batch: true, .. } => solve::run_batch(max_round.get(), rules, strategy),
+        Command::Solve {
+            secret: Some(secret),
+            engine,
+            ..
+        } => match rules.check(secret) {
+            Ok(()) => match engine {
+                Some(command) => solve::run_engine(max_round.get(), rules, &command, secret),
+                None => solve::run(max_round.get(), rules, strategy, secret),
+            },
+            Err(err) => {
+                eprintln!("{}", tr!("error", error = input::rules_error(err)));
+                process::exit(1);
+            }
+        },
+        Command::Solve { secret: None, .. } => {
+            solve::run_interactive(max_round.get(), rules, strategy)
+        }
+        Command::Engine => {
+            if let Err(err) = engine::serve(io::stdin().lock(), io::stdout().lock(), strategy) {
+                eprintln!("{}", tr!("error", error = err));
+                process::exit(1);
+            }
+        }
+        Command::Simulate {
+            games,
+            quiet,
+            backend,
+        } => simulate::run(
+            max_round,
+            rules,
+            strategy,
+            backend.into(),
+            games,
+            seed,
+            quiet,
+        ),
+        Command::Difficulty { json: false } => {
+            for difficulty in analysis::difficulties(rules, strategy) {
+                println!("{}", difficulty);
+            }
+        }
+        Command::Difficulty { json: true } => {
+            println!("{}", analysis::report(rules, strategy).to_json());
+        }
+        Command::Selfplay {
+            strategies,
+            maker,
+            games,
+            quiet,
+        } => {
+            let strategies: Vec<Strategy> = strategies.into_iter().map(Strategy::from).collect();
+            selfplay::run(max_round, rules, &strategies, maker, games, seed, quiet)
+        }
+        Command::Analyze { transcript } => match transcript::read(&transcript) {
+            Ok(rounds) => analyze::run(&rounds, rules),
+            Err(err) => {
+                eprintln!("{}", tr!("error", error = err));
+                process::exit(1);
+            }
+        },
+        Command::Script { script, secret } => {
+            let script = secret
+                .map_or(Ok(()), |secret| rules.check(secret))
+                .map_err(input::rules_error)
+                .and_then(|()| script::read(&script));
+            match script {
+                Ok(script) => script::run(&script, max_round.get(), rules, secret, seed),
+                Err(err) => {
+                    eprintln!("{}", tr!("error", error = err));
+                    process::exit(1);
+                }
+            }
+        }
+        Command::Replay {
+            transcript,
+            candidates,
+        } => match transcript::read(&transcript) {
+            Ok(rounds) => replay::run(&rounds, max_round.get(), rules, theme, candidates),
+            Err(err) => {
+                eprintln!("{}", tr!("error", error = err));
+                process::exit(1);
+            }
+        },
+        Command::Rerun { record } => match replay::read_record(&record) {
+            Ok(record) => replay::rerun(record, theme),
+            Err(err) => {
+                eprintln!("{}", tr!("error", error = err));
+                process::exit(1);
+            }
+        },
+        Command::Hint {
+            history,
+            list,
+            limit,
+        } => match hint::read_history(&history) {
+            Ok(rounds) => hint::run(&rounds, rules, strategy, list, limit),
+            Err(err) => {
+                eprintln!("{}", tr!("error", error = err));
+                process::exit(1);
+            }
+        },
+        Command::Bench => bench::run(),
+        Command::Assist => assist::run(rules, strategy),
+        #[cfg(feature = "net")]
+        Command::Connect {
+            address,
+            role,
+            solver,
+        } => {
+            let setup = mastermind::lobby::Setup { max_round, rules };
+            if let Err(err) = remote::run(&address, role, solver, setup, theme, strategy) {
+                eprintln!("{}", tr!("error", error = err));
+                process::exit(1);
+            }
+        }
+        #[cfg(feature = "store")]
+        Command::Leaderboard { db, limit } => {
+            if let Err(err) = leaderboard::run(db, limit) {
+                eprintln!("{}", tr!("error", error = err));
+                process::exit(1);
+            }
+        }
+        #[cfg(feature = "store")]
+        Command::Stats { player, db, limit } => {
+            if let Err(err) = leaderboard::run_stats(db, &player, limit) {
+                eprintln!("{}", tr!("error", error = err));
+                process::exit(1);
+            }
+        }
+        #[cfg(feature = "tui")]
+        Command::Tui { options, time } => {
+            if let Err(err) = tui::run(max_round.get(), rules, theme, strategy, options, time, seed)
+            {
+                eprintln!("{}", tr!("error", error = err));
+                process::exit(1);
+            }
+        }
+    }
+}
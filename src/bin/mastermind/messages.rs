@@ -0,0 +1,158 @@
+//! Catalog of the messages shown to the player.
+//!
+//! Catalogs use a small subset of the Fluent syntax: one `id = message` per
+//! line, indented lines continuing the previous message, `#` comments and
+//! `{ $name }` placeholders. English and French are built in, and picked from
+//! the `LC_ALL`, `LC_MESSAGES` or `LANG` environment variables. Any other
+//! language can be loaded from the file `MASTERMIND_MESSAGES` points to.
+//! Messages missing from a catalog fall back to English.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::sync::OnceLock;
+
+const ENGLISH: &str = include_str!("locales/en.ftl");
+const BUILT_IN: &[(&str, &str)] = &[("fr", include_str!("locales/fr.ftl"))];
+
+/// Formats the message `id`, e.g. `tr!("player-wins", rounds = 4)`.
+macro_rules! tr {
+    ($id:expr) => {
+        $crate::messages::get($id, &[])
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::messages::get($id, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+pub(crate) use tr;
+
+struct Catalog {
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    fn parse(source: &str) -> Result<Self, String> {
+        let mut messages = HashMap::new();
+        let mut last_id: Option<String> = None;
+        for (i, line) in source.lines().enumerate() {
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with(char::is_whitespace) {
+                let id = last_id
+                    .as_ref()
+                    .ok_or_else(|| format!("line {}: continuation without a message", i + 1))?;
+                let message: &mut String = messages.get_mut(id).unwrap();
+                if !message.is_empty() {
+                    message.push('\n');
+                }
+                message.push_str(line.trim());
+                continue;
+            }
+
+            let (id, message) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected 'id = message'", i + 1))?;
+            let id = id.trim().to_string();
+            messages.insert(id.clone(), message.trim().to_string());
+            last_id = Some(id);
+        }
+        Ok(Catalog { messages })
+    }
+
+    fn extend(&mut self, other: Catalog) {
+        self.messages.extend(other.messages);
+    }
+
+    fn format(&self, id: &str, args: &[(&str, String)]) -> String {
+        let mut message = match self.messages.get(id) {
+            Some(message) => message.clone(),
+            None => return id.to_string(),
+        };
+        for (name, value) in args {
+            message = message.replace(&format!("{{ ${} }}", name), value);
+        }
+        message
+    }
+}
+
+fn language() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .map(|value| {
+            value
+                .split(['_', '.', '@'])
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        })
+}
+
+fn load() -> Catalog {
+    let mut catalog = Catalog::parse(ENGLISH).expect("invalid English catalog");
+
+    if let Some(language) = language() {
+        if let Some((_, source)) = BUILT_IN.iter().find(|(name, _)| *name == language) {
+            catalog.extend(Catalog::parse(source).expect("invalid built-in catalog"));
+        }
+    }
+
+    if let Ok(path) = env::var("MASTERMIND_MESSAGES") {
+        match fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|source| Catalog::parse(&source))
+        {
+            Ok(messages) => catalog.extend(messages),
+            Err(err) => eprintln!("{}: {}", path, err),
+        }
+    }
+
+    catalog
+}
+
+pub fn get(id: &str, args: &[(&str, String)]) -> String {
+    static CATALOG: OnceLock<Catalog> = OnceLock::new();
+    CATALOG.get_or_init(load).format(id, args)
+}
+
+#[cfg(test)]
+mod test_messages {
+    use super::*;
+
+    #[test]
+    fn parse_and_format() {
+        let catalog = Catalog::parse(
+            "# comment\n\
+             greeting = Hello { $name }!\n\
+             \n\
+             intro =\n    first line\n    second line\n",
+        )
+        .unwrap();
+        assert_eq!(
+            catalog.format("greeting", &[("name", "Ada".to_string())]),
+            "Hello Ada!"
+        );
+        assert_eq!(catalog.format("intro", &[]), "first line\nsecond line");
+        assert_eq!(catalog.format("missing", &[]), "missing");
+    }
+
+    #[test]
+    fn parse_error() {
+        assert!(Catalog::parse("no equal sign").is_err());
+    }
+
+    #[test]
+    fn built_in_catalogs_are_complete() {
+        let english = Catalog::parse(ENGLISH).unwrap();
+        for (name, source) in BUILT_IN {
+            let catalog = Catalog::parse(source).unwrap();
+            for id in english.messages.keys() {
+                assert!(catalog.messages.contains_key(id), "{} misses {}", name, id);
+            }
+            assert_eq!(catalog.messages.len(), english.messages.len(), "{}", name);
+        }
+    }
+}
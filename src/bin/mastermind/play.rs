@@ -0,0 +1,230 @@
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::process;
+use std::time::Instant;
+
+use mastermind::clock::{Clock, Flag};
+use mastermind::record::{Recorder, Recording};
+
+use mastermind::render::{self, Theme};
+use mastermind::rules::Rules;
+use mastermind::testutil::DeterministicCodeMaker;
+use mastermind::{Code, CodeBreaker, CodeMaker, Game, GameOutcome, RandomCodeMaker, Score};
+
+use crate::input::{parse_allowed_code, parse_code, print_intro, read_line, rules_error};
+use crate::messages::tr;
+
+pub struct HumanCodeBreaker {
+    max_round: usize,
+    rules: Rules,
+    theme: Theme,
+    rounds: Vec<(Code, Score)>,
+    // guess_code only borrows self, so the guess waiting for its score is kept in a cell.
+    guess: Cell<Option<Code>>,
+    // In timed games, the clock and the code revealed when it runs out.
+    clock: RefCell<Option<Clock>>,
+    secret: Option<Code>,
+}
+
+impl HumanCodeBreaker {
+    pub fn new(max_round: usize, rules: Rules, theme: Theme) -> Self {
+        HumanCodeBreaker {
+            max_round,
+            rules,
+            theme,
+            rounds: Vec::new(),
+            guess: Cell::new(None),
+            clock: RefCell::new(None),
+            secret: None,
+        }
+    }
+
+    /// A player carrying on a game after `rounds`, which are shown.
+    #[cfg(feature = "net")]
+    pub fn resumed(
+        max_round: usize,
+        rules: Rules,
+        theme: Theme,
+        rounds: Vec<(Code, Score)>,
+    ) -> Self {
+        if !rounds.is_empty() {
+            println!("{}", render::board(&rounds, max_round, theme));
+        }
+        HumanCodeBreaker {
+            rounds,
+            ..HumanCodeBreaker::new(max_round, rules, theme)
+        }
+    }
+
+    fn timed(mut self, clock: Clock, secret: Code) -> Self {
+        self.clock = RefCell::new(Some(clock));
+        self.secret = Some(secret);
+        self
+    }
+
+    /// Ends the move on the clock, and forfeits the game if it ran out.
+    fn stop_clock(&self) {
+        let flag = match self.clock.borrow_mut().as_mut() {
+            Some(clock) => clock.stop_move(Instant::now()),
+            None => None,
+        };
+        if let (Some(flag), Some(secret)) = (flag, self.secret) {
+            println!("{}", time_up(flag, secret));
+            process::exit(0);
+        }
+    }
+}
+
+impl CodeBreaker for HumanCodeBreaker {
+    fn guess_code(&self) -> Code {
+        if let Some(clock) = self.clock.borrow_mut().as_mut() {
+            clock.start_move(Instant::now());
+        }
+        loop {
+            let mut prompt = tr!(
+                "guess-prompt",
+                round = self.rounds.len() + 1,
+                max_round = self.max_round
+            );
+            if let Some(clock) = self.clock.borrow().as_ref() {
+                prompt = format!("({}) {}", clock_status(clock, Instant::now()), prompt);
+            }
+            let line = read_line(&format!("{} ", prompt));
+            let played = self.rounds.iter().map(|&(played, _)| played);
+            let guess = parse_code(&line).and_then(|code| {
+                let checked = self.rules.check_guess(code, played);
+                checked.map(|()| code).map_err(rules_error)
+            });
+            match guess {
+                Ok(code) => {
+                    self.stop_clock();
+                    self.guess.set(Some(code));
+                    return code;
+                }
+                Err(err) => println!("{}", err),
+            }
+        }
+    }
+
+    fn set_score(&mut self, score: Score) {
+        let guess = self.guess.take().expect("score received before any guess");
+        self.rounds.push((guess, score));
+        println!(
+            "{}",
+            render::board(&self.rounds, self.max_round, self.theme)
+        );
+    }
+
+    fn loses(&mut self) {
+        println!("{}", tr!("player-loses"));
+    }
+
+    fn wins(&mut self, rounds: usize) {
+        println!("{}", tr!("player-wins", rounds = rounds));
+    }
+}
+
+/// The time left on `clock`, for the move and for the game.
+pub fn clock_status(clock: &Clock, now: Instant) -> String {
+    let times: Vec<String> = [clock.move_left(now), clock.total_left(now)]
+        .into_iter()
+        .flatten()
+        .map(render::clock)
+        .collect();
+    tr!("time-left", time = times.join(" / "))
+}
+
+pub fn time_up(flag: Flag, secret: Code) -> String {
+    match flag {
+        Flag::Move => tr!("time-up-move", code = secret),
+        Flag::Total => tr!("time-up-total", code = secret),
+    }
+}
+
+/// Lets the player break a random code, drawn from `seed`, within the time
+/// limits of `clock` if any, and writes the moves to `record` if given.
+pub fn run(
+    max_round: NonZeroUsize,
+    rules: Rules,
+    theme: Theme,
+    clock: Option<Clock>,
+    seed: u64,
+    record: Option<&Path>,
+) {
+    print_intro("play-intro", rules);
+
+    let secret = RandomCodeMaker::seeded(rules, seed).make_code();
+    let recorder = Recorder::new(rules, max_round);
+    let code_maker = Recording::new(DeterministicCodeMaker::new(secret), &recorder);
+    let mut code_breaker = HumanCodeBreaker::new(max_round.get(), rules, theme);
+    if let Some(clock) = clock {
+        code_breaker = code_breaker.timed(clock, secret);
+    }
+    let mut code_breaker = Recording::new(code_breaker, &recorder);
+    Game::new(max_round, &code_maker, &mut code_breaker)
+        .with_rules(rules)
+        .play();
+
+    if let (Some(path), Some(moves)) = (record, recorder.record()) {
+        if let Err(err) = fs::write(path, moves.to_string()) {
+            eprintln!(
+                "{}",
+                tr!("error", error = format!("{}: {}", path.display(), err))
+            );
+            process::exit(1);
+        }
+    }
+}
+
+/// Lets the player break `secret`, and returns the rounds played.
+pub fn break_code(max_round: NonZeroUsize, theme: Theme, secret: Code) -> Vec<(Code, Score)> {
+    let code_maker = DeterministicCodeMaker::new(secret);
+    let mut code_breaker = HumanCodeBreaker::new(max_round.get(), Rules::default(), theme);
+    Game::new(max_round, &code_maker, &mut code_breaker).play();
+    code_breaker.rounds
+}
+
+/// A player who types the code without it being shown, so that the code
+/// breaker can then play on the same machine.
+pub struct HiddenCodeMaker {
+    rules: Rules,
+}
+
+impl HiddenCodeMaker {
+    pub fn new(rules: Rules) -> Self {
+        HiddenCodeMaker { rules }
+    }
+}
+
+impl CodeMaker for HiddenCodeMaker {
+    fn make_code(&self) -> Code {
+        loop {
+            let line = rpassword::prompt_password(format!("{} ", tr!("hotseat-prompt")))
+                .unwrap_or_else(|err| {
+                    eprintln!("{}", tr!("error", error = err));
+                    process::exit(1);
+                });
+            match parse_allowed_code(&line, self.rules) {
+                Ok(code) => {
+                    println!("{}", tr!("hotseat-ready"));
+                    return code;
+                }
+                Err(err) => println!("{}", err),
+            }
+        }
+    }
+}
+
+/// Lets a player enter a hidden code for another player to break.
+pub fn run_hotseat(max_round: NonZeroUsize, rules: Rules, theme: Theme) {
+    let code_maker = HiddenCodeMaker::new(rules);
+    let mut code_breaker = HumanCodeBreaker::new(max_round.get(), rules, theme);
+    let outcome = Game::new(max_round, &code_maker, &mut code_breaker)
+        .with_rules(rules)
+        .play();
+    if let GameOutcome::Lost(code) = outcome {
+        println!("{}", tr!("hotseat-code", code = code));
+    }
+}
@@ -0,0 +1,72 @@
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use crate::messages::tr;
+
+const WIDTH: usize = 30;
+const REFRESH: Duration = Duration::from_millis(100);
+
+/// A progress bar drawn on stderr, with an ETA and the average number of
+/// rounds so far.
+///
+/// Nothing is drawn when hidden or when stderr is not a terminal.
+pub struct Progress {
+    total: usize,
+    visible: bool,
+    start: Instant,
+    last_draw: Option<Instant>,
+}
+
+impl Progress {
+    pub fn new(total: usize, hidden: bool) -> Self {
+        Progress {
+            total,
+            visible: !hidden && io::stderr().is_terminal(),
+            start: Instant::now(),
+            last_draw: None,
+        }
+    }
+
+    /// Updates the bar after `done` games, `average` being the average number
+    /// of rounds of the games won so far.
+    pub fn update(&mut self, done: usize, average: Option<f64>) {
+        if !self.visible {
+            return;
+        }
+        let now = Instant::now();
+        let refresh = self
+            .last_draw
+            .is_none_or(|last| now.duration_since(last) >= REFRESH);
+        if !refresh && done < self.total {
+            return;
+        }
+        self.last_draw = Some(now);
+
+        let filled = WIDTH * done / self.total.max(1);
+        let eta = if done == 0 {
+            "?".to_string()
+        } else {
+            let elapsed = self.start.elapsed().as_secs_f64();
+            let left = elapsed * (self.total - done) as f64 / done as f64;
+            format!("{:.0}s", left)
+        };
+        let average = average.map_or("-".to_string(), |average| format!("{:.3}", average));
+        eprint!(
+            "\r[{}{}] {}/{}  {}",
+            "#".repeat(filled),
+            ".".repeat(WIDTH - filled),
+            done,
+            self.total,
+            tr!("progress", average = average, eta = eta)
+        );
+        io::stderr().flush().ok();
+    }
+
+    /// Clears the bar so that results can be printed.
+    pub fn finish(&self) {
+        if self.visible {
+            eprint!("\r\x1b[2K");
+            io::stderr().flush().ok();
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::thread;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use mastermind::auth::Token;
+use mastermind::lobby::{SessionId, Setup};
+use mastermind::net::{self, Connection, Error, Feature, Outcome, Resumption, Role};
+use mastermind::render::Theme;
+use mastermind::solver::{Solver, Strategy};
+
+use crate::messages::tr;
+use crate::play::{HiddenCodeMaker, HumanCodeBreaker};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum RoleArg {
+    /// Choose the code
+    Maker,
+    /// Break the code
+    Breaker,
+}
+
+#[cfg(feature = "ws")]
+fn connect(address: &str) -> Result<Connection, Error> {
+    if address.starts_with("ws://") {
+        Connection::connect_websocket(address)
+    } else {
+        Connection::connect(address)
+    }
+}
+
+#[cfg(not(feature = "ws"))]
+fn connect(address: &str) -> Result<Connection, Error> {
+    Connection::connect(address)
+}
+
+/// Connects to the server at `address`, and returns the features it
+/// announced, or `None` if it only speaks version 1 of the protocol.
+fn handshake(address: &str) -> Result<(Connection, Option<Vec<Feature>>), Error> {
+    let mut connection = connect(address)?;
+    match net::hello(&mut connection, &Feature::all()) {
+        Ok((_, features)) => Ok((connection, Some(features))),
+        // The server did not understand, and closed the connection.
+        Err(Error::Protocol(_)) => Ok((connect(address)?, None)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Attempts at resuming a game once the connection to the server is lost.
+const RECONNECTS: usize = 5;
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// Resumes a game on a new connection to `address`.
+fn reconnect(
+    address: &str,
+    session: SessionId,
+    token: Token,
+) -> Result<(Connection, Resumption), Error> {
+    let mut attempts = 1;
+    loop {
+        thread::sleep(RECONNECT_DELAY);
+        let resumed = connect(address).and_then(|mut connection| {
+            let resumption = net::resume(&mut connection, session, token)?;
+            Ok((connection, resumption))
+        });
+        match resumed {
+            Err(Error::Closed | Error::Io(_)) if attempts < RECONNECTS => attempts += 1,
+            resumed => return resumed,
+        }
+    }
+}
+
+/// Joins a game hosted by `mastermind-server` at `address`, against a
+/// player asking for the same rules, and resumes it if the connection is
+/// lost and the server allows it.
+///
+/// The code breaker is the player, or the solver if `solver` is true.
+pub fn run(
+    address: &str,
+    role: RoleArg,
+    solver: bool,
+    setup: Setup,
+    theme: Theme,
+    strategy: Strategy,
+) -> Result<(), Error> {
+    let (mut connection, features) = handshake(address)?;
+    if let Some(features) = &features {
+        net::supports(features, &setup)?;
+    }
+    let resumable = features.is_some_and(|features| features.contains(&Feature::Resume));
+    println!("{}", tr!("remote-waiting"));
+    let joined = match role {
+        RoleArg::Maker => Role::Maker,
+        RoleArg::Breaker => Role::Breaker,
+    };
+    let (max_round, session, token) = net::join(&mut connection, joined, Some(setup))?;
+    println!(
+        "{}",
+        tr!("remote-started", session = session, token = token)
+    );
+    let rules = setup.rules;
+    let play = |connection: &mut Connection, resumption: Option<Resumption>| match resumption {
+        None => match role {
+            RoleArg::Maker => net::play_maker(connection, &HiddenCodeMaker::new(rules)),
+            RoleArg::Breaker if solver => {
+                let mut solver = Solver::with_rules(rules, strategy);
+                net::play_breaker(connection, &mut solver, max_round, token)
+            }
+            RoleArg::Breaker => {
+                let mut player = HumanCodeBreaker::new(max_round.get(), rules, theme);
+                net::play_breaker(connection, &mut player, max_round, token)
+            }
+        },
+        Some(resumption) => match role {
+            RoleArg::Maker => {
+                net::resume_maker(connection, &HiddenCodeMaker::new(rules), &resumption)
+            }
+            RoleArg::Breaker if solver => {
+                let mut solver = Solver::with_rules(rules, strategy);
+                for &(guess, score) in &resumption.rounds {
+                    solver.record(guess, score);
+                }
+                net::resume_breaker(connection, &mut solver, resumption, token)
+            }
+            RoleArg::Breaker => {
+                let rounds = resumption.rounds.clone();
+                let mut player = HumanCodeBreaker::resumed(max_round.get(), rules, theme, rounds);
+                net::resume_breaker(connection, &mut player, resumption, token)
+            }
+        },
+    };
+
+    let mut resumption = None;
+    let outcome = loop {
+        match play(&mut connection, resumption.take()) {
+            Err(err @ (Error::Closed | Error::Io(_))) if resumable => {
+                println!("{}", tr!("remote-reconnecting", error = err));
+                let resumed = reconnect(address, session, token)?;
+                connection = resumed.0;
+                println!("{}", tr!("remote-resumed", rounds = resumed.1.rounds.len()));
+                if let Some(time) = resumed.1.move_time {
+                    println!("{}", tr!("remote-move-time", seconds = time.as_secs()));
+                }
+                resumption = Some(resumed.1);
+            }
+            outcome => break outcome?,
+        }
+    };
+    match outcome {
+        Outcome::Won(rounds) => println!("{}", tr!("remote-won", rounds = rounds)),
+        Outcome::Lost(code) => println!("{}", tr!("remote-lost", code = code)),
+        _ => println!("{}", tr!("remote-over")),
+    }
+    Ok(())
+}
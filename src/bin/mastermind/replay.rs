@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::Path;
+
+use mastermind::record::{Record, Replay};
+use mastermind::render::{self, Theme};
+use mastermind::rules::Rules;
+use mastermind::solver::{Solver, Strategy};
+
+use crate::input::read_line;
+use crate::messages::tr;
+use crate::transcript::Round;
+
+/// Steps through a transcript, showing the board after each round.
+pub fn run(rounds: &[Round], max_round: usize, rules: Rules, theme: Theme, show_candidates: bool) {
+    // remaining[i] is the number of codes still possible after i rounds.
+    let mut solver = Solver::with_rules(rules, Strategy::default());
+    let mut remaining = vec![solver.candidates().len()];
+    for (guess, score) in rounds {
+        solver.record(*guess, *score);
+        remaining.push(solver.candidates().len());
+    }
+
+    let mut shown = 0;
+    loop {
+        println!(
+            "{}",
+            render::board(&rounds[..shown], max_round.max(rounds.len()), theme)
+        );
+        println!(
+            "{}",
+            tr!("replay-position", round = shown, total = rounds.len())
+        );
+        if show_candidates {
+            println!("{}", tr!("replay-candidates", count = remaining[shown]));
+        }
+
+        let line = read_line(&format!("{} ", tr!("replay-prompt")));
+        match line.trim() {
+            "" | "n" => shown = (shown + 1).min(rounds.len()),
+            "p" => shown = shown.saturating_sub(1),
+            "q" => return,
+            command => {
+                let round = command.strip_prefix('j').unwrap_or(command).trim();
+                match round.parse::<usize>() {
+                    Ok(round) if round <= rounds.len() => shown = round,
+                    _ => println!("{}", tr!("replay-invalid", command = command)),
+                }
+            }
+        }
+    }
+}
+
+/// Reads a record written by `play --record`.
+pub fn read_record(path: &Path) -> Result<Record, String> {
+    fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|content| content.parse::<Record>().map_err(|err| err.to_string()))
+        .map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+/// Plays the moves of `record` again, then steps through the rounds.
+pub fn rerun(record: Record, theme: Theme) {
+    let (max_round, rules) = (record.max_round, record.rules);
+    let mut replay = Replay::new(record);
+    replay.play();
+    run(replay.rounds(), max_round.get(), rules, theme, false);
+}
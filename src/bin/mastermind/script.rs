@@ -0,0 +1,192 @@
+//! Non-interactive games: guesses are read from a script and every step of
+//! the game is printed as a JSON object on its own line, for other programs
+//! to parse.
+//!
+//! Error messages in events are not translated.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use mastermind::rules::Rules;
+use mastermind::solver::Solver;
+use mastermind::{Code, CodeMaker, ParseCodeError, RandomCodeMaker, Scorer, SIZE};
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+enum Event {
+    Start {
+        rounds: usize,
+        pegs: usize,
+        colors: usize,
+        duplicates: bool,
+        /// Whether a guess played twice is invalid.
+        strict: bool,
+    },
+    Round {
+        round: usize,
+        guess: String,
+        matched: usize,
+        present: usize,
+        // Codes still consistent with all the scores.
+        candidates: usize,
+    },
+    /// A line of the script which is not a valid guess, and is skipped.
+    Invalid {
+        line: usize,
+        error: String,
+    },
+    Won {
+        rounds: usize,
+    },
+    Lost {
+        code: String,
+    },
+    /// The script ended before the game.
+    Unfinished {
+        rounds: usize,
+    },
+}
+
+fn emit(event: Event) {
+    println!(
+        "{}",
+        serde_json::to_string(&event).expect("events are always serializable")
+    );
+}
+
+fn parse_guess(line: &str, rules: Rules, played: &[Code]) -> Result<Code, String> {
+    let guess: Code = line
+        .parse()
+        .map_err(|err: ParseCodeError| err.to_string())?;
+    rules
+        .check_guess(guess, played.iter().copied())
+        .map_err(|err| err.to_string())?;
+    Ok(guess)
+}
+
+/// Reads the script at `path`, or stdin if `path` is `-`.
+pub fn read(path: &Path) -> Result<String, String> {
+    if path == Path::new("-") {
+        let mut script = String::new();
+        io::stdin()
+            .read_to_string(&mut script)
+            .map_err(|err| err.to_string())?;
+        Ok(script)
+    } else {
+        fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+}
+
+/// Plays the guesses of `script`, one per line, against `secret` or a random
+/// code drawn from `seed`.
+///
+/// Blank lines and lines starting with `#` are ignored.
+pub fn run(script: &str, max_round: usize, rules: Rules, secret: Option<Code>, seed: u64) {
+    let secret = secret.unwrap_or_else(|| RandomCodeMaker::seeded(rules, seed).make_code());
+    let scorer = Scorer::new(secret);
+    let mut solver = Solver::with_rules(rules, Default::default());
+    emit(Event::Start {
+        rounds: max_round,
+        pegs: SIZE,
+        colors: rules.colors(),
+        duplicates: rules.duplicates(),
+        strict: rules.strict(),
+    });
+
+    let mut played = Vec::new();
+    for (i, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let guess = match parse_guess(line, rules, &played) {
+            Ok(guess) => guess,
+            Err(error) => {
+                emit(Event::Invalid { line: i + 1, error });
+                continue;
+            }
+        };
+
+        played.push(guess);
+        let round = played.len();
+        let score = scorer.score(guess);
+        solver.record(guess, score);
+        emit(Event::Round {
+            round,
+            guess: guess.to_string(),
+            matched: score.matches(),
+            present: score.presents(),
+            candidates: solver.candidates().len(),
+        });
+        if score.matches() == SIZE {
+            emit(Event::Won { rounds: round });
+            return;
+        }
+        if round == max_round {
+            emit(Event::Lost {
+                code: secret.to_string(),
+            });
+            return;
+        }
+    }
+    emit(Event::Unfinished {
+        rounds: played.len(),
+    });
+}
+
+#[cfg(test)]
+mod test_script {
+    use super::*;
+
+    #[test]
+    fn events() {
+        let event = Event::Round {
+            round: 1,
+            guess: "AABB".to_string(),
+            matched: 1,
+            present: 0,
+            candidates: 256,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"round","round":1,"guess":"AABB","matched":1,"present":0,"candidates":256}"#
+        );
+        let event = Event::Start {
+            rounds: 10,
+            pegs: 4,
+            colors: 6,
+            duplicates: true,
+            strict: true,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"start","rounds":10,"pegs":4,"colors":6,"duplicates":true,"strict":true}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Event::Won { rounds: 3 }).unwrap(),
+            r#"{"event":"won","rounds":3}"#
+        );
+    }
+
+    #[test]
+    fn invalid_guesses() {
+        let rules = Rules::new(4, false).unwrap();
+        assert!(parse_guess("ABCD", rules, &[]).is_ok());
+        assert_eq!(
+            parse_guess("ABC", rules, &[]).unwrap_err(),
+            "expected 4 pegs, got 3"
+        );
+        assert_eq!(
+            parse_guess("ABCA", rules, &[]).unwrap_err(),
+            "peg 'A' is repeated"
+        );
+        let played = ["ABCD".parse().unwrap()];
+        assert!(parse_guess("ABCD", rules, &played).is_ok());
+        assert_eq!(
+            parse_guess("ABCD", rules.with_strict(true), &played).unwrap_err(),
+            "guess ABCD was already played"
+        );
+    }
+}
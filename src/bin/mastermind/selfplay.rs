@@ -0,0 +1,152 @@
+use std::cell::RefCell;
+use std::num::NonZeroUsize;
+use std::process;
+
+use clap::ValueEnum;
+use mastermind::rules::Rules;
+use mastermind::solver::{Backend, Strategy};
+use mastermind::testutil::DeterministicCodeMaker;
+use mastermind::{Code, CodeMaker, RandomCodeMaker};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::input::rules_error;
+use crate::messages::tr;
+use crate::progress::Progress;
+use crate::simulate;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Maker {
+    /// Any code, uniformly at random
+    Random,
+    /// A random code without repeated pegs, like many human players
+    Biased,
+    /// One of the codes the code breaker needs the most rounds to break
+    Adversarial,
+}
+
+/// Makes one of the codes a (deterministic) strategy needs the most rounds
+/// to break, among a sample of them.
+struct AdversarialCodeMaker {
+    hardest: Vec<Code>,
+    rng: RefCell<StdRng>,
+}
+
+impl AdversarialCodeMaker {
+    fn new(rules: Rules, strategy: Strategy, max_round: NonZeroUsize, seed: u64) -> Self {
+        // Every code is played, for which looking the scores up is faster.
+        let codes = rules.codes();
+        let mut progress = Progress::new(codes.len(), true);
+        let stats = simulate::play_all(
+            codes.len(),
+            max_round,
+            seed,
+            &mut progress,
+            |i| codes[i],
+            |code| {
+                simulate::play(
+                    rules,
+                    strategy,
+                    Backend::Table,
+                    max_round,
+                    &DeterministicCodeMaker::new(code),
+                )
+            },
+        );
+        AdversarialCodeMaker {
+            hardest: stats.hardest().to_vec(),
+            rng: RefCell::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl CodeMaker for AdversarialCodeMaker {
+    fn make_code(&self) -> Code {
+        *self.hardest.choose(&mut *self.rng.borrow_mut()).unwrap()
+    }
+}
+
+pub fn strategy_name(strategy: Strategy) -> &'static str {
+    match strategy {
+        Strategy::FirstCandidate => "first-candidate",
+        Strategy::Minimax => "minimax",
+    }
+}
+
+/// Plays `games` games of each strategy against the code maker and compares them.
+///
+/// Random and biased makers give the same codes to every strategy, and every
+/// maker draws its codes from `seed`.
+pub fn run(
+    max_round: NonZeroUsize,
+    rules: Rules,
+    strategies: &[Strategy],
+    maker: Maker,
+    games: usize,
+    seed: u64,
+    quiet: bool,
+) {
+    let secrets: Vec<Code> = match maker {
+        Maker::Random => {
+            let code_maker = RandomCodeMaker::seeded(rules, seed);
+            (0..games).map(|_| code_maker.make_code()).collect()
+        }
+        Maker::Biased => {
+            let distinct = Rules::new(rules.colors(), false).unwrap_or_else(|err| {
+                eprintln!("{}", tr!("error", error = rules_error(err)));
+                process::exit(1);
+            });
+            let code_maker = RandomCodeMaker::seeded(distinct, seed);
+            (0..games).map(|_| code_maker.make_code()).collect()
+        }
+        Maker::Adversarial => Vec::new(),
+    };
+
+    let mut results = Vec::with_capacity(strategies.len());
+    for &strategy in strategies {
+        let secrets = match maker {
+            Maker::Adversarial => {
+                let code_maker = AdversarialCodeMaker::new(rules, strategy, max_round, seed);
+                (0..games).map(|_| code_maker.make_code()).collect()
+            }
+            Maker::Random | Maker::Biased => secrets.clone(),
+        };
+
+        let mut progress = Progress::new(games, quiet);
+        let stats = simulate::play_all(
+            secrets.len(),
+            max_round,
+            seed,
+            &mut progress,
+            |i| secrets[i],
+            |secret| {
+                simulate::play(
+                    rules,
+                    strategy,
+                    Backend::default(),
+                    max_round,
+                    &DeterministicCodeMaker::new(secret),
+                )
+            },
+        );
+        progress.finish();
+        results.push((strategy, stats));
+    }
+
+    println!("{}", tr!("selfplay-header"));
+    for (strategy, stats) in results {
+        println!(
+            "{:<16} {:>6} {:>6} {:>8} {:>6}",
+            strategy_name(strategy),
+            stats.games(),
+            stats.lost(),
+            stats
+                .average()
+                .map_or("-".to_string(), |average| format!("{:.3}", average)),
+            stats
+                .worst()
+                .map_or("-".to_string(), |worst| worst.to_string())
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use mastermind::rules::Rules;
+use mastermind::solver::{Backend, Solver, Strategy};
+use mastermind::testutil::DeterministicCodeMaker;
+use mastermind::{Code, CodeBreaker, CodeMaker, Game, RandomCodeMaker, Score};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::messages::tr;
+use crate::progress::Progress;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Wraps the solver to learn the number of rounds it won in.
+struct CountingSolver {
+    solver: Solver,
+    won_in: Option<usize>,
+}
+
+impl CountingSolver {
+    fn new(rules: Rules, strategy: Strategy, backend: Backend) -> Self {
+        CountingSolver {
+            solver: Solver::with_backend(rules, strategy, backend),
+            won_in: None,
+        }
+    }
+}
+
+impl CodeBreaker for CountingSolver {
+    fn guess_code(&self) -> Code {
+        self.solver.guess_code()
+    }
+
+    fn set_score(&mut self, score: Score) {
+        self.solver.set_score(score);
+    }
+
+    fn loses(&mut self) {
+        self.solver.loses();
+    }
+
+    fn wins(&mut self, rounds: usize) {
+        self.won_in = Some(rounds);
+    }
+}
+
+/// Plays a game against `code_maker` and returns the number of rounds the
+/// solver needed, or `None` if it lost.
+pub fn play<T: CodeMaker>(
+    rules: Rules,
+    strategy: Strategy,
+    backend: Backend,
+    max_round: NonZeroUsize,
+    code_maker: &T,
+) -> Option<usize> {
+    let mut solver = CountingSolver::new(rules, strategy, backend);
+    Game::new(max_round, code_maker, &mut solver)
+        .with_rules(rules)
+        .play();
+    solver.won_in
+}
+
+/// Plays `games` games, the `i`-th with `play(secret(i))`, and gathers their
+/// statistics as they end, so that any number of games fits in memory.
+///
+/// With the `rayon` feature, the games are played on every core.
+/// `progress` counts the games as they end, and `seed` samples the hardest
+/// codes kept (see `Stats::hardest`).
+pub fn play_all(
+    games: usize,
+    max_round: NonZeroUsize,
+    seed: u64,
+    progress: &mut Progress,
+    secret: impl Fn(usize) -> Code + Sync,
+    play: impl Fn(Code) -> Option<usize> + Sync,
+) -> Stats {
+    let ended = Mutex::new((Stats::new(max_round.get(), seed), progress));
+    let play = |i| {
+        let secret = secret(i);
+        let rounds = play(secret);
+        let mut ended = ended.lock().unwrap();
+        let (stats, progress) = &mut *ended;
+        stats.add(secret, rounds);
+        progress.update(stats.games(), stats.average());
+    };
+    #[cfg(feature = "rayon")]
+    (0..games).into_par_iter().for_each(play);
+    #[cfg(not(feature = "rayon"))]
+    (0..games).for_each(play);
+    let (stats, _) = ended.into_inner().unwrap();
+    stats
+}
+
+/// The number of codes `Stats` keeps among the hardest ones.
+const HARDEST: usize = 8;
+
+/// Number of rounds needed to win a series of games, gathered as they end.
+pub struct Stats {
+    // rounds_needed[n] counts the games won in n rounds.
+    rounds_needed: Vec<usize>,
+    total_rounds: usize,
+    /// The sum of the squares of the rounds, for their variance.
+    total_squares: usize,
+    lost: usize,
+    /// Codes drawn uniformly among the hardest ones: lost, or else won in
+    /// the most rounds.
+    hardest: Vec<Code>,
+    /// The number of codes as hard as those of `hardest`.
+    hardest_games: usize,
+    /// Samples the hardest codes. The games ending in the same order, they
+    /// are the same for the same seed.
+    rng: StdRng,
+}
+
+impl Stats {
+    pub fn new(max_round: usize, seed: u64) -> Self {
+        Stats {
+            rounds_needed: vec![0; max_round + 1],
+            total_rounds: 0,
+            total_squares: 0,
+            lost: 0,
+            hardest: Vec::new(),
+            hardest_games: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Adds a game against `secret`, won in `rounds` or lost if `None`.
+    pub fn add(&mut self, secret: Code, rounds: Option<usize>) {
+        // Lost games are harder than any won game.
+        let hardness = |rounds: Option<usize>| rounds.unwrap_or(usize::MAX);
+        let hardest = (self.lost > 0).then_some(None).or(self.worst().map(Some));
+        match hardest.map(hardness) {
+            Some(hardest) if hardness(rounds) < hardest => {}
+            Some(hardest) if hardness(rounds) == hardest => {
+                // Reservoir sampling, which keeps every code as likely.
+                self.hardest_games += 1;
+                if self.hardest.len() < HARDEST {
+                    self.hardest.push(secret);
+                } else {
+                    let i = self.rng.gen_range(0..self.hardest_games);
+                    if let Some(code) = self.hardest.get_mut(i) {
+                        *code = secret;
+                    }
+                }
+            }
+            _ => {
+                self.hardest = vec![secret];
+                self.hardest_games = 1;
+            }
+        }
+
+        match rounds {
+            Some(rounds) => {
+                self.rounds_needed[rounds] += 1;
+                self.total_rounds += rounds;
+                self.total_squares += rounds * rounds;
+            }
+            None => self.lost += 1,
+        }
+    }
+
+    pub fn games(&self) -> usize {
+        self.won() + self.lost
+    }
+
+    pub fn won(&self) -> usize {
+        self.rounds_needed.iter().sum()
+    }
+
+    pub fn lost(&self) -> usize {
+        self.lost
+    }
+
+    /// Average number of rounds of the games won.
+    pub fn average(&self) -> Option<f64> {
+        let won = self.won();
+        (won > 0).then(|| self.total_rounds as f64 / won as f64)
+    }
+
+    /// Standard deviation of the number of rounds of the games won.
+    pub fn std_dev(&self) -> Option<f64> {
+        let won = self.won() as f64;
+        let average = self.average()?;
+        Some(
+            (self.total_squares as f64 / won - average * average)
+                .max(0.0)
+                .sqrt(),
+        )
+    }
+
+    /// Largest number of rounds needed to win a game.
+    pub fn worst(&self) -> Option<usize> {
+        self.rounds_needed.iter().rposition(|&count| count > 0)
+    }
+
+    /// Some of the hardest codes: lost, or else won in `worst` rounds.
+    pub fn hardest(&self) -> &[Code] {
+        &self.hardest
+    }
+
+    /// Number of games won for each number of rounds, skipping zeros.
+    pub fn distribution(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.rounds_needed
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|&(_, count)| count > 0)
+    }
+}
+
+/// Plays the solver against every code the rules allow, or against `games`
+/// random codes drawn from `seed`.
+pub fn run(
+    max_round: NonZeroUsize,
+    rules: Rules,
+    strategy: Strategy,
+    backend: Backend,
+    games: Option<usize>,
+    seed: u64,
+    quiet: bool,
+) {
+    // Random codes are drawn as they are played, each game from its own
+    // seed so that the codes do not depend on the order of the games.
+    let codes = games.is_none().then(|| rules.codes());
+    let games = games.unwrap_or_else(|| codes.as_ref().map_or(0, Vec::len));
+    let secret = |i: usize| match &codes {
+        Some(codes) => codes[i],
+        None => RandomCodeMaker::seeded(rules, seed.wrapping_add(i as u64)).make_code(),
+    };
+
+    let mut progress = Progress::new(games, quiet);
+    let stats = play_all(games, max_round, seed, &mut progress, secret, |secret| {
+        play(
+            rules,
+            strategy,
+            backend,
+            max_round,
+            &DeterministicCodeMaker::new(secret),
+        )
+    });
+    progress.finish();
+
+    println!("{}", tr!("simulate-games", games = stats.games()));
+    println!("{}", tr!("simulate-lost", lost = stats.lost()));
+    if let (Some(average), Some(worst)) = (stats.average(), stats.worst()) {
+        println!(
+            "{}",
+            tr!("simulate-average", average = format!("{:.3}", average))
+        );
+        if let Some(std_dev) = stats.std_dev() {
+            println!(
+                "{}",
+                tr!("simulate-std-dev", std_dev = format!("{:.3}", std_dev))
+            );
+        }
+        println!("{}", tr!("simulate-worst", worst = worst));
+    }
+    let hardest: Vec<String> = stats.hardest().iter().map(Code::to_string).collect();
+    if !hardest.is_empty() {
+        println!("{}", tr!("simulate-hardest", codes = hardest.join(" ")));
+    }
+    println!();
+    println!("{}", tr!("simulate-header"));
+    for (rounds, count) in stats.distribution() {
+        println!("{:<6}  {}", rounds, count);
+    }
+}
@@ -0,0 +1,145 @@
+use std::io::{self, BufRead};
+use std::process;
+
+use mastermind::engine::EngineCodeBreaker;
+use mastermind::rules::Rules;
+use mastermind::solver::{Solver, Strategy};
+use mastermind::{Code, CodeBreaker, Scorer, SIZE};
+
+use crate::input::{parse_allowed_code, parse_score, print_intro, read_line};
+use crate::messages::tr;
+use crate::transcript::Round;
+
+/// Plays `code_breaker` against `secret`, and returns the rounds it played.
+fn solve<U: CodeBreaker>(max_round: usize, code_breaker: &mut U, secret: Code) -> Vec<Round> {
+    let scorer = Scorer::new(secret);
+    let mut rounds = Vec::new();
+    for _round in 0..max_round {
+        let guess = code_breaker.guess_code();
+        if code_breaker.forfeited() {
+            break;
+        }
+        let score = scorer.score(guess);
+        rounds.push((guess, score));
+
+        code_breaker.set_score(score);
+        if score.matches() == SIZE {
+            break;
+        }
+    }
+    rounds
+}
+
+fn is_solved(rounds: &[Round]) -> bool {
+    rounds
+        .last()
+        .is_some_and(|(_, score)| score.matches() == SIZE)
+}
+
+fn print(max_round: usize, rounds: &[Round]) {
+    for (guess, score) in rounds {
+        println!("{} {} {}", guess, score.matches(), score.presents());
+    }
+    if !is_solved(rounds) {
+        println!("{}", tr!("not-solved", rounds = max_round));
+    }
+}
+
+/// Prints the guesses the solver plays to break `secret`.
+pub fn run(max_round: usize, rules: Rules, strategy: Strategy, secret: Code) {
+    let mut solver = Solver::with_rules(rules, strategy);
+    print(max_round, &solve(max_round, &mut solver, secret));
+}
+
+/// Prints the guesses the engine started by `command` plays to break
+/// `secret`.
+pub fn run_engine(max_round: usize, rules: Rules, command: &str, secret: Code) {
+    let mut engine = EngineCodeBreaker::spawn(command, rules).unwrap_or_else(|err| {
+        let err = format!("{}: {}", command, err);
+        eprintln!("{}", tr!("error", error = err));
+        process::exit(1);
+    });
+    print(max_round, &solve(max_round, &mut engine, secret));
+    if let Some(err) = engine.take_error() {
+        let err = format!("{}: {}", command, err);
+        eprintln!("{}", tr!("error", error = err));
+        process::exit(1);
+    }
+}
+
+/// Solves each code read from stdin, one per line, and prints
+/// `<code> <rounds> <guess>...`, with `-` as the number of rounds if the code
+/// was not solved.
+///
+/// Invalid lines are reported on stderr and skipped; the process then exits
+/// with an error once all the codes are solved.
+pub fn run_batch(max_round: usize, rules: Rules, strategy: Strategy) {
+    let mut failed = false;
+    for (i, line) in io::stdin().lock().lines().enumerate() {
+        let line = line.unwrap_or_else(|err| {
+            eprintln!("{}", tr!("error", error = err));
+            process::exit(1);
+        });
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let secret = match parse_allowed_code(line, rules) {
+            Ok(secret) => secret,
+            Err(err) => {
+                let err = tr!("line-error", line = i + 1, error = err);
+                eprintln!("{}", tr!("error", error = err));
+                failed = true;
+                continue;
+            }
+        };
+
+        let rounds = solve(max_round, &mut Solver::with_rules(rules, strategy), secret);
+        let count = if is_solved(&rounds) {
+            rounds.len().to_string()
+        } else {
+            "-".to_string()
+        };
+        let guesses: Vec<String> = rounds.iter().map(|(guess, _)| guess.to_string()).collect();
+        println!("{} {} {}", secret, count, guesses.join(" "));
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// Lets the player score the solver's guesses for a code they keep secret.
+pub fn run_interactive(max_round: usize, rules: Rules, strategy: Strategy) {
+    print_intro("solve-intro", rules);
+
+    let mut solver = Solver::with_rules(rules, strategy);
+    for round in 1..=max_round {
+        let guess = solver.guess_code();
+        let score = loop {
+            let line = read_line(&format!(
+                "{} ",
+                tr!(
+                    "score-prompt",
+                    round = round,
+                    max_round = max_round,
+                    guess = guess
+                )
+            ));
+            match parse_score(&line) {
+                Ok(score) => break score,
+                Err(err) => println!("{}", err),
+            }
+        };
+
+        solver.set_score(score);
+        if score.matches() == SIZE {
+            println!("{}", tr!("solver-wins", rounds = round));
+            return;
+        }
+        if solver.candidates().is_empty() {
+            println!("{}", tr!("contradictory-scores"));
+            return;
+        }
+    }
+    println!("{}", tr!("solver-loses"));
+}
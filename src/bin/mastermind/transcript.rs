@@ -0,0 +1,158 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+
+use mastermind::{Code, Score};
+
+use crate::input::{parse_code, parse_score};
+use crate::messages::tr;
+
+/// One round of a game: the guess and the score it got.
+pub type Round = (Code, Score);
+
+/// Reads a transcript file.
+///
+/// Each round is written on its own line as `<guess> <matched> <present>`,
+/// e.g. `AABB 1 0`. Blank lines and lines starting with `#` are ignored.
+/// An `http://` URL, such as the `/replays/{id}/transcript` of a server, is
+/// downloaded.
+pub fn read(path: &Path) -> Result<Vec<Round>, String> {
+    let content = match path.to_str().and_then(|path| path.strip_prefix("http://")) {
+        Some(url) => download(url),
+        None => fs::read_to_string(path).map_err(|err| err.to_string()),
+    }
+    .map_err(|err| format!("{}: {}", path.display(), err))?;
+    parse(&content).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+/// How long downloads wait to connect, and then for each read or write.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Gets `http://<url>` from a plain HTTP server, without TLS.
+fn download(url: &str) -> Result<String, String> {
+    let (host, path) = match url.split_once('/') {
+        Some((host, path)) => (host, format!("/{}", path)),
+        None => (url, "/".to_string()),
+    };
+    let address = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:80", host)
+    };
+    let mut response = Vec::new();
+    connect(&address)
+        .and_then(|mut stream| {
+            stream.set_read_timeout(Some(TIMEOUT))?;
+            stream.set_write_timeout(Some(TIMEOUT))?;
+            write!(stream, "GET {} HTTP/1.0\r\nHost: {}\r\n\r\n", path, host)?;
+            stream.read_to_end(&mut response)
+        })
+        .map_err(|err| err.to_string())?;
+    body(&response)
+}
+
+/// Connects to the first address `address` resolves to which answers.
+fn connect(address: &str) -> io::Result<TcpStream> {
+    let mut error = io::Error::new(io::ErrorKind::NotFound, address.to_string());
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => error = err,
+        }
+    }
+    Err(error)
+}
+
+/// The body of a successful HTTP response, decoding chunked bodies.
+fn body(response: &[u8]) -> Result<String, String> {
+    let response = String::from_utf8_lossy(response);
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| tr!("invalid-response"))?;
+    let mut lines = head.lines();
+    let status = lines.next().unwrap_or_default();
+    let mut fields = status.split_whitespace();
+    if !fields
+        .next()
+        .is_some_and(|version| version.starts_with("HTTP/1."))
+    {
+        return Err(tr!("invalid-response"));
+    }
+    let chunked = lines.any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("transfer-encoding")
+                && value.trim().eq_ignore_ascii_case("chunked")
+        })
+    });
+    let body = if chunked {
+        unchunk(body).ok_or_else(|| tr!("invalid-response"))?
+    } else {
+        body.to_string()
+    };
+    if fields.next() != Some("200") {
+        return Err(tr!("download-failed", status = status, error = body.trim()));
+    }
+    Ok(body)
+}
+
+/// Joins the chunks of a chunked body, or `None` if it is cut short.
+fn unchunk(mut body: &str) -> Option<String> {
+    let mut joined = String::new();
+    loop {
+        let (size, rest) = body.split_once("\r\n")?;
+        let size = size.split(';').next()?.trim();
+        let size = usize::from_str_radix(size, 16).ok()?;
+        if size == 0 {
+            return Some(joined);
+        }
+        joined.push_str(rest.get(..size)?);
+        body = rest.get(size..)?.strip_prefix("\r\n")?;
+    }
+}
+
+pub fn parse(content: &str) -> Result<Vec<Round>, String> {
+    let mut rounds = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        rounds.push(parse_round(line).map_err(|err| tr!("line-error", line = i + 1, error = err))?);
+    }
+    Ok(rounds)
+}
+
+pub fn parse_round(line: &str) -> Result<Round, String> {
+    let (guess, score) = line
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| tr!("expected-round"))?;
+    let guess = parse_code(guess)?;
+    let score = parse_score(score)?;
+    Ok((guess, score))
+}
+
+#[cfg(test)]
+mod test_transcript {
+    use super::*;
+
+    #[test]
+    fn reads_responses() {
+        let response = b"HTTP/1.0 200 OK\r\nContent-Type: text/plain\r\n\r\nAABB 1 0\n";
+        assert_eq!(body(response), Ok("AABB 1 0\n".to_string()));
+
+        let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+            5\r\nAABB \r\n4;ext\r\n1 0\n\r\n0\r\n\r\n";
+        assert_eq!(body(response), Ok("AABB 1 0\n".to_string()));
+
+        for response in [
+            &b"HTTP/1.0 404 Not Found\r\n\r\nno such replay"[..],
+            b"HTTP/1.0 200 OK\r\n",
+            b"SSH-2.0-OpenSSH\r\n\r\n",
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n9\r\nAABB",
+        ] {
+            assert!(body(response).is_err());
+        }
+    }
+}
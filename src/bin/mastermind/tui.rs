@@ -0,0 +1,446 @@
+use std::io;
+use std::mem;
+use std::time::{Duration, Instant};
+
+use clap::{Args, ValueEnum};
+use mastermind::clock::Clock;
+use mastermind::render::{self, Theme};
+use mastermind::rules::Rules;
+use mastermind::solver::{Solver, Strategy};
+use mastermind::{Code, CodeBreaker, CodeMaker, CodePeg, RandomCodeMaker, Score, Scorer, SIZE};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::input::{parse_score, rules_error};
+use crate::messages::tr;
+use crate::play::{clock_status, time_up};
+use crate::TimeArgs;
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Mode {
+    /// Break a random code
+    Breaker,
+    /// Score the solver's guesses for a code you keep secret
+    Maker,
+}
+
+#[derive(Args, Clone, Copy)]
+pub struct Options {
+    #[arg(long, value_enum, default_value_t = Mode::Breaker)]
+    mode: Mode,
+    /// Show the number of codes left and a suggested guess (toggle with Tab)
+    #[arg(long)]
+    assist: bool,
+}
+
+struct App {
+    mode: Mode,
+    max_round: usize,
+    rules: Rules,
+    theme: Theme,
+    strategy: Strategy,
+    assist: bool,
+    time: TimeArgs,
+    // Draws the secret of each game in breaker mode.
+    code_maker: RandomCodeMaker,
+    // Only known in breaker mode.
+    secret: Option<Code>,
+    // Only kept in timed breaker games.
+    clock: Option<Clock>,
+    rounds: Vec<(Code, Score)>,
+    // Number of candidates left after each round.
+    remaining: Vec<usize>,
+    // Plays in maker mode, and assists the player in both modes.
+    solver: Solver,
+    // The guess being composed in breaker mode, and its selected slot.
+    picker: [CodePeg; SIZE],
+    slot: usize,
+    // The score being typed in maker mode.
+    input: String,
+    message: String,
+    over: bool,
+}
+
+impl App {
+    fn new(
+        Options { mode, assist }: Options,
+        max_round: usize,
+        rules: Rules,
+        theme: Theme,
+        strategy: Strategy,
+        time: TimeArgs,
+        code_maker: RandomCodeMaker,
+    ) -> Self {
+        let secret = match mode {
+            Mode::Breaker => Some(code_maker.make_code()),
+            Mode::Maker => None,
+        };
+        let mut clock = match mode {
+            Mode::Breaker => time.clock(),
+            Mode::Maker => None,
+        };
+        if let Some(clock) = &mut clock {
+            clock.start_move(Instant::now());
+        }
+        App {
+            mode,
+            max_round,
+            rules,
+            theme,
+            strategy,
+            assist,
+            time,
+            code_maker,
+            secret,
+            clock,
+            rounds: Vec::new(),
+            remaining: Vec::new(),
+            solver: Solver::with_rules(rules, strategy),
+            picker: [CodePeg::A; SIZE],
+            slot: 0,
+            input: String::new(),
+            message: String::new(),
+            over: false,
+        }
+    }
+
+    /// Handles a key press and returns whether to quit.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Esc => return true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
+            KeyCode::Tab => self.assist = !self.assist,
+            KeyCode::Enter if self.over => {
+                let options = Options {
+                    mode: self.mode,
+                    assist: self.assist,
+                };
+                *self = App::new(
+                    options,
+                    self.max_round,
+                    self.rules,
+                    self.theme,
+                    self.strategy,
+                    self.time,
+                    mem::take(&mut self.code_maker),
+                )
+            }
+            KeyCode::Enter => self.submit(),
+            _ if self.over => {}
+            _ if self.mode == Mode::Breaker => self.handle_picker_key(key.code),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) => self.input.push(c),
+            _ => {}
+        }
+        false
+    }
+
+    /// Left and right select a slot, up and down cycle through its pegs, and
+    /// typing a peg's letter sets the slot and moves to the next one.
+    fn handle_picker_key(&mut self, code: KeyCode) {
+        let palette = self.rules.palette();
+        let cycle = |peg: CodePeg, step: usize| {
+            let index = palette.iter().position(|&p| p == peg).unwrap();
+            palette[(index + step) % palette.len()]
+        };
+        match code {
+            KeyCode::Left | KeyCode::Backspace => self.slot = self.slot.saturating_sub(1),
+            KeyCode::Right => self.slot = (self.slot + 1).min(SIZE - 1),
+            KeyCode::Up => self.picker[self.slot] = cycle(self.picker[self.slot], 1),
+            KeyCode::Down => {
+                self.picker[self.slot] = cycle(self.picker[self.slot], palette.len() - 1)
+            }
+            KeyCode::Char(c) => match CodePeg::try_from(c) {
+                Ok(peg) if !palette.contains(&peg) => self.message = tr!("peg-not-used", peg = peg),
+                Ok(peg) => {
+                    self.picker[self.slot] = peg;
+                    self.slot = (self.slot + 1).min(SIZE - 1);
+                    self.message.clear();
+                }
+                Err(_) => self.message = tr!("invalid-peg", peg = c),
+            },
+            _ => {}
+        }
+    }
+
+    fn submit(&mut self) {
+        if let Some(secret) = self.secret {
+            let guess = Code::new(self.picker);
+            let played = self.rounds.iter().map(|&(played, _)| played);
+            if let Err(err) = self.rules.check_guess(guess, played) {
+                self.message = rules_error(err);
+                return;
+            }
+            self.slot = 0;
+            self.play(guess, Scorer::new(secret).score(guess));
+            return;
+        }
+
+        let input = std::mem::take(&mut self.input);
+        match parse_score(&input).map(|score| (self.solver.guess_code(), score)) {
+            Ok((guess, score)) => self.play(guess, score),
+            Err(err) => {
+                self.message = err;
+                self.input = input;
+            }
+        }
+    }
+
+    /// Ends the game if the clock ran out.
+    fn tick(&mut self) {
+        if self.over {
+            return;
+        }
+        if let (Some(clock), Some(secret)) = (&self.clock, self.secret) {
+            if let Some(flag) = clock.flag(Instant::now()) {
+                self.over = true;
+                self.message = time_up(flag, secret);
+            }
+        }
+    }
+
+    fn play(&mut self, guess: Code, score: Score) {
+        if let (Some(clock), Some(secret)) = (&mut self.clock, self.secret) {
+            if let Some(flag) = clock.stop_move(Instant::now()) {
+                self.over = true;
+                self.message = time_up(flag, secret);
+                return;
+            }
+        }
+        self.rounds.push((guess, score));
+        self.solver.record(guess, score);
+        self.remaining.push(self.solver.candidates().len());
+        self.message.clear();
+
+        let round = self.rounds.len();
+        if score.matches() == SIZE {
+            self.over = true;
+            self.message = match self.mode {
+                Mode::Breaker => tr!("player-wins", rounds = round),
+                Mode::Maker => tr!("solver-wins", rounds = round),
+            };
+        } else if self.solver.candidates().is_empty() {
+            self.over = true;
+            self.message = tr!("contradictory-scores");
+        } else if round == self.max_round {
+            self.over = true;
+            self.message = match self.secret {
+                Some(secret) => tr!("player-loses-with-code", code = secret),
+                None => tr!("solver-loses"),
+            };
+        } else if let Some(clock) = &mut self.clock {
+            clock.start_move(Instant::now());
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [main, status, input] = Layout::vertical([
+            Constraint::Min(self.max_round as u16 + 2),
+            Constraint::Length(1),
+            Constraint::Length(3),
+        ])
+        .areas(frame.area());
+        let [board, history] = Layout::horizontal([
+            Constraint::Length(4 * SIZE as u16 + 12),
+            Constraint::Min(20),
+        ])
+        .areas(main);
+
+        frame.render_widget(
+            Paragraph::new(self.board_lines())
+                .block(Block::bordered().title(format!(" {} ", tr!("tui-board")))),
+            board,
+        );
+        frame.render_widget(
+            Paragraph::new(self.history_lines())
+                .block(Block::bordered().title(format!(" {} ", tr!("tui-history")))),
+            history,
+        );
+        frame.render_widget(
+            Paragraph::new(self.status_line())
+                .style(Style::default().add_modifier(Modifier::REVERSED)),
+            status,
+        );
+
+        let title = if self.over {
+            tr!("tui-game-over")
+        } else {
+            match self.mode {
+                Mode::Breaker => tr!("tui-guess"),
+                Mode::Maker => tr!("tui-score", guess = self.solver.guess_code()),
+            }
+        };
+        let mut text = match self.mode {
+            Mode::Breaker if !self.over => self.picker_line(),
+            _ => Line::from(self.input.clone()),
+        };
+        if !self.message.is_empty() {
+            text.push_span(Span::styled(
+                format!("  {}", self.message),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        frame.render_widget(
+            Paragraph::new(text).block(Block::bordered().title(format!(" {} ", title))),
+            input,
+        );
+    }
+
+    fn picker_line(&self) -> Line<'static> {
+        let spans = self.picker.iter().enumerate().map(|(i, &peg)| {
+            let mut span = peg_span(peg, self.theme);
+            if i == self.slot {
+                span.style = span.style.add_modifier(Modifier::REVERSED);
+            }
+            span
+        });
+        Line::from(spans.collect::<Vec<_>>())
+    }
+
+    fn board_lines(&self) -> Vec<Line<'static>> {
+        let width = self.max_round.to_string().len();
+        (0..self.max_round)
+            .map(|i| {
+                let mut spans = vec![Span::raw(format!("{:>width$} ", i + 1, width = width))];
+                match self.rounds.get(i) {
+                    Some((guess, score)) => {
+                        spans.extend(guess.pegs().iter().map(|&peg| peg_span(peg, self.theme)));
+                        spans.push(Span::raw(" "));
+                        spans.push(score_span(*score, self.theme));
+                    }
+                    None => {
+                        spans.push(Span::styled(
+                            " ○ ".repeat(SIZE),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+                        spans.push(Span::raw(" "));
+                        spans.push(Span::raw("·".repeat(SIZE)));
+                    }
+                }
+                Line::from(spans)
+            })
+            .collect()
+    }
+
+    fn history_lines(&self) -> Vec<Line<'static>> {
+        self.rounds
+            .iter()
+            .zip(&self.remaining)
+            .enumerate()
+            .map(|(i, ((guess, score), remaining))| {
+                Line::from(tr!(
+                    "tui-round",
+                    round = format!("{:>2}", i + 1),
+                    guess = guess,
+                    matches = score.matches(),
+                    presents = score.presents(),
+                    remaining = remaining
+                ))
+            })
+            .collect()
+    }
+
+    fn status_line(&self) -> String {
+        let mode = match self.mode {
+            Mode::Breaker => tr!("tui-mode-breaker"),
+            Mode::Maker => tr!("tui-mode-maker"),
+        };
+        let round = if self.over {
+            self.rounds.len()
+        } else {
+            self.rounds.len() + 1
+        };
+        let assist = if self.assist {
+            tr!("tui-on")
+        } else {
+            tr!("tui-off")
+        };
+        let mut status = format!(
+            " {}",
+            tr!(
+                "tui-status",
+                mode = mode,
+                round = round,
+                max_round = self.max_round,
+                assist = assist
+            )
+        );
+        if let (Some(clock), false) = (&self.clock, self.over) {
+            status.push_str(&format!(" │ ⏱ {}", clock_status(clock, Instant::now())));
+        }
+        if self.assist && !self.solver.candidates().is_empty() {
+            status.push_str(&format!(
+                " │ {}",
+                tr!(
+                    "hint",
+                    count = self.solver.candidates().len(),
+                    guess = self.solver.guess_code()
+                )
+            ));
+        }
+        status
+    }
+}
+
+fn peg_span(peg: CodePeg, theme: Theme) -> Span<'static> {
+    let mut style = Style::default();
+    if let Some(color) = render::color(peg, theme) {
+        style = style.fg(Color::Indexed(color));
+    }
+    if theme == Theme::HighContrast {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    Span::styled(format!("{}{} ", render::glyph(peg, theme), peg), style)
+}
+
+fn score_span(score: Score, theme: Theme) -> Span<'static> {
+    let mut style = Style::default();
+    if theme == Theme::HighContrast {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    let pegs: String = score
+        .pegs()
+        .iter()
+        .map(|&p| render::score_glyph(p))
+        .collect();
+    Span::styled(pegs, style)
+}
+
+fn run_app(terminal: &mut DefaultTerminal, mut app: App) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+        // Wake up regularly to refresh the countdown.
+        if event::poll(Duration::from_millis(200))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && app.handle_key(key) {
+                    return Ok(());
+                }
+            }
+        }
+        app.tick();
+    }
+}
+
+/// Plays in the terminal, the secrets of breaker mode being drawn from `seed`.
+pub fn run(
+    max_round: usize,
+    rules: Rules,
+    theme: Theme,
+    strategy: Strategy,
+    options: Options,
+    time: TimeArgs,
+    seed: u64,
+) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let code_maker = RandomCodeMaker::seeded(rules, seed);
+    let app = App::new(options, max_round, rules, theme, strategy, time, code_maker);
+    let result = run_app(&mut terminal, app);
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,81 @@
+use mastermind::render::{self, Theme};
+use mastermind::solver::{Solver, Strategy};
+use mastermind::{Code, CodeBreaker, CodePeg, Scorer, SIZE};
+
+use crate::input::{parse_code, read_line};
+use crate::messages::tr;
+
+// The code of the scripted game, which the minimax solver breaks in five
+// rounds with scores of every kind.
+const SECRET: [CodePeg; SIZE] = [CodePeg::F, CodePeg::A, CodePeg::D, CodePeg::E];
+
+// Number of eliminated codes shown as examples after each score.
+const EXAMPLES: usize = 3;
+
+/// Walks the player through a scripted game, explaining after each score
+/// which codes it rules out.
+pub fn run(theme: Theme) {
+    println!("{}", tr!("tutorial-intro", size = SIZE));
+
+    let secret = Code::new(SECRET);
+    let scorer = Scorer::new(secret);
+    let mut solver = Solver::with_strategy(Strategy::Minimax);
+    let mut rounds = Vec::new();
+    loop {
+        let guess = solver.guess_code();
+        println!();
+        println!(
+            "{}",
+            tr!("tutorial-round", round = rounds.len() + 1, guess = guess)
+        );
+        loop {
+            match parse_code(&read_line("> ")) {
+                Ok(code) if code == guess => break,
+                _ => println!("{}", tr!("tutorial-retype", guess = guess)),
+            }
+        }
+
+        let score = scorer.score(guess);
+        rounds.push((guess, score));
+        println!("{}", render::board(&rounds, rounds.len(), theme));
+        println!(
+            "{}",
+            tr!(
+                "tutorial-score",
+                guess = guess,
+                matches = score.matches(),
+                presents = score.presents()
+            )
+        );
+        if score.matches() == SIZE {
+            println!("{}", tr!("tutorial-solved", rounds = rounds.len()));
+            return;
+        }
+
+        let eliminated = solver.eliminated(guess, score);
+        solver.record(guess, score);
+        println!(
+            "{}",
+            tr!(
+                "tutorial-eliminated",
+                eliminated = eliminated.len(),
+                left = solver.candidates().len()
+            )
+        );
+        for (code, code_score) in eliminated.iter().take(EXAMPLES) {
+            println!(
+                "{}",
+                tr!(
+                    "tutorial-example",
+                    code = code,
+                    guess = guess,
+                    matches = code_score.matches(),
+                    presents = code_score.presents()
+                )
+            );
+        }
+        if let Some(code) = solver.candidates().only() {
+            println!("{}", tr!("tutorial-deduced", code = code));
+        }
+    }
+}
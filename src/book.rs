@@ -0,0 +1,241 @@
+//! Opening books: the first guesses of a solver, worked out beforehand so
+//! that it skips its most expensive moves.
+//!
+//! The crate ships the book of `Strategy::Minimax` for every rules, which
+//! plays exactly the guesses the strategy would compute. A solver may be
+//! given another book, or none (see `Solver::with_book`).
+
+use crate::rules::Rules;
+use crate::{Code, Score, SIZE};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Book {
+    opening: Code,
+    /// The second guess after each score of the opening, by `Score::index`.
+    replies: [Option<Code>; (SIZE + 1) * (SIZE + 1)],
+}
+
+impl Book {
+    /// A book which opens with `opening`, with no replies yet.
+    pub fn new(opening: Code) -> Self {
+        Book {
+            opening,
+            replies: [None; (SIZE + 1) * (SIZE + 1)],
+        }
+    }
+
+    /// Replies `guess` when the opening gets `score`.
+    pub fn with_reply(mut self, score: Score, guess: Code) -> Self {
+        self.replies[score.index()] = Some(guess);
+        self
+    }
+
+    pub fn opening(&self) -> Code {
+        self.opening
+    }
+
+    /// The guess after the opening got `score`, if the book has one.
+    pub fn reply(&self, score: Score) -> Option<Code> {
+        self.replies[score.index()]
+    }
+
+    /// The replies of the book, with the scores they answer, in the order of
+    /// `Score::index`.
+    pub fn replies(&self) -> impl Iterator<Item = (Score, Code)> + '_ {
+        self.replies
+            .iter()
+            .enumerate()
+            .filter_map(|(index, reply)| Some((Score::from_index(index), (*reply)?)))
+    }
+
+    /// The book of `Strategy::Minimax` under `rules`.
+    pub fn minimax(rules: Rules) -> Option<Book> {
+        let &(_, _, opening, replies) = MINIMAX.iter().find(|&&(colors, duplicates, _, _)| {
+            (colors, duplicates) == (rules.colors(), rules.duplicates())
+        })?;
+        // The tables are checked by the tests, which expect a book for all
+        // rules.
+        let mut book = Book::new(opening.parse().ok()?);
+        for &(matches, presents, guess) in replies {
+            book = book.with_reply(Score::from_counts(matches, presents)?, guess.parse().ok()?);
+        }
+        Some(book)
+    }
+}
+
+/// The colors and duplicates of rules, the opening, and the replies to the
+/// scores by their matches and presents.
+type Entry = (
+    usize,
+    bool,
+    &'static str,
+    &'static [(usize, usize, &'static str)],
+);
+
+/// The book of `Strategy::Minimax` for each rules, which has no reply to the
+/// scores no code can get.
+const MINIMAX: [Entry; 9] = [
+    (1, true, "AAAA", &[]),
+    (
+        2,
+        true,
+        "AAAB",
+        &[
+            (0, 2, "BBBA"),
+            (1, 0, "BBBB"),
+            (1, 2, "ABBA"),
+            (2, 0, "ABBB"),
+            (2, 2, "AABA"),
+            (3, 0, "AABB"),
+        ],
+    ),
+    (
+        3,
+        true,
+        "AAAB",
+        &[
+            (0, 0, "CCCC"),
+            (0, 1, "BCCC"),
+            (0, 2, "ABCC"),
+            (1, 0, "ACCC"),
+            (1, 1, "ABBC"),
+            (1, 2, "ABBA"),
+            (2, 0, "AACC"),
+            (2, 1, "ABCA"),
+            (2, 2, "ABCA"),
+            (3, 0, "AABB"),
+        ],
+    ),
+    (
+        4,
+        true,
+        "AABC",
+        &[
+            (0, 0, "DDDD"),
+            (0, 1, "DDBD"),
+            (0, 2, "DDAB"),
+            (0, 3, "BDCA"),
+            (0, 4, "BCAA"),
+            (1, 0, "ADCD"),
+            (1, 1, "ADAD"),
+            (1, 2, "ABCB"),
+            (1, 3, "ABCA"),
+            (2, 0, "ADAD"),
+            (2, 1, "ABBA"),
+            (2, 2, "ABAC"),
+            (3, 0, "ABBD"),
+        ],
+    ),
+    (
+        4,
+        false,
+        "ABCD",
+        &[(0, 4, "ACDB"), (1, 3, "ACDB"), (2, 2, "ACDB")],
+    ),
+    (
+        5,
+        true,
+        "AABB",
+        &[
+            (0, 0, "CCCD"),
+            (0, 1, "BCAC"),
+            (0, 2, "BCAD"),
+            (0, 3, "ABAC"),
+            (0, 4, "BBAA"),
+            (1, 0, "ACBC"),
+            (1, 1, "ACAA"),
+            (1, 2, "ABAC"),
+            (2, 0, "ACAC"),
+            (2, 1, "ABBC"),
+            (2, 2, "ABAC"),
+            (3, 0, "AACD"),
+        ],
+    ),
+    (
+        5,
+        false,
+        "ABCD",
+        &[
+            (0, 3, "BCDE"),
+            (0, 4, "ACDB"),
+            (1, 2, "ACBE"),
+            (1, 3, "ACDB"),
+            (2, 1, "ABDE"),
+            (2, 2, "ABCE"),
+            (3, 0, "ACDE"),
+        ],
+    ),
+    (
+        6,
+        true,
+        "AABB",
+        &[
+            (0, 0, "CCDE"),
+            (0, 1, "BCDD"),
+            (0, 2, "BCDD"),
+            (0, 3, "ABAC"),
+            (0, 4, "BBAA"),
+            (1, 0, "ACDD"),
+            (1, 1, "AACD"),
+            (1, 2, "ABAC"),
+            (2, 0, "ABCD"),
+            (2, 1, "ABBC"),
+            (2, 2, "ABAC"),
+            (3, 0, "ABBC"),
+        ],
+    ),
+    (
+        6,
+        false,
+        "ABCD",
+        &[
+            (0, 2, "BEDF"),
+            (0, 3, "BCDE"),
+            (0, 4, "ACDB"),
+            (1, 1, "ABDE"),
+            (1, 2, "ABDE"),
+            (1, 3, "ACDB"),
+            (2, 0, "ACDE"),
+            (2, 1, "ACBE"),
+            (2, 2, "ABCE"),
+            (3, 0, "ABDE"),
+        ],
+    ),
+];
+
+#[cfg(test)]
+mod test_book {
+    use super::*;
+    use crate::solver::{Backend, Solver, Strategy};
+    use crate::CodeBreaker;
+
+    /// The fastest backend available.
+    #[cfg(feature = "table")]
+    const BACKEND: Backend = Backend::Table;
+    #[cfg(not(feature = "table"))]
+    const BACKEND: Backend = Backend::Scorer;
+
+    #[test]
+    fn minimax_books_play_the_computed_guesses() {
+        for colors in 1..=crate::CodePeg::ALL.len() {
+            for duplicates in [true, false] {
+                let Ok(rules) = Rules::new(colors, duplicates) else {
+                    continue;
+                };
+                let book = Book::minimax(rules).unwrap();
+                let computed =
+                    || Solver::with_backend(rules, Strategy::Minimax, BACKEND).with_book(None);
+                assert_eq!(computed().guess_code(), book.opening(), "{:?}", rules);
+                let scores = (0..=SIZE)
+                    .flat_map(|matches| (0..=SIZE).map(move |presents| (matches, presents)));
+                for score in scores.filter_map(|(m, p)| Score::from_counts(m, p)) {
+                    let mut solver = computed();
+                    solver.record(book.opening(), score);
+                    let reply = (!solver.candidates().is_empty() && score.matches() < SIZE)
+                        .then(|| solver.guess_code());
+                    assert_eq!(book.reply(score), reply, "{:?} after {:?}", rules, score);
+                }
+            }
+        }
+    }
+}
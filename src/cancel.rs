@@ -0,0 +1,151 @@
+//! Cancellation of games played asynchronously, e.g. by servers whose
+//! players left.
+
+use std::collections::HashMap;
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Poll, Waker};
+
+use crate::sync::{lock, Mutex};
+
+#[derive(Default)]
+struct Shared {
+    cancelled: AtomicBool,
+    // The tasks to wake once cancelled.
+    wakers: Mutex<Wakers>,
+}
+
+/// The waker of each `run` in progress, under a key of its own.
+#[derive(Default)]
+struct Wakers {
+    next_key: u64,
+    entries: HashMap<u64, Waker>,
+}
+
+/// The waker of a `run`, removed once it completes or is dropped, so that
+/// a token shared by many games does not keep the wakers of finished ones.
+struct Registration<'a> {
+    shared: &'a Shared,
+    key: Option<u64>,
+}
+
+impl Registration<'_> {
+    fn register(&mut self, waker: &Waker) {
+        let mut wakers = lock(&self.shared.wakers);
+        match self.key.and_then(|key| wakers.entries.get_mut(&key)) {
+            Some(registered) => {
+                if !registered.will_wake(waker) {
+                    registered.clone_from(waker);
+                }
+            }
+            // Not registered yet, or woken by a cancellation.
+            None => {
+                let key = wakers.next_key;
+                wakers.next_key += 1;
+                wakers.entries.insert(key, waker.clone());
+                self.key = Some(key);
+            }
+        }
+    }
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            lock(&self.shared.wakers).entries.remove(&key);
+        }
+    }
+}
+
+/// A token shared by the games to stop and whoever stops them.
+///
+/// It works with any executor: cancelling wakes the tasks waiting on it.
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    shared: Arc<Shared>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.shared.cancelled.store(true, Ordering::SeqCst);
+        let wakers: Vec<Waker> = lock(&self.shared.wakers)
+            .entries
+            .drain()
+            .map(|(_, waker)| waker)
+            .collect();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.shared.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Awaits `future`, or returns `None` as soon as the token is cancelled,
+    /// dropping `future`.
+    pub async fn run<F: Future>(&self, future: F) -> Option<F::Output> {
+        let mut future = pin!(future);
+        let mut registration = Registration {
+            shared: &self.shared,
+            key: None,
+        };
+        poll_fn(|context| {
+            registration.register(context.waker());
+            // Checked once the waker is registered, not to miss a
+            // cancellation in between.
+            if self.is_cancelled() {
+                return Poll::Ready(None);
+            }
+            future.as_mut().poll(context).map(Some)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod test_cancel {
+    use std::task::Context;
+
+    use super::*;
+
+    #[test]
+    fn cancels() {
+        let token = CancelToken::new();
+        let mut context = Context::from_waker(Waker::noop());
+        let mut pending = pin!(token.run(std::future::pending::<()>()));
+        assert_eq!(pending.as_mut().poll(&mut context), Poll::Pending);
+
+        token.clone().cancel();
+        assert!(token.is_cancelled());
+        assert_eq!(pending.as_mut().poll(&mut context), Poll::Ready(None));
+        let mut ready = pin!(token.run(std::future::ready(1)));
+        assert_eq!(ready.as_mut().poll(&mut context), Poll::Ready(None));
+    }
+
+    #[test]
+    fn releases_wakers() {
+        let token = CancelToken::new();
+        let wakers = || lock(&token.shared.wakers).entries.len();
+        let mut context = Context::from_waker(Waker::noop());
+        for _ in 0..3 {
+            let mut ready = pin!(token.run(std::future::ready(1)));
+            assert_eq!(ready.as_mut().poll(&mut context), Poll::Ready(Some(1)));
+        }
+        assert_eq!(wakers(), 0);
+
+        {
+            let mut pending = pin!(token.run(std::future::pending::<()>()));
+            assert_eq!(pending.as_mut().poll(&mut context), Poll::Pending);
+            assert_eq!(pending.as_mut().poll(&mut context), Poll::Pending);
+            assert_eq!(wakers(), 1);
+        }
+        assert_eq!(wakers(), 0);
+    }
+}
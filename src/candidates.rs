@@ -0,0 +1,200 @@
+//! Sets of codes, such as the codes a solver still considers.
+//!
+//! A `CandidateSet` has a bit for each code, at its `Code::index`, so that
+//! testing a code, intersecting sets and counting their codes only take a
+//! few machine words, and its codes come in the order of `Code::all`.
+
+use crate::rules::Rules;
+use crate::Code;
+
+const WORDS: usize = Code::COUNT.div_ceil(u64::BITS as usize);
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CandidateSet {
+    words: [u64; WORDS],
+}
+
+impl CandidateSet {
+    pub fn new() -> Self {
+        CandidateSet::default()
+    }
+
+    /// Every code.
+    pub fn all() -> Self {
+        let mut set = CandidateSet {
+            words: [u64::MAX; WORDS],
+        };
+        // Without the bits past the last code.
+        set.words[WORDS - 1] >>= WORDS * u64::BITS as usize - Code::COUNT;
+        set
+    }
+
+    /// The codes allowed by `rules`.
+    pub fn with_rules(rules: Rules) -> Self {
+        if rules == Rules::default() {
+            return CandidateSet::all();
+        }
+        rules.codes().into_iter().collect()
+    }
+
+    /// Adds `code`, and returns whether it was not in the set yet.
+    pub fn insert(&mut self, code: Code) -> bool {
+        let (word, bit) = position(code);
+        let added = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        added
+    }
+
+    /// Removes `code`, and returns whether it was in the set.
+    pub fn remove(&mut self, code: Code) -> bool {
+        let (word, bit) = position(code);
+        let removed = self.words[word] & bit != 0;
+        self.words[word] &= !bit;
+        removed
+    }
+
+    pub fn contains(&self, code: Code) -> bool {
+        let (word, bit) = position(code);
+        self.words[word] & bit != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    /// The first code in the order of `Code::all`.
+    pub fn first(&self) -> Option<Code> {
+        self.iter().next()
+    }
+
+    /// The code, if the set has exactly one.
+    pub fn only(&self) -> Option<Code> {
+        let mut codes = self.iter();
+        codes.next().filter(|_| codes.next().is_none())
+    }
+
+    /// The codes in both sets.
+    pub fn intersection(&self, other: &CandidateSet) -> Self {
+        let mut set = self.clone();
+        for (word, other) in set.words.iter_mut().zip(&other.words) {
+            *word &= other;
+        }
+        set
+    }
+
+    /// Keeps only the codes for which `keep` is true.
+    pub fn retain(&mut self, mut keep: impl FnMut(Code) -> bool) {
+        for (i, word) in self.words.iter_mut().enumerate() {
+            let mut bits = *word;
+            while bits != 0 {
+                let bit = bits.trailing_zeros();
+                bits &= bits - 1;
+                let code = Code::from_index(i * u64::BITS as usize + bit as usize);
+                if !code.is_some_and(&mut keep) {
+                    *word &= !(1 << bit);
+                }
+            }
+        }
+    }
+
+    /// The codes in the order of `Code::all`.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            words: &self.words,
+            word: 0,
+            bits: self.words[0],
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<Code> {
+        self.iter().collect()
+    }
+}
+
+/// The word of the bit of `code`, and the bit in it.
+fn position(code: Code) -> (usize, u64) {
+    let index = code.index();
+    let bits = u64::BITS as usize;
+    (index / bits, 1 << (index % bits))
+}
+
+impl FromIterator<Code> for CandidateSet {
+    fn from_iter<I: IntoIterator<Item = Code>>(codes: I) -> Self {
+        let mut set = CandidateSet::new();
+        for code in codes {
+            set.insert(code);
+        }
+        set
+    }
+}
+
+impl<'a> IntoIterator for &'a CandidateSet {
+    type Item = Code;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// The codes of a `CandidateSet`.
+pub struct Iter<'a> {
+    words: &'a [u64; WORDS],
+    word: usize,
+    /// The bits of `words[word]` not visited yet.
+    bits: u64,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = Code;
+
+    fn next(&mut self) -> Option<Code> {
+        while self.bits == 0 {
+            self.word += 1;
+            self.bits = *self.words.get(self.word)?;
+        }
+        let bit = self.bits.trailing_zeros() as usize;
+        // Clears the lowest bit set.
+        self.bits &= self.bits - 1;
+        Code::from_index(self.word * u64::BITS as usize + bit)
+    }
+}
+
+#[cfg(test)]
+mod test_candidates {
+    use super::*;
+
+    #[test]
+    fn sets() {
+        let all = CandidateSet::all();
+        assert_eq!(all.len(), Code::COUNT);
+        assert_eq!(all.to_vec(), Code::all());
+
+        let rules = Rules::new(5, false).unwrap();
+        let set = CandidateSet::with_rules(rules);
+        assert_eq!(set.to_vec(), rules.codes());
+        assert_eq!(set.intersection(&all), set);
+
+        let (abcd, aaaa) = ("ABCD".parse().unwrap(), "AAAA".parse().unwrap());
+        let mut set = CandidateSet::new();
+        assert!(set.is_empty() && set.first().is_none());
+        assert!(set.insert(abcd));
+        assert!(!set.insert(abcd));
+        assert_eq!(set.only(), Some(abcd));
+        set.insert(aaaa);
+        assert_eq!((set.len(), set.first(), set.only()), (2, Some(aaaa), None));
+        assert!(set.contains(abcd) && !set.contains("ABCE".parse().unwrap()));
+
+        set.retain(|code| code != aaaa);
+        assert_eq!(set.to_vec(), [abcd]);
+        assert!(set.remove(abcd) && !set.remove(abcd));
+        assert!(set.is_empty());
+    }
+}
@@ -0,0 +1,372 @@
+//! A chat bot hosting games in the channels of a chat such as Discord or
+//! Slack, whatever the transport: the bot is given each message with the
+//! channel it was posted in, and returns the reply to post, if any.
+//!
+//! Messages are commands, after an optional prefix such as `!mm`:
+//!
+//! ```text
+//! new [<rounds>] [<colors>] [unique]    starts a game in the channel
+//! guess <code>                          e.g. guess ABCD
+//! board                                 shows the rounds so far
+//! give up                               reveals the code
+//! help
+//! ```
+//!
+//! Everyone in a channel plays the same game. Bots keying games by user
+//! instead give each player their own.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::hash::Hash;
+use std::num::NonZeroUsize;
+
+use crate::rules::Rules;
+use crate::session::{GameSession, State};
+use crate::{Code, CodePeg, Score, ScorePeg, SIZE};
+
+pub const DEFAULT_ROUNDS: NonZeroUsize = NonZeroUsize::new(10).unwrap();
+
+/// What a message asks the bot to do.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    New {
+        rounds: Option<NonZeroUsize>,
+        colors: Option<usize>,
+        duplicates: bool,
+    },
+    Guess(Code),
+    Board,
+    GiveUp,
+    Help,
+}
+
+impl Command {
+    /// Reads a command, without its prefix.
+    ///
+    /// Returns `None` if the message is not a command, and an error to reply
+    /// with if it is an invalid one.
+    pub fn parse(text: &str) -> Option<Result<Command, String>> {
+        let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        let command = match words.as_slice() {
+            ["new", options @ ..] => new(options),
+            ["guess", code] => code
+                .parse()
+                .map(Command::Guess)
+                .map_err(|err| format!("{}.", err)),
+            ["guess", ..] => Err("Guess a code, e.g. `guess ABCD`.".to_string()),
+            ["board"] => Ok(Command::Board),
+            ["give", "up"] => Ok(Command::GiveUp),
+            ["help"] => Ok(Command::Help),
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+fn new(options: &[&str]) -> Result<Command, String> {
+    let (duplicates, numbers) = match options {
+        [numbers @ .., "unique"] => (false, numbers),
+        numbers => (true, numbers),
+    };
+    let numbers = numbers
+        .iter()
+        .map(|n| n.parse().map_err(|_| format!("`{}` is not a number.", n)))
+        .collect::<Result<Vec<usize>, String>>()?;
+    let rounds = match numbers.first() {
+        Some(&rounds) => Some(
+            NonZeroUsize::new(rounds)
+                .ok_or_else(|| "A game needs at least one round.".to_string())?,
+        ),
+        None => None,
+    };
+    match numbers.as_slice() {
+        [] | [_] | [_, _] => Ok(Command::New {
+            rounds,
+            colors: numbers.get(1).copied(),
+            duplicates,
+        }),
+        _ => Err("Start a game with `new [<rounds>] [<colors>] [unique]`.".to_string()),
+    }
+}
+
+/// A colored circle per peg.
+pub fn emoji_peg(peg: CodePeg) -> &'static str {
+    match peg {
+        CodePeg::A => "🔴",
+        CodePeg::B => "🟢",
+        CodePeg::C => "🟡",
+        CodePeg::D => "🔵",
+        CodePeg::E => "🟣",
+        CodePeg::F => "🟠",
+    }
+}
+
+pub fn emoji_code(code: Code) -> String {
+    code.pegs().iter().map(|&peg| emoji_peg(peg)).collect()
+}
+
+/// `⚫` for a matched peg, `⚪` for a present peg and `➖` for no peg.
+pub fn emoji_score(score: Score) -> String {
+    let pegs = score.pegs();
+    let pegs = pegs.iter().map(|peg| match peg {
+        Some(ScorePeg::Match) => "⚫",
+        Some(ScorePeg::Present) => "⚪",
+        None => "➖",
+    });
+    pegs.collect()
+}
+
+fn round(number: usize, guess: Code, score: Score) -> String {
+    format!(
+        "{}. `{}` {} {}",
+        number,
+        guess,
+        emoji_code(guess),
+        emoji_score(score)
+    )
+}
+
+/// The games of each channel, keyed by `K`.
+pub struct ChatBot<K> {
+    prefix: String,
+    games: HashMap<K, GameSession>,
+}
+
+impl<K: Eq + Hash> Default for ChatBot<K> {
+    fn default() -> Self {
+        ChatBot::new()
+    }
+}
+
+impl<K: Eq + Hash> ChatBot<K> {
+    /// A bot answering every message which is a command.
+    pub fn new() -> Self {
+        ChatBot::with_prefix("")
+    }
+
+    /// A bot answering only the messages starting with `prefix`.
+    pub fn with_prefix(prefix: &str) -> Self {
+        ChatBot {
+            prefix: prefix.to_string(),
+            games: HashMap::new(),
+        }
+    }
+
+    /// The game being played in `channel`.
+    pub fn game(&self, channel: &K) -> Option<&GameSession> {
+        self.games.get(channel)
+    }
+
+    /// Starts `session` in `channel`, replacing its game if any.
+    pub fn start(&mut self, channel: K, session: GameSession) {
+        self.games.insert(channel, session);
+    }
+
+    /// The reply to `message`, posted in `channel`, if any.
+    pub fn reply(&mut self, channel: K, message: &str) -> Option<String> {
+        let text = message.trim().strip_prefix(&self.prefix)?;
+        match Command::parse(text) {
+            Some(Ok(command)) => Some(self.run(channel, command)),
+            Some(Err(error)) => Some(error),
+            // With a prefix, the message was meant for the bot.
+            None if !self.prefix.is_empty() => Some(self.help()),
+            None => None,
+        }
+    }
+
+    /// Runs `command` in `channel`, and returns the reply.
+    pub fn run(&mut self, channel: K, command: Command) -> String {
+        match command {
+            Command::New {
+                rounds,
+                colors,
+                duplicates,
+            } => {
+                let colors = colors.unwrap_or(CodePeg::ALL.len());
+                let rules = match Rules::new(colors, duplicates) {
+                    Ok(rules) => rules,
+                    Err(err) => return format!("{}.", err),
+                };
+                let rounds = rounds.unwrap_or(DEFAULT_ROUNDS);
+                let palette: String = rules.palette().iter().map(|&p| emoji_peg(p)).collect();
+                self.start(channel, GameSession::random(rules, rounds));
+                format!(
+                    "New game: break a code of {} pegs among {} in {} rounds, with `{}guess <code>`.",
+                    SIZE,
+                    palette,
+                    rounds,
+                    self.prefix_spaced()
+                )
+            }
+            Command::Guess(guess) => {
+                let Some(session) = self.games.get_mut(&channel) else {
+                    return self.no_game();
+                };
+                let score = match session.guess(guess) {
+                    Ok(score) => score,
+                    Err(err) => return format!("{}.", err),
+                };
+                let number = session.rounds().len();
+                let mut reply = round(number, guess, score);
+                match session.state() {
+                    State::Playing => {
+                        if let Some(max_round) = session.max_round() {
+                            let _ = write!(reply, " ({} left)", max_round.get() - number);
+                        }
+                    }
+                    State::Won => {
+                        let _ = write!(reply, "\n🎉 Solved in {} rounds!", number);
+                        self.games.remove(&channel);
+                    }
+                    State::Lost => {
+                        let secret = session.reveal();
+                        let _ = write!(
+                            reply,
+                            "\nOut of rounds, the code was `{}` {}.",
+                            secret,
+                            emoji_code(secret)
+                        );
+                        self.games.remove(&channel);
+                    }
+                }
+                reply
+            }
+            Command::Board => {
+                let Some(session) = self.games.get(&channel) else {
+                    return self.no_game();
+                };
+                if session.rounds().is_empty() {
+                    return "No guess yet.".to_string();
+                }
+                let rounds = session.rounds().iter().enumerate();
+                let lines: Vec<String> = rounds
+                    .map(|(i, &(guess, score))| round(i + 1, guess, score))
+                    .collect();
+                lines.join("\n")
+            }
+            Command::GiveUp => match self.games.remove(&channel) {
+                Some(session) => {
+                    let secret = session.reveal();
+                    format!("The code was `{}` {}.", secret, emoji_code(secret))
+                }
+                None => self.no_game(),
+            },
+            Command::Help => self.help(),
+        }
+    }
+
+    /// The prefix followed by a space, if any, to quote commands.
+    fn prefix_spaced(&self) -> String {
+        if self.prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{} ", self.prefix)
+        }
+    }
+
+    fn no_game(&self) -> String {
+        format!(
+            "No game in this channel, start one with `{}new`.",
+            self.prefix_spaced()
+        )
+    }
+
+    fn help(&self) -> String {
+        let p = self.prefix_spaced();
+        format!(
+            "`{p}new [<rounds>] [<colors>] [unique]` starts a game, \
+             `{p}guess ABCD` plays a guess, `{p}board` shows the rounds and \
+             `{p}give up` reveals the code. ⚫ is a right color in the right \
+             place, ⚪ a right color in the wrong place."
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_chat {
+    use super::*;
+
+    fn code(s: &str) -> Code {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses() {
+        assert_eq!(
+            Command::parse("New 12 4 unique"),
+            Some(Ok(Command::New {
+                rounds: NonZeroUsize::new(12),
+                colors: Some(4),
+                duplicates: false,
+            }))
+        );
+        assert_eq!(
+            Command::parse("guess abcd"),
+            Some(Ok(Command::Guess(code("ABCD"))))
+        );
+        assert!(matches!(Command::parse("guess ABCX"), Some(Err(_))));
+        assert!(matches!(Command::parse("new ten"), Some(Err(_))));
+        assert!(matches!(Command::parse("new 0"), Some(Err(_))));
+        assert_eq!(Command::parse("hello there"), None);
+    }
+
+    #[test]
+    fn plays() {
+        let mut bot = ChatBot::with_prefix("!mm");
+        assert_eq!(bot.reply("general", "guess ABCD"), None);
+        assert!(bot
+            .reply("general", "!mm guess ABCD")
+            .unwrap()
+            .starts_with("No game"));
+        assert!(bot
+            .reply("general", "!mm hello")
+            .unwrap()
+            .contains("!mm new"));
+
+        bot.start(
+            "general",
+            GameSession::new(
+                Rules::default(),
+                NonZeroUsize::new(2).unwrap(),
+                code("ABCD"),
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            bot.reply("general", "!mm guess AABB").unwrap(),
+            "1. `AABB` 🔴🔴🟢🟢 ⚫⚪➖➖ (1 left)"
+        );
+        assert!(bot
+            .reply("random", "!mm board")
+            .unwrap()
+            .starts_with("No game"));
+        assert_eq!(
+            bot.reply("general", "!mm guess ABCD").unwrap(),
+            "2. `ABCD` 🔴🟢🟡🔵 ⚫⚫⚫⚫\n🎉 Solved in 2 rounds!"
+        );
+        assert!(bot.game(&"general").is_none());
+
+        bot.start(
+            "general",
+            GameSession::new(
+                Rules::default(),
+                NonZeroUsize::new(3).unwrap(),
+                code("ABCD"),
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            bot.reply("general", "!mm give up").unwrap(),
+            "The code was `ABCD` 🔴🟢🟡🔵."
+        );
+        assert!(bot
+            .reply("general", "!mm new 5")
+            .unwrap()
+            .starts_with("New game"));
+        assert_eq!(
+            bot.game(&"general").unwrap().max_round(),
+            NonZeroUsize::new(5)
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Time control for a code breaker: a limit per move, for the whole game, or
+//! both.
+//!
+//! Times are passed in rather than read from the system clock, so that the
+//! caller decides when a move starts and ends.
+
+use std::time::{Duration, Instant};
+
+/// Which limit was exceeded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Flag {
+    Move,
+    Total,
+}
+
+pub struct Clock {
+    per_move: Option<Duration>,
+    total: Option<Duration>,
+    // Time spent on the moves already played.
+    used: Duration,
+    move_start: Option<Instant>,
+}
+
+impl Clock {
+    pub fn new(per_move: Option<Duration>, total: Option<Duration>) -> Self {
+        Clock {
+            per_move,
+            total,
+            used: Duration::ZERO,
+            move_start: None,
+        }
+    }
+
+    pub fn start_move(&mut self, now: Instant) {
+        self.move_start = Some(now);
+    }
+
+    /// Ends the current move, and returns the limit it exceeded, if any.
+    pub fn stop_move(&mut self, now: Instant) -> Option<Flag> {
+        let flag = self.flag(now);
+        self.used += self.elapsed(now);
+        self.move_start = None;
+        flag
+    }
+
+    /// Time left for the current move, if moves are limited.
+    pub fn move_left(&self, now: Instant) -> Option<Duration> {
+        self.per_move
+            .map(|per_move| per_move.saturating_sub(self.elapsed(now)))
+    }
+
+    /// Time left for the rest of the game, if the game is limited.
+    pub fn total_left(&self, now: Instant) -> Option<Duration> {
+        self.total
+            .map(|total| total.saturating_sub(self.used + self.elapsed(now)))
+    }
+
+    /// The limit exceeded at `now`, if any.
+    pub fn flag(&self, now: Instant) -> Option<Flag> {
+        if self.total_left(now) == Some(Duration::ZERO) {
+            Some(Flag::Total)
+        } else if self.move_left(now) == Some(Duration::ZERO) {
+            Some(Flag::Move)
+        } else {
+            None
+        }
+    }
+
+    fn elapsed(&self, now: Instant) -> Duration {
+        self.move_start
+            .map_or(Duration::ZERO, |start| now.saturating_duration_since(start))
+    }
+}
+
+#[cfg(test)]
+mod test_clock {
+    use super::*;
+
+    #[test]
+    fn counts_down() {
+        let start = Instant::now();
+        let mut clock = Clock::new(Some(Duration::from_secs(30)), Some(Duration::from_secs(60)));
+        clock.start_move(start);
+        let now = start + Duration::from_secs(20);
+        assert_eq!(clock.move_left(now), Some(Duration::from_secs(10)));
+        assert_eq!(clock.total_left(now), Some(Duration::from_secs(40)));
+        assert_eq!(clock.stop_move(now), None);
+
+        // Waiting between moves does not count.
+        let start = now + Duration::from_secs(100);
+        clock.start_move(start);
+        let now = start + Duration::from_secs(5);
+        assert_eq!(clock.move_left(now), Some(Duration::from_secs(25)));
+        assert_eq!(clock.total_left(now), Some(Duration::from_secs(35)));
+    }
+
+    #[test]
+    fn flags() {
+        let start = Instant::now();
+        let mut clock = Clock::new(Some(Duration::from_secs(30)), Some(Duration::from_secs(40)));
+        clock.start_move(start);
+        assert_eq!(
+            clock.flag(start + Duration::from_secs(30)),
+            Some(Flag::Move)
+        );
+        assert_eq!(clock.stop_move(start + Duration::from_secs(25)), None);
+
+        let start = start + Duration::from_secs(25);
+        clock.start_move(start);
+        assert_eq!(
+            clock.stop_move(start + Duration::from_secs(20)),
+            Some(Flag::Total)
+        );
+    }
+
+    #[test]
+    fn unlimited() {
+        let start = Instant::now();
+        let mut clock = Clock::new(None, None);
+        clock.start_move(start);
+        assert_eq!(clock.move_left(start), None);
+        assert_eq!(clock.stop_move(start + Duration::from_secs(3600)), None);
+    }
+}
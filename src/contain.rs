@@ -0,0 +1,138 @@
+//! Players whose panics are contained, so that a buggy bot, e.g. a plugin,
+//! forfeits its game instead of taking its host down.
+//!
+//! Panics are only caught when they unwind, i.e. unless the host is built
+//! with `panic = "abort"`. The panic hook still reports them.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::rules::Rules;
+use crate::{Code, CodeBreaker, CodeMaker, CodePeg, RandomCodeMaker, Score, SIZE};
+
+/// A player which is no longer called once it panicked, since its state may
+/// then be broken.
+///
+/// A code breaker which panics forfeits the game. A code maker which panics
+/// plays a random code allowed by the rules, like one running out of time
+/// (see `timeout::WithTimeout`), and the host may then call the game off.
+///
+/// It is a sync player, and an async one through the blanket impls.
+pub struct Contained<P> {
+    player: P,
+    rules: Rules,
+    panicked: AtomicBool,
+}
+
+impl<P> Contained<P> {
+    pub fn new(player: P, rules: Rules) -> Self {
+        Contained {
+            player,
+            rules,
+            panicked: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether the player panicked.
+    pub fn panicked(&self) -> bool {
+        self.panicked.load(Ordering::Relaxed)
+    }
+
+    pub fn get_ref(&self) -> &P {
+        &self.player
+    }
+
+    pub fn into_inner(self) -> P {
+        self.player
+    }
+}
+
+/// Calls `f` unless the player already panicked, and remembers if it does.
+fn contain<R>(panicked: &AtomicBool, f: impl FnOnce() -> R) -> Option<R> {
+    if panicked.load(Ordering::Relaxed) {
+        return None;
+    }
+    // The player is never called again after a panic, so its state cannot
+    // be seen broken.
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    if result.is_err() {
+        #[cfg(feature = "tracing")]
+        tracing::warn!("the player panicked");
+        panicked.store(true, Ordering::Relaxed);
+    }
+    result.ok()
+}
+
+impl<P: CodeMaker> CodeMaker for Contained<P> {
+    fn make_code(&self) -> Code {
+        contain(&self.panicked, || self.player.make_code())
+            .unwrap_or_else(|| CodeMaker::make_code(&RandomCodeMaker::new(self.rules)))
+    }
+}
+
+impl<P: CodeBreaker> CodeBreaker for Contained<P> {
+    fn guess_code(&self) -> Code {
+        // Ignored by the game, since the player then forfeits.
+        contain(&self.panicked, || self.player.guess_code())
+            .unwrap_or(Code::new([CodePeg::ALL[0]; SIZE]))
+    }
+
+    fn set_score(&mut self, score: Score) {
+        contain(&self.panicked, || self.player.set_score(score));
+    }
+
+    fn loses(&mut self) {
+        contain(&self.panicked, || self.player.loses());
+    }
+
+    fn wins(&mut self, rounds: usize) {
+        contain(&self.panicked, || self.player.wins(rounds));
+    }
+
+    fn forfeited(&self) -> bool {
+        contain(&self.panicked, || self.player.forfeited()).unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod test_contain {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+    use crate::testutil::{DeterministicCodeMaker, FailingPlayer};
+    use crate::{Game, GameOutcome};
+
+    #[test]
+    fn forfeits_code_breakers_which_panic() {
+        let code: Code = "ABCD".parse().unwrap();
+        // Panics on its second guess.
+        let code_breaker = FailingPlayer::new(vec!["AABB".parse().unwrap()]);
+        let mut code_breaker = Contained::new(code_breaker, Rules::default());
+        let outcome = Game::new(
+            NonZeroUsize::new(10).unwrap(),
+            &DeterministicCodeMaker::new(code),
+            &mut code_breaker,
+        )
+        .play();
+        assert_eq!(outcome, GameOutcome::Lost(code));
+        assert!(code_breaker.panicked());
+        // Nor is it told it lost, after it panicked.
+        let code_breaker = code_breaker.into_inner();
+        let code_breaker = code_breaker.get_ref();
+        assert_eq!(code_breaker.num_rounds(), 1);
+        assert!(!code_breaker.has_lost);
+    }
+
+    #[test]
+    fn replaces_codes_of_code_makers_which_panic() {
+        let rules = Rules::new(4, false).unwrap();
+        let code_maker = Contained::new(FailingPlayer::new(Vec::new()), rules);
+        let code = code_maker.make_code();
+        assert!(code_maker.panicked());
+        assert_eq!(rules.check(code), Ok(()));
+
+        let code_maker = Contained::new(DeterministicCodeMaker::new(code), rules);
+        assert_eq!(code_maker.make_code(), code);
+        assert!(!code_maker.panicked());
+    }
+}
@@ -0,0 +1,458 @@
+//! The Universal Mastermind Interface, a text protocol over stdin and stdout
+//! to plug code breakers written in any language, in the spirit of UCI for
+//! chess engines.
+//!
+//! The game sends commands, one per line, and the engine answers:
+//!
+//! ```text
+//! game    umi                         once started
+//! engine  id name <name>              optionally, then
+//! engine  umiok
+//! game    isready
+//! engine  readyok
+//! game    position <colors> duplicates|unique [strict] [<guess> <matched> <present>]...
+//! game    go                          to ask for a guess in this position
+//! engine  info <text>                 optionally, then
+//! engine  bestguess <code> | bestguess none
+//! game    quit
+//! ```
+//!
+//! The position describes the game from its start, e.g. `position 6
+//! duplicates AABB 1 0 CCDD 0 1`, with `strict` if a guess may not be
+//! played twice. Engines ignore the commands they do not
+//! know, and games the lines they do not expect.
+//!
+//! `serve` runs this crate's solver as an engine, and `EngineCodeBreaker`
+//! plays an external engine as a `CodeBreaker`.
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, Command as Process, Stdio};
+use std::str::FromStr;
+
+use crate::rules::{Rules, RulesError};
+use crate::solver::{Solver, Strategy};
+use crate::{Code, CodeBreaker, CodePeg, Score, SIZE};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Umi,
+    IsReady,
+    Position(Rules, Vec<(Code, Score)>),
+    Go,
+    Quit,
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::Umi => write!(f, "umi"),
+            Command::IsReady => write!(f, "isready"),
+            Command::Position(rules, rounds) => {
+                let duplicates = if rules.duplicates() {
+                    "duplicates"
+                } else {
+                    "unique"
+                };
+                write!(f, "position {} {}", rules.colors(), duplicates)?;
+                if rules.strict() {
+                    write!(f, " strict")?;
+                }
+                for (guess, score) in rounds {
+                    write!(f, " {} {} {}", guess, score.matches(), score.presents())?;
+                }
+                Ok(())
+            }
+            Command::Go => write!(f, "go"),
+            Command::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ParseCommandError {
+    /// The line is not a command, or not a well formed one.
+    Invalid(String),
+    /// The rules of the position are not supported.
+    Rules(RulesError),
+}
+
+impl fmt::Display for ParseCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseCommandError::Invalid(command) => write!(f, "invalid command '{}'", command),
+            ParseCommandError::Rules(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ParseCommandError {}
+
+impl FromStr for Command {
+    type Err = ParseCommandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseCommandError::Invalid(s.trim().to_string());
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words[..] {
+            ["umi"] => Ok(Command::Umi),
+            ["isready"] => Ok(Command::IsReady),
+            ["go"] => Ok(Command::Go),
+            ["quit"] => Ok(Command::Quit),
+            ["position", colors, duplicates, ref rounds @ ..] => {
+                let colors = colors.parse().map_err(|_| invalid())?;
+                let duplicates = match duplicates {
+                    "duplicates" => true,
+                    "unique" => false,
+                    _ => return Err(invalid()),
+                };
+                let (strict, rounds) = match rounds {
+                    ["strict", rounds @ ..] => (true, rounds),
+                    rounds => (false, rounds),
+                };
+                if rounds.len() % 3 != 0 {
+                    return Err(invalid());
+                }
+                let rules = Rules::new(colors, duplicates)
+                    .map_err(ParseCommandError::Rules)?
+                    .with_strict(strict);
+                let rounds = rounds
+                    .chunks(3)
+                    .map(|round| {
+                        let guess = round[0].parse().map_err(|_| invalid())?;
+                        let matches = round[1].parse().map_err(|_| invalid())?;
+                        let presents = round[2].parse().map_err(|_| invalid())?;
+                        let score = Score::from_counts(matches, presents).ok_or_else(invalid)?;
+                        Ok((guess, score))
+                    })
+                    .collect::<Result<_, ParseCommandError>>()?;
+                Ok(Command::Position(rules, rounds))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Answers the commands read from `input` with the solver, playing
+/// `strategy`, until `quit` or the end of the input.
+pub fn serve<R: BufRead, W: Write>(input: R, mut output: W, strategy: Strategy) -> io::Result<()> {
+    let mut solver = Solver::with_strategy(strategy);
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match line.parse() {
+            Ok(Command::Umi) => {
+                writeln!(output, "id name mastermind {}", env!("CARGO_PKG_VERSION"))?;
+                writeln!(output, "umiok")?;
+            }
+            Ok(Command::IsReady) => writeln!(output, "readyok")?,
+            Ok(Command::Position(rules, rounds)) => {
+                solver = Solver::with_rules(rules, strategy);
+                for (guess, score) in rounds {
+                    solver.record(guess, score);
+                }
+            }
+            Ok(Command::Go) => {
+                writeln!(output, "info candidates {}", solver.candidates().len())?;
+                if solver.candidates().is_empty() {
+                    writeln!(output, "bestguess none")?;
+                } else {
+                    writeln!(output, "bestguess {}", solver.guess_code())?;
+                }
+            }
+            Ok(Command::Quit) => break,
+            Err(err) => writeln!(output, "info string {}", err)?,
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// The streams to talk to an engine.
+struct Pipes {
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+}
+
+impl Pipes {
+    fn send(&mut self, command: &Command) -> io::Result<()> {
+        writeln!(self.output, "{}", command)?;
+        self.output.flush()
+    }
+
+    /// Waits for the next line, or fails once the engine stopped.
+    fn receive(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        match self.input.read_line(&mut line)? {
+            0 => Err(io::ErrorKind::UnexpectedEof.into()),
+            _ => Ok(line.trim().to_string()),
+        }
+    }
+}
+
+/// An external engine playing as the code breaker.
+///
+/// It forfeits if the engine fails, answers something else than a guess, or
+/// finds no code consistent with the scores (see `take_error`).
+pub struct EngineCodeBreaker {
+    pipes: RefCell<Pipes>,
+    child: Option<Child>,
+    name: Option<String>,
+    rules: Rules,
+    rounds: Vec<(Code, Score)>,
+    guess: Cell<Option<Code>>,
+    error: RefCell<Option<io::Error>>,
+}
+
+impl EngineCodeBreaker {
+    /// Talks to an engine, reading its answers from `input` and writing the
+    /// commands to `output`, and waits for it to be ready.
+    pub fn new<R: BufRead + 'static, W: Write + 'static>(
+        input: R,
+        output: W,
+        rules: Rules,
+    ) -> io::Result<Self> {
+        let mut pipes = Pipes {
+            input: Box::new(input),
+            output: Box::new(output),
+        };
+        pipes.send(&Command::Umi)?;
+        let mut name = None;
+        loop {
+            let line = pipes.receive()?;
+            if let Some(id) = line.strip_prefix("id name ") {
+                name = Some(id.to_string());
+            } else if line == "umiok" {
+                break;
+            }
+        }
+        Ok(EngineCodeBreaker {
+            pipes: RefCell::new(pipes),
+            child: None,
+            name,
+            rules,
+            rounds: Vec::new(),
+            guess: Cell::new(None),
+            error: RefCell::new(None),
+        })
+    }
+
+    /// Starts `command`, split on whitespace, as the engine.
+    pub fn spawn(command: &str, rules: Rules) -> io::Result<Self> {
+        let mut words = command.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+        let mut child = Process::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(io::Error::other(
+                "the engine's standard streams are not piped",
+            ));
+        };
+        let stdout = BufReader::new(stdout);
+        let mut engine = EngineCodeBreaker::new(stdout, stdin, rules)?;
+        engine.child = Some(child);
+        Ok(engine)
+    }
+
+    /// The name the engine gave, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Why the engine forfeited, if it did.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.get_mut().take()
+    }
+
+    /// Asks the engine for its guess, or `None` if it found no code
+    /// consistent with the scores.
+    pub fn best_guess(&self) -> io::Result<Option<Code>> {
+        let mut pipes = self.pipes.borrow_mut();
+        pipes.send(&Command::Position(self.rules, self.rounds.clone()))?;
+        pipes.send(&Command::Go)?;
+        loop {
+            let line = pipes.receive()?;
+            let Some(guess) = line.strip_prefix("bestguess ") else {
+                continue;
+            };
+            return match guess.trim() {
+                "none" => Ok(None),
+                guess => guess.parse().map(Some).map_err(|_| {
+                    let error = format!("invalid guess '{}'", guess);
+                    io::Error::new(io::ErrorKind::InvalidData, error)
+                }),
+            };
+        }
+    }
+}
+
+impl CodeBreaker for EngineCodeBreaker {
+    fn guess_code(&self) -> Code {
+        let guess = self.best_guess().and_then(|guess| {
+            guess.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no guess left"))
+        });
+        match guess {
+            Ok(guess) => {
+                self.guess.set(Some(guess));
+                guess
+            }
+            Err(err) => {
+                *self.error.borrow_mut() = Some(err);
+                // Ignored by the game, since the engine forfeited.
+                Code::new([CodePeg::ALL[0]; SIZE])
+            }
+        }
+    }
+
+    fn set_score(&mut self, score: Score) {
+        // Only scored after a guess.
+        if let Some(guess) = self.guess.take() {
+            self.rounds.push((guess, score));
+        }
+    }
+
+    fn loses(&mut self) {}
+
+    fn forfeited(&self) -> bool {
+        self.error.borrow().is_some()
+    }
+}
+
+impl Drop for EngineCodeBreaker {
+    fn drop(&mut self) {
+        // The engine may already be gone.
+        let _ = self.pipes.get_mut().send(&Command::Quit);
+        if let Some(child) = &mut self.child {
+            let _ = child.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_engine {
+    use std::num::NonZeroUsize;
+
+    use std::io::Cursor;
+    use std::sync::mpsc;
+    use std::thread;
+
+    use super::*;
+    use crate::testutil::DeterministicCodeMaker;
+    use crate::Game;
+
+    #[test]
+    fn commands() {
+        let rules = Rules::new(5, false).unwrap();
+        let rounds = vec![("ABCD".parse().unwrap(), Score::from_counts(1, 2).unwrap())];
+        let command = Command::Position(rules, rounds);
+        assert_eq!(command.to_string(), "position 5 unique ABCD 1 2");
+        assert_eq!(command.to_string().parse::<Command>().unwrap(), command);
+        let command = Command::Position(rules.with_strict(true), Vec::new());
+        assert_eq!(command.to_string(), "position 5 unique strict");
+        assert_eq!(command.to_string().parse::<Command>().unwrap(), command);
+        assert_eq!("go".parse::<Command>().unwrap(), Command::Go);
+        assert_eq!(
+            "position 6 duplicates ABCD 1".parse::<Command>(),
+            Err(ParseCommandError::Invalid(
+                "position 6 duplicates ABCD 1".to_string()
+            ))
+        );
+        assert_eq!(
+            "position 9 duplicates".parse::<Command>(),
+            Err(ParseCommandError::Rules(RulesError::Colors(9)))
+        );
+    }
+
+    #[test]
+    fn serves() {
+        let input = Cursor::new("umi\nposition 6 duplicates AABB 4 0\ngo\nquit\ngo\n");
+        let mut output = Vec::new();
+        serve(input, &mut output, Strategy::default()).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("id name mastermind"));
+        assert_eq!(lines[1..], ["umiok", "info candidates 1", "bestguess AABB"]);
+    }
+
+    /// A pipe from one thread to another.
+    struct Pipe(mpsc::Sender<Vec<u8>>);
+
+    impl Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let _ = self.0.send(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct Receiver(mpsc::Receiver<Vec<u8>>, Vec<u8>);
+
+    impl io::Read for Receiver {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.1.is_empty() {
+                self.1 = self.0.recv().unwrap_or_default();
+            }
+            let n = buf.len().min(self.1.len());
+            buf[..n].copy_from_slice(&self.1[..n]);
+            self.1.drain(..n);
+            Ok(n)
+        }
+    }
+
+    fn pipe() -> (Pipe, BufReader<Receiver>) {
+        let (sender, receiver) = mpsc::channel();
+        (Pipe(sender), BufReader::new(Receiver(receiver, Vec::new())))
+    }
+
+    #[test]
+    fn plays_an_engine() {
+        let (to_engine, engine_input) = pipe();
+        let (engine_output, from_engine) = pipe();
+        let engine = thread::spawn(move || serve(engine_input, engine_output, Strategy::Minimax));
+
+        let mut code_breaker =
+            EngineCodeBreaker::new(from_engine, to_engine, Rules::default()).unwrap();
+        assert!(code_breaker.name().is_some());
+        let code_maker = DeterministicCodeMaker::new("CAFE".parse().unwrap());
+        Game::new(
+            NonZeroUsize::new(5).unwrap(),
+            &code_maker,
+            &mut code_breaker,
+        )
+        .play();
+        let (guess, score) = *code_breaker.rounds.last().unwrap();
+        assert_eq!(guess, "CAFE".parse().unwrap());
+        assert_eq!(score.matches(), 4);
+
+        drop(code_breaker);
+        engine.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn forfeits_once_the_engine_stops() {
+        let input = Cursor::new(b"umiok\n".to_vec());
+        let mut code_breaker = EngineCodeBreaker::new(input, io::sink(), Rules::default()).unwrap();
+        let code_maker = DeterministicCodeMaker::new("CAFE".parse().unwrap());
+        Game::new(
+            NonZeroUsize::new(5).unwrap(),
+            &code_maker,
+            &mut code_breaker,
+        )
+        .play();
+        assert!(code_breaker.rounds.is_empty());
+        let error = code_breaker.take_error().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,286 @@
+//! A C interface, with the `ffi` feature, so that C and C++ programs, or
+//! any language which can call C, can embed the engine. `cbindgen` writes
+//! its header, `mastermind.h`, from this module, as set up in
+//! `cbindgen.toml`.
+//!
+//! Games are handles created by `mastermind_game_new` and released by
+//! `mastermind_game_free`. Codes are passed as NUL-terminated strings such
+//! as `"ABCD"`, and functions which can fail return a `MastermindStatus`.
+
+use std::ffi::{c_char, CStr};
+use std::num::NonZeroUsize;
+use std::ptr;
+
+use crate::rules::Rules;
+use crate::session::{self, GameSession, GuessError};
+use crate::{Code, Scorer, SIZE};
+
+/// The size of the buffers codes are written to, with their NUL.
+pub const MASTERMIND_CODE_LEN: usize = SIZE + 1;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MastermindStatus {
+    Ok,
+    /// A pointer is null, or a string is not UTF-8.
+    InvalidArgument,
+    /// A string is not a code.
+    InvalidCode,
+    /// The rules of the game do not allow the guess.
+    Rules,
+    /// The game is already over.
+    Over,
+    /// The game is not over yet.
+    Playing,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MastermindState {
+    Playing,
+    Won,
+    Lost,
+    /// The game is null.
+    Invalid,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MastermindScore {
+    /// Pegs of the right color in the right place.
+    pub matched: usize,
+    /// Pegs of the right color in the wrong place.
+    pub present: usize,
+}
+
+impl From<crate::Score> for MastermindScore {
+    fn from(score: crate::Score) -> Self {
+        MastermindScore {
+            matched: score.matches(),
+            present: score.presents(),
+        }
+    }
+}
+
+/// A game against a random code, played one guess at a time.
+pub struct MastermindGame {
+    session: GameSession,
+}
+
+unsafe fn code(s: *const c_char) -> Result<Code, MastermindStatus> {
+    if s.is_null() {
+        return Err(MastermindStatus::InvalidArgument);
+    }
+    let s = CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| MastermindStatus::InvalidArgument)?;
+    s.parse().map_err(|_| MastermindStatus::InvalidCode)
+}
+
+/// Writes `code` and its NUL to `out`.
+unsafe fn write_code(code: Code, out: *mut c_char) {
+    let s = code.to_string();
+    ptr::copy_nonoverlapping(s.as_ptr().cast(), out, SIZE);
+    *out.add(SIZE) = 0;
+}
+
+/// Writes the score of `guess` against `secret` to `score`.
+///
+/// # Safety
+///
+/// `secret` and `guess` must be null or NUL-terminated strings, and `score`
+/// null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn mastermind_score(
+    secret: *const c_char,
+    guess: *const c_char,
+    score: *mut MastermindScore,
+) -> MastermindStatus {
+    if score.is_null() {
+        return MastermindStatus::InvalidArgument;
+    }
+    let (secret, guess) = match (code(secret), code(guess)) {
+        (Ok(secret), Ok(guess)) => (secret, guess),
+        (Err(err), _) | (_, Err(err)) => return err,
+    };
+    *score = Scorer::new(secret).score(guess).into();
+    MastermindStatus::Ok
+}
+
+/// Starts a game against a random code, or returns null if the rules are
+/// not valid or `max_round` is 0. The game must be released with
+/// `mastermind_game_free`.
+#[no_mangle]
+pub extern "C" fn mastermind_game_new(
+    colors: usize,
+    duplicates: bool,
+    max_round: usize,
+) -> *mut MastermindGame {
+    match (Rules::new(colors, duplicates), NonZeroUsize::new(max_round)) {
+        (Ok(rules), Some(max_round)) => Box::into_raw(Box::new(MastermindGame {
+            session: GameSession::random(rules, max_round),
+        })),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Starts a game like `mastermind_game_new`, against a code drawn from
+/// `seed`, so that the same seed hides the same code. `strict` rules reject
+/// a guess played twice.
+#[no_mangle]
+pub extern "C" fn mastermind_game_new_seeded(
+    colors: usize,
+    duplicates: bool,
+    strict: bool,
+    max_round: usize,
+    seed: u64,
+) -> *mut MastermindGame {
+    match (Rules::new(colors, duplicates), NonZeroUsize::new(max_round)) {
+        (Ok(rules), Some(max_round)) => Box::into_raw(Box::new(MastermindGame {
+            session: GameSession::seeded(rules.with_strict(strict), max_round, seed),
+        })),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Releases a game. Does nothing if `game` is null.
+///
+/// # Safety
+///
+/// `game` must be null or a game from `mastermind_game_new`, not released
+/// yet.
+#[no_mangle]
+pub unsafe extern "C" fn mastermind_game_free(game: *mut MastermindGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// Plays `guess` and writes its score to `score`.
+///
+/// # Safety
+///
+/// `game` must be null or a live game, `guess` null or a NUL-terminated
+/// string, and `score` null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn mastermind_game_guess(
+    game: *mut MastermindGame,
+    guess: *const c_char,
+    score: *mut MastermindScore,
+) -> MastermindStatus {
+    let Some(game) = game.as_mut() else {
+        return MastermindStatus::InvalidArgument;
+    };
+    if score.is_null() {
+        return MastermindStatus::InvalidArgument;
+    }
+    let guess = match code(guess) {
+        Ok(guess) => guess,
+        Err(err) => return err,
+    };
+    match game.session.guess(guess) {
+        Ok(result) => {
+            *score = result.into();
+            MastermindStatus::Ok
+        }
+        Err(GuessError::Over) => MastermindStatus::Over,
+        Err(GuessError::Rules(_)) => MastermindStatus::Rules,
+    }
+}
+
+/// # Safety
+///
+/// `game` must be null or a live game.
+#[no_mangle]
+pub unsafe extern "C" fn mastermind_game_state(game: *const MastermindGame) -> MastermindState {
+    let Some(game) = game.as_ref() else {
+        return MastermindState::Invalid;
+    };
+    match game.session.state() {
+        session::State::Playing => MastermindState::Playing,
+        session::State::Won => MastermindState::Won,
+        session::State::Lost => MastermindState::Lost,
+    }
+}
+
+/// The number of guesses played so far, or 0 if `game` is null.
+///
+/// # Safety
+///
+/// `game` must be null or a live game.
+#[no_mangle]
+pub unsafe extern "C" fn mastermind_game_rounds(game: *const MastermindGame) -> usize {
+    game.as_ref().map_or(0, |game| game.session.rounds().len())
+}
+
+/// Writes the code to `out` once the game is over.
+///
+/// # Safety
+///
+/// `game` must be null or a live game, and `out` null or valid for writes
+/// of `MASTERMIND_CODE_LEN` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn mastermind_game_secret(
+    game: *const MastermindGame,
+    out: *mut c_char,
+) -> MastermindStatus {
+    let Some(game) = game.as_ref() else {
+        return MastermindStatus::InvalidArgument;
+    };
+    if out.is_null() {
+        return MastermindStatus::InvalidArgument;
+    }
+    match game.session.secret() {
+        Some(secret) => {
+            write_code(secret, out);
+            MastermindStatus::Ok
+        }
+        None => MastermindStatus::Playing,
+    }
+}
+
+#[cfg(test)]
+mod test_ffi {
+    use std::ffi::CString;
+
+    use super::*;
+
+    #[test]
+    fn plays() {
+        let c = |s: &str| CString::new(s).unwrap();
+        let mut score = MastermindScore::default();
+        unsafe {
+            let status = mastermind_score(c("ABCD").as_ptr(), c("AFBC").as_ptr(), &mut score);
+            assert_eq!(status, MastermindStatus::Ok);
+            assert_eq!((score.matched, score.present), (1, 2));
+            let status = mastermind_score(c("ABCD").as_ptr(), c("AB").as_ptr(), &mut score);
+            assert_eq!(status, MastermindStatus::InvalidCode);
+
+            assert!(mastermind_game_new(0, true, 3).is_null());
+            assert!(mastermind_game_new(1, true, 0).is_null());
+            assert!(mastermind_game_new_seeded(1, true, false, 0, 7).is_null());
+            let game = mastermind_game_new(1, true, 3);
+            let mut secret = [0 as c_char; MASTERMIND_CODE_LEN];
+            let status = mastermind_game_secret(game, secret.as_mut_ptr());
+            assert_eq!(status, MastermindStatus::Playing);
+            let status = mastermind_game_guess(game, c("BBBB").as_ptr(), &mut score);
+            assert_eq!(status, MastermindStatus::Rules);
+            let status = mastermind_game_guess(game, ptr::null(), &mut score);
+            assert_eq!(status, MastermindStatus::InvalidArgument);
+
+            let status = mastermind_game_guess(game, c("AAAA").as_ptr(), &mut score);
+            assert_eq!(status, MastermindStatus::Ok);
+            assert_eq!(score.matched, 4);
+            assert_eq!(mastermind_game_state(game), MastermindState::Won);
+            assert_eq!(mastermind_game_rounds(game), 1);
+            assert_eq!(mastermind_game_state(ptr::null()), MastermindState::Invalid);
+            assert_eq!(mastermind_game_rounds(ptr::null()), 0);
+            let status = mastermind_game_guess(game, c("AAAA").as_ptr(), &mut score);
+            assert_eq!(status, MastermindStatus::Over);
+            let status = mastermind_game_secret(game, secret.as_mut_ptr());
+            assert_eq!(status, MastermindStatus::Ok);
+            assert_eq!(CStr::from_ptr(secret.as_ptr()).to_str(), Ok("AAAA"));
+            mastermind_game_free(game);
+        }
+    }
+}
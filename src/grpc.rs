@@ -0,0 +1,290 @@
+//! A gRPC service for typed clients in any language, with the `grpc`
+//! feature.
+//!
+//! The service is defined in `proto/mastermind.proto`. `server` serves it,
+//! and `proto::mastermind_client::MastermindClient` is a client for it.
+//! Games are kept in memory until the server stops.
+//!
+//! Messages are limited to `MAX_MESSAGE_LEN` bytes. Servers made with
+//! `limited_server` also limit the games each client creates and the
+//! guesses sent to each game: requests over the limit fail with
+//! `RESOURCE_EXHAUSTED`.
+
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::num::NonZeroUsize;
+use std::pin::Pin;
+use std::time::Instant;
+
+use futures_util::{stream, Stream};
+use tokio::sync::watch;
+use tonic::{Request, Response, Status};
+
+use crate::manager::SessionManager;
+use crate::ratelimit::{Limits, RateLimiter};
+use crate::rules::Rules;
+use crate::session::{self, GameSession, GuessError};
+use crate::{Code, CodePeg, SIZE};
+
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("mastermind");
+}
+
+use proto::mastermind_server::{Mastermind, MastermindServer};
+use proto::{CreateGameRequest, Game, GameId, GuessRequest, Round, State};
+
+pub const DEFAULT_ROUNDS: NonZeroUsize = NonZeroUsize::new(10).unwrap();
+/// The largest message decoded, in bytes.
+pub const MAX_MESSAGE_LEN: usize = 1024;
+
+/// A game, and the channel its observers watch.
+struct Entry {
+    session: GameSession,
+    updates: watch::Sender<Game>,
+}
+
+#[derive(Default)]
+pub struct Service {
+    games: SessionManager<Entry>,
+    clients: Option<RateLimiter<Option<IpAddr>>>,
+    guesses: Option<RateLimiter<u64>>,
+}
+
+impl Service {
+    /// A service whose clients may only create games and guess as often as
+    /// `limits` allow.
+    pub fn with_limits(limits: Limits) -> Self {
+        Service {
+            games: SessionManager::new(),
+            clients: limits.games.map(RateLimiter::new),
+            guesses: limits.guesses.map(RateLimiter::new),
+        }
+    }
+}
+
+/// The service, each server having its own games.
+pub fn server() -> MastermindServer<Service> {
+    serve(Service::default())
+}
+
+/// Like `server`, for public servers limited as `Service::with_limits`.
+pub fn limited_server(limits: Limits) -> MastermindServer<Service> {
+    serve(Service::with_limits(limits))
+}
+
+fn serve(service: Service) -> MastermindServer<Service> {
+    MastermindServer::new(service).max_decoding_message_size(MAX_MESSAGE_LEN)
+}
+
+/// Counts a request of `key`, and returns its error if it is over its limit.
+fn over_limit<K: Eq + Hash>(limiter: Option<&RateLimiter<K>>, key: K) -> Option<Status> {
+    let limited = limiter?.check(key, Instant::now()).err()?;
+    Some(Status::resource_exhausted(limited.to_string()))
+}
+
+fn game(id: u64, session: &GameSession) -> Game {
+    let state = match session.state() {
+        session::State::Playing => State::Playing,
+        session::State::Won => State::Won,
+        session::State::Lost => State::Lost,
+    };
+    Game {
+        id,
+        state: state.into(),
+        max_rounds: session.max_round().map_or(0, NonZeroUsize::get) as u32,
+        pegs: SIZE as u32,
+        colors: session.rules().colors() as u32,
+        duplicates: session.rules().duplicates(),
+        strict: session.rules().strict(),
+        rounds: session
+            .rounds()
+            .iter()
+            .map(|(guess, score)| Round {
+                guess: guess.to_string(),
+                matched: score.matches() as u32,
+                present: score.presents() as u32,
+            })
+            .collect(),
+        code: session.secret().map(|code| code.to_string()),
+    }
+}
+
+fn not_found(id: u64) -> Status {
+    Status::not_found(format!("no game {}", id))
+}
+
+#[tonic::async_trait]
+impl Mastermind for Service {
+    async fn create_game(
+        &self,
+        request: Request<CreateGameRequest>,
+    ) -> Result<Response<Game>, Status> {
+        let client = request.remote_addr().map(|address| address.ip());
+        if let Some(status) = over_limit(self.clients.as_ref(), client) {
+            return Err(status);
+        }
+        let request = request.into_inner();
+        let colors = request.colors.map_or(CodePeg::ALL.len(), |c| c as usize);
+        let rules = Rules::new(colors, request.duplicates.unwrap_or(true))
+            .map_err(|err| Status::invalid_argument(err.to_string()))?
+            .with_strict(request.strict.unwrap_or(false));
+        let rounds = request
+            .rounds
+            .map_or(Some(DEFAULT_ROUNDS), |r| NonZeroUsize::new(r as usize))
+            .ok_or_else(|| Status::invalid_argument("a game needs at least one round"))?;
+
+        let session = match request.seed {
+            Some(seed) => GameSession::seeded(rules, rounds, seed),
+            None => GameSession::random(rules, rounds),
+        };
+        let (updates, _) = watch::channel(game(0, &session));
+        let now = Instant::now();
+        let (_, view) = self
+            .games
+            .insert_with(Entry { session, updates }, now, |id, entry| {
+                let view = game(id, &entry.session);
+                entry.updates.send_replace(view.clone());
+                view
+            });
+        Ok(Response::new(view))
+    }
+
+    async fn get_game(&self, request: Request<GameId>) -> Result<Response<Game>, Status> {
+        let id = request.into_inner().id;
+        let view = self
+            .games
+            .with(id, Instant::now(), |entry| game(id, &entry.session));
+        Ok(Response::new(view.ok_or_else(|| not_found(id))?))
+    }
+
+    async fn guess(&self, request: Request<GuessRequest>) -> Result<Response<Game>, Status> {
+        let GuessRequest { id, guess } = request.into_inner();
+        if let Some(status) = over_limit(self.guesses.as_ref(), id) {
+            return Err(status);
+        }
+        let guess: Code = guess
+            .parse()
+            .map_err(|err: crate::ParseCodeError| Status::invalid_argument(err.to_string()))?;
+        let view = self.games.with(id, Instant::now(), |entry| {
+            entry.session.guess(guess)?;
+            let view = game(id, &entry.session);
+            entry.updates.send_replace(view.clone());
+            Ok(view)
+        });
+        let view = view.ok_or_else(|| not_found(id))?;
+        let view = view.map_err(|err| match err {
+            GuessError::Over => Status::failed_precondition(err.to_string()),
+            GuessError::Rules(_) => Status::invalid_argument(err.to_string()),
+        })?;
+        Ok(Response::new(view))
+    }
+
+    type ObserveStream = Pin<Box<dyn Stream<Item = Result<Game, Status>> + Send>>;
+
+    async fn observe(
+        &self,
+        request: Request<GameId>,
+    ) -> Result<Response<Self::ObserveStream>, Status> {
+        let id = request.into_inner().id;
+        let mut updates = self
+            .games
+            .with(id, Instant::now(), |entry| entry.updates.subscribe())
+            .ok_or_else(|| not_found(id))?;
+        // Send the game as it is first.
+        updates.mark_changed();
+        let games = stream::unfold((updates, false), |(mut updates, over)| async move {
+            if over {
+                return None;
+            }
+            updates.changed().await.ok()?;
+            let game = updates.borrow_and_update().clone();
+            let over = game.state() != State::Playing;
+            Some((Ok(game), (updates, over)))
+        });
+        Ok(Response::new(Box::pin(games)))
+    }
+}
+
+#[cfg(test)]
+mod test_grpc {
+    use std::net::SocketAddr;
+
+    use futures_util::StreamExt;
+    use tokio::net::TcpListener;
+    use tonic::transport::server::TcpIncoming;
+    use tonic::transport::Server;
+
+    use super::proto::mastermind_client::MastermindClient;
+    use super::*;
+
+    #[test]
+    fn plays_a_game() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address: SocketAddr = listener.local_addr().unwrap();
+            let incoming = TcpIncoming::from(listener);
+            tokio::spawn(
+                Server::builder()
+                    .add_service(server())
+                    .serve_with_incoming(incoming),
+            );
+
+            let mut client = MastermindClient::connect(format!("http://{}", address))
+                .await
+                .unwrap();
+            let request = CreateGameRequest {
+                rounds: Some(2),
+                colors: Some(1),
+                duplicates: None,
+                seed: None,
+                strict: Some(true),
+            };
+            let game = client.create_game(request).await.unwrap().into_inner();
+            assert_eq!(game.state(), State::Playing);
+            assert_eq!((game.max_rounds, game.colors, game.code), (2, 1, None));
+            assert!(game.strict);
+            let mut observer = client
+                .observe(GameId { id: game.id })
+                .await
+                .unwrap()
+                .into_inner();
+            assert!(observer.next().await.unwrap().unwrap().rounds.is_empty());
+
+            let guess = |guess: &str| GuessRequest {
+                id: game.id,
+                guess: guess.to_string(),
+            };
+            let status = client.guess(guess("ABCD")).await.unwrap_err();
+            assert_eq!(status.code(), tonic::Code::InvalidArgument);
+            // With a single color, the code can only be AAAA.
+            let won = client.guess(guess("AAAA")).await.unwrap().into_inner();
+            assert_eq!(won.state(), State::Won);
+            assert_eq!(won.code.as_deref(), Some("AAAA"));
+
+            assert_eq!(observer.next().await.unwrap().unwrap(), won);
+            assert!(observer.next().await.is_none());
+            let status = client.get_game(GameId { id: 7 }).await.unwrap_err();
+            assert_eq!(status.code(), tonic::Code::NotFound);
+        });
+    }
+
+    #[test]
+    fn limits() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let service = Service::with_limits(Limits {
+            games: Some(crate::ratelimit::Quota::per_minute(1)),
+            guesses: None,
+        });
+        let create = || service.create_game(Request::new(CreateGameRequest::default()));
+        assert!(runtime.block_on(create()).is_ok());
+        let status = runtime.block_on(create()).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+    }
+}
@@ -0,0 +1,405 @@
+//! A REST API for web apps, with the `http` feature.
+//!
+//! | Request                       | Body                                   | Response            |
+//! |-------------------------------|----------------------------------------|---------------------|
+//! | `POST /games`                 | `{"rounds", "colors", "duplicates"}`, all optional | the new game |
+//! | `GET /games/{id}`             |                                        | the game            |
+//! | `POST /games/{id}/guesses`    | `{"guess": "ABCD"}`                    | the game            |
+//! | `GET /games/{id}/transcript`  |                                        | the transcript, as text |
+//!
+//! Games are JSON objects, which only include the code once the game is
+//! over. Transcripts list one `<guess> <matched> <present>` round per line.
+//! Errors are `{"error": message}` objects. Games are kept in memory until
+//! the server stops, or until they are idle for the timeout of the router,
+//! if any.
+//!
+//! Request bodies are limited to `MAX_BODY_LEN` bytes. Routers made with
+//! `limited_router` also limit the games each client creates, clients being
+//! told apart by their address if the router is served with it (see
+//! `Router::into_make_service_with_connect_info`), and the guesses sent to
+//! each game: requests over the limit get a `429 Too Many Requests`
+//! response, with a `Retry-After` header.
+
+use std::hash::Hash;
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::extract::{self, ConnectInfo, DefaultBodyLimit, Path, Request};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::manager::SessionManager;
+use crate::ratelimit::{Limits, RateLimited, RateLimiter};
+use crate::rules::Rules;
+use crate::session::{GameSession, GuessError, State};
+use crate::{Code, CodePeg, ParseCodeError, SIZE};
+
+pub const DEFAULT_ROUNDS: NonZeroUsize = NonZeroUsize::new(10).unwrap();
+/// The largest request body accepted, in bytes.
+pub const MAX_BODY_LEN: usize = 1024;
+
+type Shared = Arc<SessionManager>;
+
+/// The routes of the API, each server having its own games.
+pub fn router() -> Router {
+    routes(SessionManager::new(), Limits::default())
+}
+
+/// Like `router`, but dropping the games unused for `timeout` whenever a
+/// game is created.
+pub fn router_with_idle_timeout(timeout: Duration) -> Router {
+    routes(
+        SessionManager::with_idle_timeout(timeout),
+        Limits::default(),
+    )
+}
+
+/// Like `router`, for public servers: clients may only create games and
+/// guess as often as `limits` allow, and games unused for `idle_timeout`,
+/// if any, are dropped.
+pub fn limited_router(limits: Limits, idle_timeout: Option<Duration>) -> Router {
+    let games = match idle_timeout {
+        Some(timeout) => SessionManager::with_idle_timeout(timeout),
+        None => SessionManager::new(),
+    };
+    routes(games, limits)
+}
+
+fn routes(games: SessionManager, limits: Limits) -> Router {
+    let mut create = post(create_game);
+    if let Some(quota) = limits.games {
+        let limiter = Arc::new(RateLimiter::new(quota));
+        create = create.route_layer(middleware::from_fn_with_state(limiter, limit_clients));
+    }
+    let mut guess = post(submit_guess);
+    if let Some(quota) = limits.guesses {
+        let limiter = Arc::new(RateLimiter::new(quota));
+        guess = guess.route_layer(middleware::from_fn_with_state(limiter, limit_games));
+    }
+    Router::new()
+        .route("/games", create)
+        .route("/games/{id}", get(get_game))
+        .route("/games/{id}/guesses", guess)
+        .route("/games/{id}/transcript", get(get_transcript))
+        .layer(DefaultBodyLimit::max(MAX_BODY_LEN))
+        .with_state(Shared::new(games))
+}
+
+/// Counts a request of `key`, and returns the response to it if it is over
+/// its limit.
+fn over_limit<K: Eq + Hash>(limiter: &RateLimiter<K>, key: K) -> Option<Response> {
+    let limited = limiter.check(key, Instant::now()).err()?;
+    let RateLimited { retry_after } = limited;
+    let mut response = Error(StatusCode::TOO_MANY_REQUESTS, limited.to_string()).into_response();
+    let seconds = retry_after.as_secs_f64().ceil() as u64;
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+    Some(response)
+}
+
+/// Limits the requests of each client, or of all the clients together if
+/// their address is unknown.
+async fn limit_clients(
+    extract::State(limiter): extract::State<Arc<RateLimiter<Option<IpAddr>>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip());
+    match over_limit(&limiter, client) {
+        Some(response) => response,
+        None => next.run(request).await,
+    }
+}
+
+async fn limit_games(
+    extract::State(limiter): extract::State<Arc<RateLimiter<u64>>>,
+    Path(id): Path<u64>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match over_limit(&limiter, id) {
+        Some(response) => response,
+        None => next.run(request).await,
+    }
+}
+
+#[derive(Debug)]
+struct Error(StatusCode, String);
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        #[derive(Serialize)]
+        struct Body {
+            error: String,
+        }
+        (self.0, Json(Body { error: self.1 })).into_response()
+    }
+}
+
+fn unprocessable<E: ToString>(err: E) -> Error {
+    Error(StatusCode::UNPROCESSABLE_ENTITY, err.to_string())
+}
+
+fn not_found(id: u64) -> Error {
+    Error(StatusCode::NOT_FOUND, format!("no game {}", id))
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct NewGame {
+    rounds: Option<NonZeroUsize>,
+    colors: Option<usize>,
+    duplicates: Option<bool>,
+    strict: Option<bool>,
+    /// Draws the code, so that the same seed hides the same code.
+    seed: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewGuess {
+    guess: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct RoundView {
+    guess: String,
+    matched: usize,
+    present: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct GameView {
+    id: u64,
+    state: &'static str,
+    /// `None` if the game goes on until the code is broken.
+    max_rounds: Option<NonZeroUsize>,
+    pegs: usize,
+    colors: usize,
+    duplicates: bool,
+    strict: bool,
+    rounds: Vec<RoundView>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<String>,
+}
+
+impl GameView {
+    fn new(id: u64, session: &GameSession) -> Self {
+        GameView {
+            id,
+            state: match session.state() {
+                State::Playing => "playing",
+                State::Won => "won",
+                State::Lost => "lost",
+            },
+            max_rounds: session.max_round(),
+            pegs: SIZE,
+            colors: session.rules().colors(),
+            duplicates: session.rules().duplicates(),
+            strict: session.rules().strict(),
+            rounds: session
+                .rounds()
+                .iter()
+                .map(|(guess, score)| RoundView {
+                    guess: guess.to_string(),
+                    matched: score.matches(),
+                    present: score.presents(),
+                })
+                .collect(),
+            code: session.secret().map(|code| code.to_string()),
+        }
+    }
+}
+
+async fn create_game(
+    extract::State(games): extract::State<Shared>,
+    new_game: Option<Json<NewGame>>,
+) -> Result<(StatusCode, Json<GameView>), Error> {
+    let Json(new_game) = new_game.unwrap_or_default();
+    let rules = Rules::new(
+        new_game.colors.unwrap_or(CodePeg::ALL.len()),
+        new_game.duplicates.unwrap_or(true),
+    )
+    .map_err(unprocessable)?
+    .with_strict(new_game.strict.unwrap_or(false));
+    let rounds = new_game.rounds.unwrap_or(DEFAULT_ROUNDS);
+
+    let now = Instant::now();
+    games.expire(now);
+    let session = match new_game.seed {
+        Some(seed) => GameSession::seeded(rules, rounds, seed),
+        None => GameSession::random(rules, rounds),
+    };
+    let (_, view) = games.insert_with(session, now, |id, session| GameView::new(id, session));
+    Ok((StatusCode::CREATED, Json(view)))
+}
+
+async fn get_game(
+    extract::State(games): extract::State<Shared>,
+    Path(id): Path<u64>,
+) -> Result<Json<GameView>, Error> {
+    let view = games.with(id, Instant::now(), |session| GameView::new(id, session));
+    Ok(Json(view.ok_or_else(|| not_found(id))?))
+}
+
+async fn submit_guess(
+    extract::State(games): extract::State<Shared>,
+    Path(id): Path<u64>,
+    Json(new_guess): Json<NewGuess>,
+) -> Result<Json<GameView>, Error> {
+    let guess: Code = new_guess
+        .guess
+        .parse()
+        .map_err(unprocessable::<ParseCodeError>)?;
+    let view = games.with(id, Instant::now(), |session| {
+        session.guess(guess).map_err(|err| {
+            let status = match err {
+                GuessError::Over => StatusCode::CONFLICT,
+                GuessError::Rules(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            };
+            Error(status, err.to_string())
+        })?;
+        Ok(GameView::new(id, session))
+    });
+    Ok(Json(view.ok_or_else(|| not_found(id))??))
+}
+
+async fn get_transcript(
+    extract::State(games): extract::State<Shared>,
+    Path(id): Path<u64>,
+) -> Result<String, Error> {
+    let transcript = games.with(id, Instant::now(), |session| {
+        let mut transcript: String = session
+            .rounds()
+            .iter()
+            .map(|(guess, score)| format!("{} {} {}\n", guess, score.matches(), score.presents()))
+            .collect();
+        for (guess, err) in session.violations() {
+            transcript += &format!("# {} rejected: {}\n", guess, err);
+        }
+        transcript
+    });
+    transcript.ok_or_else(|| not_found(id))
+}
+
+#[cfg(test)]
+mod test_http {
+    use axum::extract::FromRequest;
+
+    use super::*;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    fn guess(games: &Shared, id: u64, guess: &str) -> Result<Json<GameView>, Error> {
+        let new_guess = NewGuess {
+            guess: guess.to_string(),
+        };
+        block_on(submit_guess(
+            extract::State(games.clone()),
+            Path(id),
+            Json(new_guess),
+        ))
+    }
+
+    #[test]
+    fn plays_a_game() {
+        let games = Shared::default();
+        let new_game = NewGame {
+            rounds: NonZeroUsize::new(2),
+            colors: Some(4),
+            duplicates: None,
+            strict: Some(true),
+            seed: Some(7),
+        };
+        let (status, Json(game)) = block_on(create_game(
+            extract::State(games.clone()),
+            Some(Json(new_game)),
+        ))
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!((game.id, game.state, game.colors), (0, "playing", 4));
+        assert!(game.strict);
+        assert_eq!(game.code, None);
+        let two = NonZeroUsize::new(2).unwrap();
+        let seeded = GameSession::seeded(Rules::new(4, true).unwrap(), two, 7);
+        assert_eq!(
+            games.with(0, Instant::now(), |game| game.reveal()),
+            Some(seeded.reveal())
+        );
+        // Pick the code, so that the first guesses cannot break it.
+        let session =
+            GameSession::new(Rules::new(4, true).unwrap(), two, "ABCD".parse().unwrap()).unwrap();
+        games.with(0, Instant::now(), |game| *game = session);
+
+        let err = guess(&games, 0, "ABCE").unwrap_err();
+        assert_eq!(err.0, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(guess(&games, 0, "AAAA").is_ok());
+        let Json(game) = guess(&games, 0, "BBBB").unwrap();
+        assert_eq!(game.state, "lost");
+        assert_eq!(game.code.as_deref(), Some("ABCD"));
+        assert_eq!(game.rounds.len(), 2);
+        assert_eq!(
+            guess(&games, 0, "CCCC").unwrap_err().0,
+            StatusCode::CONFLICT
+        );
+
+        let transcript = block_on(get_transcript(extract::State(games.clone()), Path(0))).unwrap();
+        assert!(transcript.starts_with("AAAA "));
+        assert_eq!(transcript.lines().count(), 3);
+        assert!(transcript.ends_with("# ABCE rejected: peg 'E' is not used in this game\n"));
+    }
+
+    #[test]
+    fn errors() {
+        let games = Shared::default();
+        let err = block_on(get_game(extract::State(games.clone()), Path(3))).unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+
+        let new_game = NewGame {
+            colors: Some(9),
+            ..NewGame::default()
+        };
+        let err = block_on(create_game(extract::State(games), Some(Json(new_game)))).unwrap_err();
+        assert_eq!(err.0, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let request = Request::builder()
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(axum::body::Body::from(r#"{"rounds": 0}"#))
+            .unwrap();
+        let rejection = block_on(Json::<NewGame>::from_request(request, &())).unwrap_err();
+        assert_eq!(rejection.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[test]
+    fn limits() {
+        let limiter = RateLimiter::new(crate::ratelimit::Quota::per_minute(1));
+        assert!(over_limit(&limiter, 0).is_none());
+        assert!(over_limit(&limiter, 1).is_none());
+        let response = over_limit(&limiter, 0).unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "60");
+        let quota = Some(crate::ratelimit::Quota::per_minute(10));
+        let _ = limited_router(
+            Limits {
+                games: quota,
+                guesses: quota,
+            },
+            None,
+        );
+    }
+}
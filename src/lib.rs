@@ -1,6 +1,72 @@
+use std::fmt;
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::cancel::CancelToken;
+use crate::rules::{Rules, RulesError};
+use crate::sync::{lock, Mutex};
+
+pub mod analysis;
+#[cfg(feature = "net")]
+pub mod auth;
+#[cfg(feature = "batch")]
+pub mod batch;
+pub mod book;
+pub mod cancel;
+pub mod candidates;
+pub mod chat;
+pub mod clock;
+pub mod contain;
+pub mod engine;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "net")]
+pub mod lobby;
+pub mod manager;
+#[cfg(feature = "net")]
+pub mod net;
+#[cfg(feature = "packed")]
+pub mod packed;
+pub mod position;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod ratelimit;
+pub mod record;
+pub mod render;
+pub mod rules;
+pub mod session;
+pub mod solver;
+#[cfg(feature = "net")]
+pub mod spectate;
+pub mod stateless;
+#[cfg(feature = "store")]
+pub mod store;
+mod sync;
+#[cfg(feature = "table")]
+pub mod table;
+#[cfg(feature = "metrics")]
+pub mod telemetry;
+#[cfg(any(test, feature = "testutil"))]
+pub mod testutil;
+#[cfg(feature = "async")]
+pub mod timeout;
+pub mod verify;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 pub const SIZE: usize = 4;
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum CodePeg {
     A,
     B,
@@ -10,112 +76,711 @@ pub enum CodePeg {
     F,
 }
 
-#[derive(Clone, Copy)]
+impl CodePeg {
+    /// Every peg, in order. A slice rather than an array, so that its length
+    /// is not part of the API and pegs can be added.
+    pub const ALL: &'static [CodePeg] = &[
+        CodePeg::A,
+        CodePeg::B,
+        CodePeg::C,
+        CodePeg::D,
+        CodePeg::E,
+        CodePeg::F,
+    ];
+}
+
+impl TryFrom<char> for CodePeg {
+    type Error = ParseCodeError;
+
+    /// Reads a peg from its letter, in upper or lower case.
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c.to_ascii_uppercase() {
+            'A' => Ok(CodePeg::A),
+            'B' => Ok(CodePeg::B),
+            'C' => Ok(CodePeg::C),
+            'D' => Ok(CodePeg::D),
+            'E' => Ok(CodePeg::E),
+            'F' => Ok(CodePeg::F),
+            _ => Err(ParseCodeError::InvalidPeg(c)),
+        }
+    }
+}
+
+impl fmt::Display for CodePeg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let c = match self {
+            CodePeg::A => 'A',
+            CodePeg::B => 'B',
+            CodePeg::C => 'C',
+            CodePeg::D => 'D',
+            CodePeg::E => 'E',
+            CodePeg::F => 'F',
+        };
+        write!(f, "{}", c)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Code {
     pegs: [CodePeg; SIZE],
 }
 
 impl Code {
-    pub fn new(pegs: [CodePeg; SIZE]) -> Self {
+    /// The number of codes, which the rules may restrict. It grows with
+    /// `CodePeg::ALL`.
+    pub const COUNT: usize = CodePeg::ALL.len().pow(SIZE as u32);
+
+    pub const fn new(pegs: [CodePeg; SIZE]) -> Self {
         Code { pegs }
     }
+
+    pub fn pegs(&self) -> [CodePeg; SIZE] {
+        self.pegs
+    }
+
+    /// The position of the code in `Code::all`.
+    pub fn index(&self) -> usize {
+        self.pegs
+            .iter()
+            .fold(0, |index, &peg| index * CodePeg::ALL.len() + peg as usize)
+    }
+
+    /// The code at `index` in `Code::all`, if there are that many.
+    pub fn from_index(mut index: usize) -> Option<Code> {
+        if index >= Code::COUNT {
+            return None;
+        }
+        let mut pegs = [CodePeg::A; SIZE];
+        for peg in pegs.iter_mut().rev() {
+            *peg = CodePeg::ALL[index % CodePeg::ALL.len()];
+            index /= CodePeg::ALL.len();
+        }
+        Some(Code::new(pegs))
+    }
+
+    /// All the possible codes, in lexicographic order.
+    pub fn all() -> Vec<Code> {
+        let mut codes = vec![Code::new([CodePeg::A; SIZE])];
+        for i in 0..SIZE {
+            codes = codes
+                .into_iter()
+                .flat_map(|code| {
+                    CodePeg::ALL.iter().map(move |&peg| {
+                        let mut pegs = code.pegs;
+                        pegs[i] = peg;
+                        Code::new(pegs)
+                    })
+                })
+                .collect();
+        }
+        codes
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for peg in self.pegs {
+            write!(f, "{}", peg)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ParseCodeError {
+    WrongLength(usize),
+    InvalidPeg(char),
+}
+
+impl fmt::Display for ParseCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseCodeError::WrongLength(len) => {
+                write!(f, "expected {} pegs, got {}", SIZE, len)
+            }
+            ParseCodeError::InvalidPeg(c) => write!(f, "invalid peg '{}'", c),
+        }
+    }
+}
+
+impl std::error::Error for ParseCodeError {}
+
+impl FromStr for Code {
+    type Err = ParseCodeError;
+
+    /// Reads the characters in place, so that parsing never allocates.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let len = s.chars().count();
+        if len != SIZE {
+            return Err(ParseCodeError::WrongLength(len));
+        }
+
+        let mut pegs = [CodePeg::A; SIZE];
+        for (peg, c) in pegs.iter_mut().zip(s.chars()) {
+            *peg = CodePeg::try_from(c)?;
+        }
+        Ok(Code::new(pegs))
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum ScorePeg {
     Match,
     Present,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// A score takes a single byte, so that long transcripts and tables of
+/// scores stay small: its pegs are only laid out when asked for.
+///
+/// A score only keeps its counts, so that scores are equal, and hash the
+/// same, whatever the order their pegs were given in.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Score {
-    pegs: [Option<ScorePeg>; SIZE],
+    /// `matches * (SIZE + 1) + presents`.
+    index: u8,
 }
 
 impl Score {
-    fn new(pegs: [Option<ScorePeg>; SIZE]) -> Self {
-        Score { pegs }
+    const fn new(pegs: [Option<ScorePeg>; SIZE]) -> Self {
+        let (mut matches, mut presents) = (0, 0);
+        let mut i = 0;
+        while i < SIZE {
+            match pegs[i] {
+                Some(ScorePeg::Match) => matches += 1,
+                Some(ScorePeg::Present) => presents += 1,
+                None => {}
+            }
+            i += 1;
+        }
+        Score::from_index(matches * (SIZE + 1) + presents)
+    }
+
+    /// Builds a score from its number of matched and present pegs.
+    ///
+    /// Returns `None` if no guess can get such a score.
+    pub const fn from_counts(matches: usize, presents: usize) -> Option<Self> {
+        // Subtracts rather than adds, as the counts may come from anyone.
+        if matches > SIZE || presents > SIZE - matches || (matches == SIZE - 1 && presents == 1) {
+            return None;
+        }
+        Some(Score::from_index(matches * (SIZE + 1) + presents))
+    }
+
+    /// The score of an `index`, which must come from a valid score.
+    pub(crate) const fn from_index(index: usize) -> Self {
+        Score { index: index as u8 }
+    }
+
+    /// Matched pegs first, then present pegs, then `None`.
+    pub fn pegs(&self) -> [Option<ScorePeg>; SIZE] {
+        let mut pegs = [None; SIZE];
+        for (i, peg) in pegs.iter_mut().enumerate() {
+            if i < self.matches() {
+                *peg = Some(ScorePeg::Match);
+            } else if i < self.matches() + self.presents() {
+                *peg = Some(ScorePeg::Present);
+            }
+        }
+        pegs
+    }
+
+    pub const fn matches(&self) -> usize {
+        self.index() / (SIZE + 1)
+    }
+
+    pub const fn presents(&self) -> usize {
+        self.index() % (SIZE + 1)
+    }
+
+    /// The index of the score among the `(SIZE + 1)^2` match and present
+    /// counts, for tables of scores.
+    pub(crate) const fn index(&self) -> usize {
+        self.index as usize
+    }
+}
+
+impl TryFrom<(u8, u8)> for Score {
+    type Error = ScoreError;
+
+    /// Reads a score from its number of matched and present pegs, e.g. as
+    /// typed by a player or received from the network.
+    fn try_from((matches, presents): (u8, u8)) -> Result<Self, Self::Error> {
+        let (matches, presents) = (usize::from(matches), usize::from(presents));
+        if matches + presents > SIZE {
+            return Err(ScoreError::TooManyPegs(matches + presents));
+        }
+        Score::from_counts(matches, presents).ok_or(ScoreError::Impossible(matches, presents))
+    }
+}
+
+impl fmt::Debug for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Score")
+            .field("matches", &self.matches())
+            .field("presents", &self.presents())
+            .finish()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ScoreError {
+    /// More pegs are scored than a code has.
+    TooManyPegs(usize),
+    /// No guess gets this many matched and present pegs, such as a single
+    /// present peg with every other one matched.
+    Impossible(usize, usize),
+}
+
+impl fmt::Display for ScoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScoreError::TooManyPegs(pegs) => write!(f, "{} pegs scored, only {}", pegs, SIZE),
+            ScoreError::Impossible(matches, presents) => write!(
+                f,
+                "no guess gets {} matched and {} present pegs",
+                matches, presents
+            ),
+        }
     }
 }
 
+impl std::error::Error for ScoreError {}
+
 pub struct Scorer {
     code: Code,
+    /// The number of pegs of each color in the code.
+    colors: [usize; CodePeg::ALL.len()],
 }
 
 impl Scorer {
-    pub fn new(code: Code) -> Self {
-        Scorer { code }
+    pub const fn new(code: Code) -> Self {
+        let mut colors = [0; CodePeg::ALL.len()];
+        let mut i = 0;
+        while i < SIZE {
+            colors[code.pegs[i] as usize] += 1;
+            i += 1;
+        }
+        Scorer { code, colors }
     }
 
-    pub fn score(&self, guess: Code) -> Score {
-        let mut score_accumulator: Vec<ScorePeg> = Vec::with_capacity(SIZE);
-
-        let mut score_peg_not_matched: Vec<CodePeg> = Vec::with_capacity(SIZE);
-        let mut guess_peg_not_matched: Vec<CodePeg> = Vec::with_capacity(SIZE);
-
-        for i in 0..SIZE {
-            if self.code.pegs[i] == guess.pegs[i] {
-                score_accumulator.push(ScorePeg::Match);
+    /// Counts the pegs of each color instead of collecting them, so that
+    /// scoring never allocates.
+    ///
+    /// It is a `const fn`, for tables of scores to be worked out when
+    /// compiling.
+    pub const fn score(&self, guess: Code) -> Score {
+        let mut matches = 0;
+        let mut code_colors = [0; CodePeg::ALL.len()];
+        let mut guess_colors = [0; CodePeg::ALL.len()];
+        let mut i = 0;
+        while i < SIZE {
+            let (code_peg, guess_peg) = (self.code.pegs[i] as usize, guess.pegs[i] as usize);
+            if code_peg == guess_peg {
+                matches += 1;
             } else {
-                score_peg_not_matched.push(self.code.pegs[i]);
-                guess_peg_not_matched.push(guess.pegs[i]);
+                code_colors[code_peg] += 1;
+                guess_colors[guess_peg] += 1;
             }
+            i += 1;
         }
-
-        for peg in guess_peg_not_matched {
-            let index = score_peg_not_matched.iter().position(|&item| item == peg);
-            if let Some(i) = index {
-                score_accumulator.push(ScorePeg::Present);
-                score_peg_not_matched.remove(i);
-            }
+        let mut presents = 0;
+        let mut color = 0;
+        while color < CodePeg::ALL.len() {
+            presents += if code_colors[color] < guess_colors[color] {
+                code_colors[color]
+            } else {
+                guess_colors[color]
+            };
+            color += 1;
         }
+        // At most SIZE pegs are in common, so the score is valid.
+        Score::from_index(matches * (SIZE + 1) + presents)
+    }
 
-        let mut score: [Option<ScorePeg>; SIZE] = [None; SIZE];
-        for i in 0..score_accumulator.len() {
-            score[i] = Some(score_accumulator[i])
+    /// The score of `guess` with its peg at `position` changed to `peg`,
+    /// worked out from `score`, the score of `guess`, for solvers which
+    /// search by changing one peg at a time.
+    ///
+    /// Returns `None` if `position` is not less than `SIZE`, or if `score`
+    /// cannot be the score of `guess`.
+    pub fn rescore_with_change(
+        &self,
+        guess: Code,
+        score: Score,
+        position: usize,
+        peg: CodePeg,
+    ) -> Option<Score> {
+        let old = *guess.pegs.get(position)?;
+        if old == peg {
+            return Some(score);
         }
-        Score::new(score)
+        let code_peg = self.code.pegs[position];
+        let matches = (score.matches() + usize::from(code_peg == peg))
+            .checked_sub(usize::from(code_peg == old))?;
+        // The pegs in common, whatever their position, change by one when
+        // the guess had no more of the old color than the code, or fewer of
+        // the new one.
+        let mut common = score.matches() + score.presents();
+        let count = |color: CodePeg| guess.pegs.iter().filter(|&&p| p == color).count();
+        if count(old) <= self.colors[old as usize] {
+            common = common.checked_sub(1)?;
+        }
+        if count(peg) < self.colors[peg as usize] {
+            common += 1;
+        }
+        Score::from_counts(matches, common.checked_sub(matches)?)
     }
 }
 
+/// The player who picks the code.
+///
+/// The player traits are not sealed, since players are meant to be written
+/// outside this crate: methods added later come with a default instead.
 pub trait CodeMaker {
     fn make_code(&self) -> Code;
 }
 
+/// A code maker which draws a random code allowed by its rules.
+#[derive(Default)]
+pub struct RandomCodeMaker {
+    rules: Rules,
+    /// Draws the codes if seeded, instead of the thread's generator.
+    rng: Option<Mutex<StdRng>>,
+}
+
+impl RandomCodeMaker {
+    pub fn new(rules: Rules) -> Self {
+        RandomCodeMaker { rules, rng: None }
+    }
+
+    /// A code maker which draws the same codes, in the same order, for the
+    /// same `seed`, to replay games.
+    pub fn seeded(rules: Rules, seed: u64) -> Self {
+        RandomCodeMaker {
+            rules,
+            rng: Some(Mutex::new(StdRng::seed_from_u64(seed))),
+        }
+    }
+
+    fn draw(&self, rng: &mut impl Rng) -> Code {
+        let palette = self.rules.palette();
+        let mut pegs = [CodePeg::A; SIZE];
+        if self.rules.duplicates() {
+            for peg in pegs.iter_mut() {
+                *peg = palette[rng.gen_range(0..palette.len())];
+            }
+        } else {
+            for (peg, &chosen) in pegs.iter_mut().zip(palette.choose_multiple(rng, SIZE)) {
+                *peg = chosen;
+            }
+        }
+        Code::new(pegs)
+    }
+}
+
+impl CodeMaker for RandomCodeMaker {
+    fn make_code(&self) -> Code {
+        match &self.rng {
+            Some(rng) => self.draw(&mut *lock(rng)),
+            None => self.draw(&mut rand::thread_rng()),
+        }
+    }
+}
+
+/// The player who breaks the code. Not sealed, like `CodeMaker`.
 pub trait CodeBreaker {
     fn guess_code(&self) -> Code;
     fn set_score(&mut self, score: Score);
     fn loses(&mut self);
+
+    /// Called once the code is broken, in `rounds` rounds, after the score
+    /// of the last guess.
+    fn wins(&mut self, _rounds: usize) {}
+
+    /// Whether the code breaker resigned instead of making its last guess,
+    /// which is then ignored.
+    fn forfeited(&self) -> bool {
+        false
+    }
+}
+
+/// A code maker which waits, e.g. for the network or a user interface,
+/// without blocking a thread. Every `CodeMaker` which is `Sync` is one.
+///
+/// The futures are `Send`, for servers to spawn games on any thread.
+///
+/// Not sealed either: a method added to the async traits comes with a
+/// default, and with its blocking version in the trait it mirrors.
+pub trait AsyncCodeMaker {
+    fn make_code(&self) -> impl Future<Output = Code> + Send;
+}
+
+impl<T: CodeMaker + Sync> AsyncCodeMaker for T {
+    async fn make_code(&self) -> Code {
+        CodeMaker::make_code(self)
+    }
 }
 
-pub struct Game<'a, T: CodeMaker, U: CodeBreaker> {
-    max_round: usize,
+/// A code breaker which waits without blocking a thread, like
+/// `AsyncCodeMaker`. Every `CodeBreaker` which is `Send` and `Sync` is one.
+pub trait AsyncCodeBreaker {
+    fn guess_code(&self) -> impl Future<Output = Code> + Send;
+    fn set_score(&mut self, score: Score) -> impl Future<Output = ()> + Send;
+    fn loses(&mut self) -> impl Future<Output = ()> + Send;
+
+    /// Called once the code is broken, like `CodeBreaker::wins`.
+    fn wins(&mut self, _rounds: usize) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Whether the code breaker gave up with its last guess, which then
+    /// loses the game instead of being scored.
+    fn forfeited(&self) -> bool {
+        false
+    }
+}
+
+impl<T: CodeBreaker + Send + Sync> AsyncCodeBreaker for T {
+    async fn guess_code(&self) -> Code {
+        CodeBreaker::guess_code(self)
+    }
+
+    async fn set_score(&mut self, score: Score) {
+        CodeBreaker::set_score(self, score)
+    }
+
+    async fn loses(&mut self) {
+        CodeBreaker::loses(self)
+    }
+
+    async fn wins(&mut self, rounds: usize) {
+        CodeBreaker::wins(self, rounds)
+    }
+
+    fn forfeited(&self) -> bool {
+        CodeBreaker::forfeited(self)
+    }
+}
+
+pub struct Game<'a, T, U> {
+    /// `None` if the game goes on until the code is broken.
+    max_round: Option<NonZeroUsize>,
+    rules: Rules,
     code_maker: &'a T,
     code_breaker: &'a mut U,
 }
 
-impl<'a, T: CodeMaker, U: CodeBreaker> Game<'a, T, U> {
-    pub fn new(max_round: usize, code_maker: &'a T, code_breaker: &'a mut U) -> Self {
+impl<'a, T, U> Game<'a, T, U> {
+    /// A game of at most `max_round` rounds, under the classic rules.
+    pub fn new(max_round: NonZeroUsize, code_maker: &'a T, code_breaker: &'a mut U) -> Self {
+        Game {
+            max_round: Some(max_round),
+            rules: Rules::default(),
+            code_maker,
+            code_breaker,
+        }
+    }
+
+    /// A game which goes on until the code breaker breaks the code, or
+    /// forfeits. The solvers always do (see `solver::Strategy`).
+    pub fn unlimited(code_maker: &'a T, code_breaker: &'a mut U) -> Self {
         Game {
-            max_round,
+            max_round: None,
+            rules: Rules::default(),
             code_maker,
             code_breaker,
         }
     }
 
-    pub fn play(self) {
-        let scorer = Scorer::new(self.code_maker.make_code());
-        for _round in 0..self.max_round {
-            let score = scorer.score(self.code_breaker.guess_code());
+    /// Plays under `rules`: a guess with a peg outside of the palette, a
+    /// repeated peg when duplicates are not allowed, or a guess played again
+    /// under strict rules, forfeits the game.
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
+    }
+}
+
+impl<T: CodeMaker, U: CodeBreaker> Game<'_, T, U> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "game", skip_all, fields(max_round = self.max_round))
+    )]
+    /// Plays the game to its end, asking the code maker for its code once.
+    pub fn play(self) -> GameOutcome {
+        let code = self.code_maker.make_code();
+        let scorer = Scorer::new(code);
+        let mut played = Vec::new();
+        #[cfg(feature = "metrics")]
+        telemetry::game_started();
+        for _round in 0..self.max_round.map_or(usize::MAX, NonZeroUsize::get) {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("round", round = _round + 1).entered();
+            #[cfg(feature = "metrics")]
+            let start = std::time::Instant::now();
+            let guess = self.code_breaker.guess_code();
+            #[cfg(feature = "metrics")]
+            telemetry::moved(start.elapsed());
+            if self.code_breaker.forfeited() {
+                #[cfg(feature = "tracing")]
+                tracing::info!("the code breaker forfeited");
+                #[cfg(feature = "metrics")]
+                telemetry::game_finished("forfeited", Some(_round));
+                self.code_breaker.loses();
+                return GameOutcome::Lost(code);
+            }
+            if let Err(err) = self.rules.check_guess(guess, played.iter().copied()) {
+                #[cfg(feature = "tracing")]
+                tracing::info!(%guess, "the guess breaks the rules: {}", err);
+                #[cfg(feature = "metrics")]
+                telemetry::game_finished("rejected", Some(_round));
+                self.code_breaker.loses();
+                return GameOutcome::Rejected(guess, err);
+            }
+            played.push(guess);
+            let score = scorer.score(guess);
+            #[cfg(feature = "tracing")]
+            tracing::debug!(%guess, matched = score.matches(), present = score.presents());
             self.code_breaker.set_score(score);
             if score == Score::new([Some(ScorePeg::Match); SIZE]) {
-                return;
+                #[cfg(feature = "tracing")]
+                tracing::info!(rounds = _round + 1, "won");
+                #[cfg(feature = "metrics")]
+                telemetry::game_finished("won", Some(_round + 1));
+                self.code_breaker.wins(_round + 1);
+                return GameOutcome::Won(_round + 1);
             }
         }
+        #[cfg(feature = "tracing")]
+        tracing::info!("lost");
+        #[cfg(feature = "metrics")]
+        telemetry::game_finished("lost", self.max_round.map(NonZeroUsize::get));
         self.code_breaker.loses();
+        GameOutcome::Lost(code)
     }
 }
 
+/// How a game ended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum GameOutcome {
+    /// The code breaker won in this number of rounds.
+    Won(usize),
+    /// The code breaker lost, or forfeited; this was the code.
+    Lost(Code),
+    /// The code breaker lost with a guess the rules do not allow.
+    Rejected(Code, RulesError),
+    /// The game was cancelled before its end.
+    Aborted,
+}
+
+impl<T: AsyncCodeMaker, U: AsyncCodeBreaker> Game<'_, T, U> {
+    /// Plays like `play`, awaiting the players instead of blocking.
+    pub async fn play_async(self) -> GameOutcome {
+        self.play_cancellable(&CancelToken::new()).await
+    }
+
+    /// Plays like `play_async` until `token` is cancelled, which drops the
+    /// move in progress and aborts the game, without telling the players.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "game", skip_all, fields(max_round = self.max_round))
+    )]
+    pub async fn play_cancellable(self, token: &CancelToken) -> GameOutcome {
+        #[cfg(feature = "metrics")]
+        telemetry::game_started();
+        let outcome = self.play_rounds(token).await;
+        #[cfg(feature = "metrics")]
+        if outcome == GameOutcome::Aborted {
+            telemetry::game_finished("aborted", None);
+        }
+        outcome
+    }
+
+    async fn play_rounds(mut self, token: &CancelToken) -> GameOutcome {
+        let Some(code) = token.run(self.code_maker.make_code()).await else {
+            return GameOutcome::Aborted;
+        };
+        let mut played = Vec::new();
+        for round in 1..=self.max_round.map_or(usize::MAX, NonZeroUsize::get) {
+            let round_played = self.play_round(token, code, &mut played, round);
+            #[cfg(feature = "tracing")]
+            let round_played =
+                tracing::Instrument::instrument(round_played, tracing::debug_span!("round", round));
+            if let Some(outcome) = round_played.await {
+                return outcome;
+            }
+        }
+        #[cfg(feature = "tracing")]
+        tracing::info!("lost");
+        let told = token.run(self.code_breaker.loses()).await;
+        finished(told, GameOutcome::Lost(code), "lost", played.len())
+    }
+
+    /// Plays one round, and returns the outcome of the game if it ended.
+    async fn play_round(
+        &mut self,
+        token: &CancelToken,
+        code: Code,
+        played: &mut Vec<Code>,
+        round: usize,
+    ) -> Option<GameOutcome> {
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let Some(guess) = token.run(self.code_breaker.guess_code()).await else {
+            return Some(GameOutcome::Aborted);
+        };
+        #[cfg(feature = "metrics")]
+        telemetry::moved(start.elapsed());
+        if self.code_breaker.forfeited() {
+            #[cfg(feature = "tracing")]
+            tracing::info!("the code breaker forfeited");
+            let told = token.run(self.code_breaker.loses()).await;
+            let outcome = GameOutcome::Lost(code);
+            return Some(finished(told, outcome, "forfeited", played.len()));
+        }
+        if let Err(err) = self.rules.check_guess(guess, played.iter().copied()) {
+            #[cfg(feature = "tracing")]
+            tracing::info!(%guess, "the guess breaks the rules: {}", err);
+            let told = token.run(self.code_breaker.loses()).await;
+            let outcome = GameOutcome::Rejected(guess, err);
+            return Some(finished(told, outcome, "rejected", played.len()));
+        }
+        played.push(guess);
+        let score = Scorer::new(code).score(guess);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%guess, matched = score.matches(), present = score.presents());
+        token.run(self.code_breaker.set_score(score)).await?;
+        if score == Score::new([Some(ScorePeg::Match); SIZE]) {
+            #[cfg(feature = "tracing")]
+            tracing::info!(rounds = round, "won");
+            let told = token.run(self.code_breaker.wins(round)).await;
+            return Some(finished(told, GameOutcome::Won(round), "won", round));
+        }
+        None
+    }
+}
+
+/// `outcome` if the code breaker was `told` about it, recorded as `_label`
+/// after `_rounds`, or `Aborted` if the game was cancelled meanwhile.
+fn finished(
+    told: Option<()>,
+    outcome: GameOutcome,
+    _label: &'static str,
+    _rounds: usize,
+) -> GameOutcome {
+    if told.is_none() {
+        return GameOutcome::Aborted;
+    }
+    #[cfg(feature = "metrics")]
+    telemetry::game_finished(_label, Some(_rounds));
+    outcome
+}
+
 #[cfg(test)]
 mod test_scorer {
     use super::*;
@@ -176,6 +841,17 @@ mod test_scorer {
                 guess: Code::new([CodePeg::A, CodePeg::A, CodePeg::D, CodePeg::D]),
                 score: Score::new([Some(ScorePeg::Match), None, None, None]),
             },
+            TestCase {
+                name: "count_present_only_once",
+                code: Code::new([CodePeg::A, CodePeg::A, CodePeg::B, CodePeg::C]),
+                guess: Code::new([CodePeg::C, CodePeg::A, CodePeg::A, CodePeg::A]),
+                score: Score::new([
+                    Some(ScorePeg::Match),
+                    Some(ScorePeg::Present),
+                    Some(ScorePeg::Present),
+                    None,
+                ]),
+            },
         ];
 
         for test_case in test_cases {
@@ -184,91 +860,629 @@ mod test_scorer {
             assert_eq!(score, test_case.score, "test case{}", test_case.name,);
         }
     }
+
+    #[test]
+    fn scores_when_compiling() {
+        const CODE: Code = Code::new([CodePeg::A, CodePeg::B, CodePeg::C, CodePeg::D]);
+        const SCORE: Score =
+            Scorer::new(CODE).score(Code::new([CodePeg::D, CodePeg::B, CodePeg::A, CodePeg::A]));
+        const COUNTS: (usize, usize) = (SCORE.matches(), SCORE.presents());
+        assert_eq!(COUNTS, (1, 2));
+    }
+
+    #[test]
+    fn rescore_with_change() {
+        let codes = Code::all();
+        for &code in codes.iter().step_by(11) {
+            let scorer = Scorer::new(code);
+            for &guess in codes.iter().step_by(5) {
+                let score = scorer.score(guess);
+                for position in 0..SIZE {
+                    for &peg in CodePeg::ALL {
+                        let mut changed = guess;
+                        changed.pegs[position] = peg;
+                        assert_eq!(
+                            scorer.rescore_with_change(guess, score, position, peg),
+                            Some(scorer.score(changed)),
+                            "code {}, guess {}, {} at {}",
+                            code,
+                            guess,
+                            peg,
+                            position
+                        );
+                    }
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
-mod test_game {
+mod test_score {
     use super::*;
 
-    struct DeterministicCodeMaker {
-        code: Code,
+    #[test]
+    fn from_counts() {
+        assert_eq!(Score::from_counts(0, 0), Some(Score::new([None; SIZE])));
+        assert_eq!(
+            Score::from_counts(1, 2),
+            Some(Score::new([
+                Some(ScorePeg::Match),
+                Some(ScorePeg::Present),
+                Some(ScorePeg::Present),
+                None
+            ]))
+        );
+        assert_eq!(Score::from_counts(3, 1), None);
+        assert_eq!(Score::from_counts(2, 3), None);
+        assert_eq!(Score::from_counts(6, usize::MAX), None);
+        assert_eq!(Score::from_counts(usize::MAX, 1), None);
     }
 
-    impl DeterministicCodeMaker {
-        fn new(code: Code) -> Self {
-            DeterministicCodeMaker { code }
-        }
+    #[test]
+    fn try_from_counts() {
+        assert_eq!(
+            Score::try_from((1, 2)),
+            Ok(Score::from_counts(1, 2).unwrap())
+        );
+        assert_eq!(
+            Score::try_from((4, 0)),
+            Ok(Score::new([Some(ScorePeg::Match); SIZE]))
+        );
+        assert_eq!(Score::try_from((3, 1)), Err(ScoreError::Impossible(3, 1)));
+        assert_eq!(Score::try_from((2, 3)), Err(ScoreError::TooManyPegs(5)));
+        assert_eq!(
+            Score::try_from((u8::MAX, u8::MAX)),
+            Err(ScoreError::TooManyPegs(510))
+        );
     }
 
-    impl CodeMaker for DeterministicCodeMaker {
-        fn make_code(&self) -> Code {
-            self.code
-        }
+    #[test]
+    fn ignores_the_order_of_pegs() {
+        use std::collections::HashSet;
+
+        let present_first =
+            Score::new([Some(ScorePeg::Present), None, Some(ScorePeg::Match), None]);
+        let scored = Score::from_counts(1, 1).unwrap();
+        assert_eq!(present_first, scored);
+        assert_eq!(present_first.pegs(), scored.pegs());
+        assert_eq!(HashSet::from([present_first, scored]).len(), 1);
     }
 
-    struct DummyCodeBreaker {
-        code: Code,
-        has_won: bool,
-        has_lost: bool,
-        num_rounds: usize,
-    }
-
-    impl DummyCodeBreaker {
-        fn new(code: Code) -> Self {
-            DummyCodeBreaker {
-                code,
-                has_won: false,
-                has_lost: false,
-                num_rounds: 0,
-            }
-        }
+    #[test]
+    fn takes_one_byte() {
+        assert_eq!(std::mem::size_of::<Score>(), 1);
+        let score = Score::from_counts(1, 2).unwrap();
+        assert_eq!((score.matches(), score.presents()), (1, 2));
+        assert_eq!(
+            score.pegs(),
+            [
+                Some(ScorePeg::Match),
+                Some(ScorePeg::Present),
+                Some(ScorePeg::Present),
+                None
+            ]
+        );
+        assert_eq!(format!("{:?}", score), "Score { matches: 1, presents: 2 }");
     }
+}
 
-    impl CodeBreaker for DummyCodeBreaker {
-        fn guess_code(&self) -> Code {
-            self.code
-        }
+#[cfg(test)]
+mod test_code {
+    use super::*;
 
-        fn set_score(&mut self, score: Score) {
-            self.num_rounds += 1;
-            if score != Score::new([Some(ScorePeg::Match); SIZE]) {
-                return;
-            }
-            self.has_won = true;
-        }
+    #[test]
+    fn parse() {
+        let code: Code = "abFE".parse().unwrap();
+        assert_eq!(
+            code,
+            Code::new([CodePeg::A, CodePeg::B, CodePeg::F, CodePeg::E])
+        );
+        assert_eq!(code.to_string(), "ABFE");
+    }
 
-        fn loses(&mut self) {
-            self.has_lost = true;
+    #[test]
+    fn all() {
+        let codes = Code::all();
+        assert_eq!(codes.len(), 1296);
+        assert_eq!(codes[0], Code::new([CodePeg::A; SIZE]));
+        assert_eq!(
+            codes[1],
+            Code::new([CodePeg::A, CodePeg::A, CodePeg::A, CodePeg::B])
+        );
+        assert_eq!(codes[1295], Code::new([CodePeg::F; SIZE]));
+        assert_eq!(codes.len(), Code::COUNT);
+        for (i, &code) in codes.iter().enumerate() {
+            assert_eq!(code.index(), i);
+            assert_eq!(Code::from_index(i), Some(code));
         }
+        assert_eq!(Code::from_index(Code::COUNT), None);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("ABC".parse::<Code>(), Err(ParseCodeError::WrongLength(3)));
+        assert_eq!("ABCDE".parse::<Code>(), Err(ParseCodeError::WrongLength(5)));
+        assert_eq!("ABGD".parse::<Code>(), Err(ParseCodeError::InvalidPeg('G')));
     }
+}
+
+#[cfg(test)]
+mod test_game {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::{Context, Poll, Waker};
+
+    use super::testutil::{DeterministicCodeMaker, ScriptedCodeBreaker};
+    use super::*;
 
     #[test]
     fn wins_at_first_guess() {
         let code = Code::new([CodePeg::B, CodePeg::B, CodePeg::A, CodePeg::E]);
         let code_maker = DeterministicCodeMaker::new(code);
-        let mut code_breaker = DummyCodeBreaker::new(code);
-        let game = Game::new(3, &code_maker, &mut code_breaker);
+        let mut code_breaker = ScriptedCodeBreaker::new(vec![code]);
+        let game = Game::new(
+            NonZeroUsize::new(3).unwrap(),
+            &code_maker,
+            &mut code_breaker,
+        );
+        game.play();
+        assert!(code_breaker.has_won);
+        assert!(!code_breaker.has_lost);
+        assert_eq!(code_breaker.num_rounds(), 1);
+    }
+
+    #[test]
+    fn empty_scripts_forfeit() {
+        let code = Code::new([CodePeg::B, CodePeg::B, CodePeg::A, CodePeg::E]);
+        let mut code_breaker = ScriptedCodeBreaker::new(Vec::new());
+        let outcome = Game::new(
+            NonZeroUsize::new(3).unwrap(),
+            &DeterministicCodeMaker::new(code),
+            &mut code_breaker,
+        )
+        .play();
+        assert_eq!(outcome, GameOutcome::Lost(code));
+        assert_eq!(code_breaker.num_rounds(), 0);
+    }
+
+    #[test]
+    fn wins_after_scripted_guesses() {
+        let code = Code::new([CodePeg::B, CodePeg::B, CodePeg::A, CodePeg::E]);
+        let code_maker = DeterministicCodeMaker::new(code);
+        let mut code_breaker = ScriptedCodeBreaker::new(vec![
+            Code::new([CodePeg::A, CodePeg::A, CodePeg::A, CodePeg::A]),
+            Code::new([CodePeg::B, CodePeg::B, CodePeg::C, CodePeg::C]),
+            code,
+        ]);
+        let game = Game::new(
+            NonZeroUsize::new(8).unwrap(),
+            &code_maker,
+            &mut code_breaker,
+        );
         game.play();
         assert!(code_breaker.has_won);
         assert!(!code_breaker.has_lost);
-        assert_eq!(code_breaker.num_rounds, 1);
+        assert_eq!(code_breaker.num_rounds(), 3);
     }
 
     #[test]
     fn loses() {
         let num_round = 8;
-        let code_maker = DeterministicCodeMaker::new(Code::new([
-            CodePeg::A,
-            CodePeg::E,
+        let code = Code::new([CodePeg::A, CodePeg::E, CodePeg::F, CodePeg::C]);
+        let code_maker = DeterministicCodeMaker::new(code);
+        let mut code_breaker = ScriptedCodeBreaker::new(vec![Code::new([
+            CodePeg::B,
+            CodePeg::B,
             CodePeg::F,
-            CodePeg::C,
-        ]));
-        let mut code_breaker =
-            DummyCodeBreaker::new(Code::new([CodePeg::B, CodePeg::B, CodePeg::F, CodePeg::D]));
-        let game = Game::new(num_round, &code_maker, &mut code_breaker);
-        game.play();
+            CodePeg::D,
+        ])]);
+        let game = Game::new(
+            NonZeroUsize::new(num_round).unwrap(),
+            &code_maker,
+            &mut code_breaker,
+        );
+        assert_eq!(game.play(), GameOutcome::Lost(code));
         assert!(code_breaker.has_lost);
         assert!(!code_breaker.has_won);
-        assert_eq!(code_breaker.num_rounds, num_round);
+        assert_eq!(code_breaker.num_rounds(), num_round);
+    }
+
+    /// Makes a new code each time it is asked for one.
+    struct FickleCodeMaker(AtomicUsize);
+
+    impl CodeMaker for FickleCodeMaker {
+        fn make_code(&self) -> Code {
+            Code::from_index(self.0.fetch_add(1, Ordering::Relaxed)).unwrap()
+        }
+    }
+
+    #[test]
+    fn asks_for_the_code_once() {
+        let code_maker = FickleCodeMaker(AtomicUsize::new(0));
+        let guesses = vec![Code::new([CodePeg::F; SIZE])];
+        let mut code_breaker = ScriptedCodeBreaker::new(guesses.clone());
+        let outcome = Game::new(
+            NonZeroUsize::new(5).unwrap(),
+            &code_maker,
+            &mut code_breaker,
+        )
+        .play();
+        assert_eq!(outcome, GameOutcome::Lost(Code::new([CodePeg::A; SIZE])));
+        assert_eq!(code_maker.0.load(Ordering::Relaxed), 1);
+
+        let mut code_breaker = ScriptedCodeBreaker::new(guesses);
+        let outcome = block_on(
+            Game::new(
+                NonZeroUsize::new(5).unwrap(),
+                &code_maker,
+                &mut code_breaker,
+            )
+            .play_async(),
+        );
+        assert_eq!(outcome, GameOutcome::Lost(Code::from_index(1).unwrap()));
+        assert_eq!(code_maker.0.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn seeded_code_makers_repeat_their_codes() {
+        let rules = Rules::new(5, false).unwrap();
+        let codes = |seed| {
+            let code_maker = RandomCodeMaker::seeded(rules, seed);
+            (0..20)
+                .map(|_| CodeMaker::make_code(&code_maker))
+                .collect::<Vec<_>>()
+        };
+        let drawn = codes(7);
+        assert_eq!(codes(7), drawn);
+        assert_ne!(codes(8), drawn);
+        assert!(drawn.iter().all(|&code| rules.check(code).is_ok()));
+    }
+
+    #[test]
+    fn plays_until_broken() {
+        let code = Code::new([CodePeg::B, CodePeg::B, CodePeg::A, CodePeg::E]);
+        let code_maker = DeterministicCodeMaker::new(code);
+        let mut guesses = vec![Code::new([CodePeg::F; SIZE]); 40];
+        guesses.push(code);
+        let mut code_breaker = ScriptedCodeBreaker::new(guesses);
+        Game::unlimited(&code_maker, &mut code_breaker).play();
+        assert!(code_breaker.has_won);
+        assert_eq!(code_breaker.num_rounds(), 41);
+    }
+
+    #[test]
+    fn rejects_guesses_the_rules_do_not_allow() {
+        let rules = Rules::new(5, false).unwrap();
+        let code = Code::new([CodePeg::A, CodePeg::B, CodePeg::C, CodePeg::D]);
+        let code_maker = DeterministicCodeMaker::new(code);
+        for guess in [
+            Code::new([CodePeg::A, CodePeg::B, CodePeg::C, CodePeg::F]),
+            Code::new([CodePeg::A, CodePeg::A, CodePeg::C, CodePeg::D]),
+        ] {
+            let mut code_breaker = ScriptedCodeBreaker::new(vec![guess, code]);
+            Game::new(
+                NonZeroUsize::new(8).unwrap(),
+                &code_maker,
+                &mut code_breaker,
+            )
+            .with_rules(rules)
+            .play();
+            assert!(code_breaker.has_lost);
+            assert_eq!(code_breaker.num_rounds(), 0);
+        }
+
+        // The same guesses are fine under the classic rules.
+        let guess = Code::new([CodePeg::A, CodePeg::A, CodePeg::C, CodePeg::F]);
+        let mut code_breaker = ScriptedCodeBreaker::new(vec![guess, code]);
+        Game::new(
+            NonZeroUsize::new(8).unwrap(),
+            &code_maker,
+            &mut code_breaker,
+        )
+        .play();
+        assert!(code_breaker.has_won);
+    }
+
+    #[test]
+    fn rejects_repeated_guesses_under_strict_rules() {
+        let code = Code::new([CodePeg::A, CodePeg::B, CodePeg::C, CodePeg::D]);
+        let code_maker = DeterministicCodeMaker::new(code);
+        let guess = Code::new([CodePeg::A; SIZE]);
+        let mut code_breaker = ScriptedCodeBreaker::new(vec![guess, guess, code]);
+        let outcome = Game::new(
+            NonZeroUsize::new(8).unwrap(),
+            &code_maker,
+            &mut code_breaker,
+        )
+        .with_rules(Rules::default().with_strict(true))
+        .play();
+        assert_eq!(
+            outcome,
+            GameOutcome::Rejected(guess, RulesError::Repeated(guess))
+        );
+        assert!(code_breaker.has_lost);
+        assert_eq!(code_breaker.num_rounds(), 1);
+
+        let mut code_breaker = ScriptedCodeBreaker::new(vec![guess, guess, code]);
+        let outcome = Game::new(
+            NonZeroUsize::new(8).unwrap(),
+            &code_maker,
+            &mut code_breaker,
+        )
+        .play();
+        assert_eq!(outcome, GameOutcome::Won(3));
+    }
+
+    /// Polls `future` until it completes, for futures which wake themselves.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    /// A future which is pending once, as if it waited for the network.
+    struct Yield(bool);
+
+    impl Future for Yield {
+        type Output = ();
+
+        fn poll(mut self: std::pin::Pin<&mut Self>, _: &mut Context) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Plays a scripted code breaker, waiting before each guess.
+    struct WaitingCodeBreaker(ScriptedCodeBreaker);
+
+    impl AsyncCodeBreaker for WaitingCodeBreaker {
+        async fn guess_code(&self) -> Code {
+            Yield(false).await;
+            CodeBreaker::guess_code(&self.0)
+        }
+
+        async fn set_score(&mut self, score: Score) {
+            CodeBreaker::set_score(&mut self.0, score)
+        }
+
+        async fn loses(&mut self) {
+            CodeBreaker::loses(&mut self.0)
+        }
+
+        async fn wins(&mut self, rounds: usize) {
+            CodeBreaker::wins(&mut self.0, rounds)
+        }
+    }
+
+    #[test]
+    fn plays_async() {
+        let code = Code::new([CodePeg::B, CodePeg::B, CodePeg::A, CodePeg::E]);
+        let code_maker = DeterministicCodeMaker::new(code);
+        let guesses = vec![
+            Code::new([CodePeg::A, CodePeg::A, CodePeg::A, CodePeg::A]),
+            code,
+        ];
+        let mut code_breaker = WaitingCodeBreaker(ScriptedCodeBreaker::new(guesses.clone()));
+        let outcome = block_on(
+            Game::new(
+                NonZeroUsize::new(8).unwrap(),
+                &code_maker,
+                &mut code_breaker,
+            )
+            .play_async(),
+        );
+        assert_eq!(outcome, GameOutcome::Won(2));
+        assert!(code_breaker.0.has_won);
+
+        // Players which do not wait play as well.
+        let mut code_breaker = ScriptedCodeBreaker::new(guesses[..1].to_vec());
+        let outcome = block_on(
+            Game::new(
+                NonZeroUsize::new(3).unwrap(),
+                &code_maker,
+                &mut code_breaker,
+            )
+            .play_async(),
+        );
+        assert_eq!(outcome, GameOutcome::Lost(code));
+        assert!(code_breaker.has_lost);
+
+        // Guesses are checked against the rules.
+        let rules = Rules::new(6, false).unwrap();
+        let mut code_breaker = WaitingCodeBreaker(ScriptedCodeBreaker::new(guesses.clone()));
+        let outcome = block_on(
+            Game::new(
+                NonZeroUsize::new(8).unwrap(),
+                &code_maker,
+                &mut code_breaker,
+            )
+            .with_rules(rules)
+            .play_async(),
+        );
+        assert_eq!(
+            outcome,
+            GameOutcome::Rejected(guesses[0], RulesError::Duplicate(CodePeg::A))
+        );
+        assert!(code_breaker.0.has_lost);
+    }
+
+    #[test]
+    fn aborts() {
+        let code_maker = DeterministicCodeMaker::new(Code::new([CodePeg::A; SIZE]));
+        let guesses = vec![Code::new([CodePeg::B; SIZE])];
+        let mut code_breaker = WaitingCodeBreaker(ScriptedCodeBreaker::new(guesses));
+        let token = CancelToken::new();
+        let outcome = {
+            let game = Game::new(
+                NonZeroUsize::new(8).unwrap(),
+                &code_maker,
+                &mut code_breaker,
+            );
+            let mut game = std::pin::pin!(game.play_cancellable(&token));
+            let mut context = Context::from_waker(Waker::noop());
+            assert!(game.as_mut().poll(&mut context).is_pending());
+            token.cancel();
+            game.as_mut().poll(&mut context)
+        };
+        assert_eq!(outcome, Poll::Ready(GameOutcome::Aborted));
+        assert!(!code_breaker.0.has_lost);
+    }
+}
+
+/// The public API does not panic on any valid input.
+#[cfg(test)]
+mod test_api {
+    use super::*;
+    use crate::session::{GameSession, State};
+    use crate::solver::{Solver, Strategy};
+    use crate::testutil::DeterministicCodeMaker;
+
+    fn all_rules() -> Vec<Rules> {
+        (1..=CodePeg::ALL.len())
+            .flat_map(|colors| [true, false].map(|duplicates| Rules::new(colors, duplicates)))
+            .filter_map(Result::ok)
+            .collect()
+    }
+
+    #[test]
+    fn scores_every_pair() {
+        for index in 0..=Code::COUNT + 1 {
+            let code = Code::from_index(index);
+            assert_eq!(
+                code.map(|code| code.index()),
+                (index < Code::COUNT).then_some(index)
+            );
+        }
+        for matches in 0..=SIZE + 1 {
+            for presents in 0..=SIZE + 1 {
+                let valid = matches + presents <= SIZE && (matches, presents) != (SIZE - 1, 1);
+                let score = Score::from_counts(matches, presents);
+                assert_eq!(score.is_some(), valid, "{} {}", matches, presents);
+                if let Some(score) = score {
+                    let pegs = score.pegs();
+                    let count = |peg| pegs.iter().filter(|&&p| p == Some(peg)).count();
+                    assert_eq!(count(ScorePeg::Match), matches);
+                    assert_eq!(count(ScorePeg::Present), presents);
+                }
+            }
+        }
+        let codes = Code::all();
+        for &secret in &codes {
+            let scorer = Scorer::new(secret);
+            for &guess in &codes {
+                let score = scorer.score(guess);
+                assert!(score.matches() + score.presents() <= SIZE);
+                assert_eq!(score.matches() == SIZE, guess == secret);
+                if guess.index() % 17 == 0 {
+                    for position in 0..SIZE {
+                        for &peg in CodePeg::ALL {
+                            let mut pegs = guess.pegs();
+                            pegs[position] = peg;
+                            assert_eq!(
+                                scorer.rescore_with_change(guess, score, position, peg),
+                                Some(scorer.score(Code::new(pegs)))
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rescores_any_change() {
+        let codes = Code::all();
+        let scores: Vec<Score> = (0..=SIZE)
+            .flat_map(|matches| (0..=SIZE).filter_map(move |p| Score::from_counts(matches, p)))
+            .collect();
+        for &secret in codes.iter().step_by(7) {
+            let scorer = Scorer::new(secret);
+            for &guess in codes.iter().step_by(13) {
+                // Scores which are not the score of the guess as well.
+                for &score in &scores {
+                    for position in 0..=SIZE {
+                        for &peg in CodePeg::ALL {
+                            let rescored = scorer.rescore_with_change(guess, score, position, peg);
+                            if position == SIZE {
+                                assert_eq!(rescored, None);
+                            } else if score == scorer.score(guess) {
+                                let mut pegs = guess.pegs();
+                                pegs[position] = peg;
+                                assert_eq!(rescored, Some(scorer.score(Code::new(pegs))));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn plays_after_contradictions() {
+        for rules in all_rules() {
+            let codes = rules.codes();
+            let (first, last) = (codes[0], codes[codes.len() - 1]);
+            if first == last {
+                continue;
+            }
+            let code_maker = DeterministicCodeMaker::new(first);
+            let mut solver = Solver::with_rules(rules, Strategy::Minimax);
+            // Broken, then not broken.
+            solver.record(first, Scorer::new(first).score(first));
+            solver.record(first, Scorer::new(last).score(first));
+            assert_eq!(solver.guess(), None);
+            let outcome = Game::new(NonZeroUsize::new(3).unwrap(), &code_maker, &mut solver)
+                .with_rules(rules)
+                .play();
+            assert_eq!(outcome, GameOutcome::Lost(first));
+        }
+    }
+
+    #[test]
+    fn plays_every_game() {
+        for rules in all_rules() {
+            let codes = rules.codes();
+            for (i, &secret) in codes.iter().enumerate() {
+                let code_maker = DeterministicCodeMaker::new(secret);
+                let mut solver = Solver::with_rules(rules, Strategy::FirstCandidate);
+                let outcome = Game::unlimited(&code_maker, &mut solver)
+                    .with_rules(rules)
+                    .play();
+                assert!(
+                    matches!(outcome, GameOutcome::Won(rounds) if rounds <= codes.len()),
+                    "{:?} against {}: {:?}",
+                    rules,
+                    secret,
+                    outcome
+                );
+                if i % 97 == 0 {
+                    let mut solver = Solver::with_rules(rules, Strategy::Minimax);
+                    let opening = solver.guess();
+                    let outcome =
+                        Game::new(NonZeroUsize::new(1).unwrap(), &code_maker, &mut solver).play();
+                    if opening == Some(secret) {
+                        assert_eq!(outcome, GameOutcome::Won(1));
+                    } else {
+                        assert_eq!(outcome, GameOutcome::Lost(secret));
+                    }
+                }
+            }
+
+            // Every guess, allowed or not, until the code is broken.
+            let secret = codes[codes.len() - 1];
+            let mut session = GameSession::unlimited(rules, secret).unwrap();
+            for guess in Code::all() {
+                let _ = session.guess(guess);
+            }
+            assert_eq!(session.state(), State::Won);
+        }
     }
 }
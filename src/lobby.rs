@@ -0,0 +1,256 @@
+//! Pairs the code makers and code breakers asking for the same game.
+//!
+//! The lobby does not know how players are reached: each request comes with
+//! a handle, such as the player's connection, which is handed back with the
+//! pairing.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::num::NonZeroUsize;
+
+use crate::net::Role;
+use crate::rules::Rules;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PlayerId(u64);
+
+impl fmt::Display for PlayerId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Identifies a game started by the lobby.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SessionId(pub u64);
+
+impl fmt::Display for SessionId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The game a player asks for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Setup {
+    pub max_round: NonZeroUsize,
+    pub rules: Rules,
+}
+
+/// Two players to start a game with.
+#[derive(Debug)]
+pub struct Pairing<T> {
+    pub session: SessionId,
+    pub setup: Setup,
+    pub maker: T,
+    pub breaker: T,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum LobbyError {
+    UnknownPlayer(PlayerId),
+    /// The player is already waiting for a game.
+    AlreadyWaiting(PlayerId),
+}
+
+impl fmt::Display for LobbyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LobbyError::UnknownPlayer(id) => write!(f, "unknown player {}", id),
+            LobbyError::AlreadyWaiting(id) => write!(f, "player {} is already waiting", id),
+        }
+    }
+}
+
+impl std::error::Error for LobbyError {}
+
+struct Player {
+    name: String,
+    rating: Option<u32>,
+}
+
+struct Request<T> {
+    player: PlayerId,
+    role: Role,
+    setup: Setup,
+    handle: T,
+}
+
+pub struct Lobby<T> {
+    players: HashMap<PlayerId, Player>,
+    // In the order the requests were made.
+    waiting: Vec<Request<T>>,
+    next_player: u64,
+    next_session: u64,
+}
+
+impl<T> Default for Lobby<T> {
+    fn default() -> Self {
+        Lobby {
+            players: HashMap::new(),
+            waiting: Vec::new(),
+            next_player: 0,
+            next_session: 0,
+        }
+    }
+}
+
+impl<T> Lobby<T> {
+    pub fn new() -> Self {
+        Lobby::default()
+    }
+
+    /// Registers a player, with their rating if they have one.
+    pub fn register(&mut self, name: &str, rating: Option<u32>) -> PlayerId {
+        let id = PlayerId(self.next_player);
+        self.next_player += 1;
+        let name = name.to_string();
+        self.players.insert(id, Player { name, rating });
+        id
+    }
+
+    pub fn name(&self, player: PlayerId) -> Option<&str> {
+        self.players.get(&player).map(|player| player.name.as_str())
+    }
+
+    /// Forgets a player, and their request if they were waiting.
+    pub fn unregister(&mut self, player: PlayerId) -> Option<T> {
+        self.players.remove(&player);
+        let index = self.waiting.iter().position(|r| r.player == player)?;
+        Some(self.waiting.remove(index).handle)
+    }
+
+    /// The number of players waiting for a game.
+    pub fn waiting(&self) -> usize {
+        self.waiting.len()
+    }
+
+    /// Asks for a game as `role`, reaching `player` with `handle`.
+    ///
+    /// If another player waits for the same setup in the other role, they
+    /// are paired: the closest in rating when both players are rated, or
+    /// else the first one to have asked. Otherwise the request waits.
+    pub fn request(
+        &mut self,
+        player: PlayerId,
+        role: Role,
+        setup: Setup,
+        handle: T,
+    ) -> Result<Option<Pairing<T>>, LobbyError> {
+        let rating = self
+            .players
+            .get(&player)
+            .ok_or(LobbyError::UnknownPlayer(player))?
+            .rating;
+        if self.waiting.iter().any(|r| r.player == player) {
+            return Err(LobbyError::AlreadyWaiting(player));
+        }
+
+        let opponent = self
+            .waiting
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.role != role && r.setup == setup)
+            .min_by_key(|(i, r)| {
+                let other = self.players[&r.player].rating;
+                let distance = match (rating, other) {
+                    (Some(a), Some(b)) => a.abs_diff(b),
+                    _ => u32::MAX,
+                };
+                (distance, *i)
+            })
+            .map(|(i, _)| i);
+        let Some(index) = opponent else {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(%player, ?role, "waiting for an opponent");
+            self.waiting.push(Request {
+                player,
+                role,
+                setup,
+                handle,
+            });
+            return Ok(None);
+        };
+
+        let opponent = self.waiting.remove(index).handle;
+        let (maker, breaker) = match role {
+            Role::Maker => (handle, opponent),
+            Role::Breaker => (opponent, handle),
+        };
+        let session = SessionId(self.next_session);
+        self.next_session += 1;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%player, ?role, %session, "paired");
+        Ok(Some(Pairing {
+            session,
+            setup,
+            maker,
+            breaker,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test_lobby {
+    use super::*;
+
+    fn setup(colors: usize) -> Setup {
+        Setup {
+            max_round: NonZeroUsize::new(10).unwrap(),
+            rules: Rules::new(colors, true).unwrap(),
+        }
+    }
+
+    #[test]
+    fn pairs_same_setup() {
+        let mut lobby = Lobby::new();
+        let alice = lobby.register("alice", None);
+        let bob = lobby.register("bob", None);
+        let carol = lobby.register("carol", None);
+        assert_eq!(lobby.name(bob), Some("bob"));
+
+        assert!(lobby
+            .request(alice, Role::Maker, setup(6), "alice")
+            .unwrap()
+            .is_none());
+        assert!(lobby
+            .request(bob, Role::Breaker, setup(4), "bob")
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            lobby
+                .request(alice, Role::Maker, setup(6), "alice")
+                .unwrap_err(),
+            LobbyError::AlreadyWaiting(alice)
+        );
+
+        let pairing = lobby
+            .request(carol, Role::Breaker, setup(6), "carol")
+            .unwrap()
+            .unwrap();
+        assert_eq!((pairing.maker, pairing.breaker), ("alice", "carol"));
+        assert_eq!(pairing.session, SessionId(0));
+        assert_eq!(lobby.waiting(), 1);
+        assert_eq!(lobby.unregister(bob), Some("bob"));
+        assert_eq!(lobby.waiting(), 0);
+    }
+
+    #[test]
+    fn pairs_by_rating() {
+        let mut lobby = Lobby::new();
+        let weak = lobby.register("weak", Some(1000));
+        let strong = lobby.register("strong", Some(1800));
+        let player = lobby.register("player", Some(1700));
+        lobby.request(weak, Role::Maker, setup(6), "weak").unwrap();
+        lobby
+            .request(strong, Role::Maker, setup(6), "strong")
+            .unwrap();
+
+        let pairing = lobby
+            .request(player, Role::Breaker, setup(6), "player")
+            .unwrap()
+            .unwrap();
+        assert_eq!(pairing.maker, "strong");
+    }
+}
@@ -0,0 +1,247 @@
+//! Many games played at once, as by a server or a bot, each under the id
+//! it was given.
+//!
+//! The manager can be shared between threads. Like `Clock`, it is given the
+//! time rather than reading it, both when a game is used and when idle games
+//! are expired.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::session::GameSession;
+use crate::sync::{lock, Mutex};
+
+struct Entry<T> {
+    game: T,
+    last_used: Instant,
+}
+
+struct Games<T> {
+    next_id: u64,
+    entries: HashMap<u64, Entry<T>>,
+}
+
+/// Games of type `T`, usually a `GameSession` or a struct holding one,
+/// keyed by id.
+pub struct SessionManager<T = GameSession> {
+    idle_timeout: Option<Duration>,
+    games: Mutex<Games<T>>,
+}
+
+impl<T> Default for SessionManager<T> {
+    fn default() -> Self {
+        SessionManager::new()
+    }
+}
+
+impl<T> SessionManager<T> {
+    /// A manager which keeps its games until they are removed.
+    pub fn new() -> Self {
+        SessionManager {
+            idle_timeout: None,
+            games: Mutex::new(Games {
+                next_id: 0,
+                entries: HashMap::new(),
+            }),
+        }
+    }
+
+    /// A manager whose games expire once unused for `timeout`.
+    pub fn with_idle_timeout(timeout: Duration) -> Self {
+        SessionManager {
+            idle_timeout: Some(timeout),
+            ..SessionManager::new()
+        }
+    }
+
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+
+    /// Adds a game, and returns its id. Ids are never reused.
+    pub fn insert(&self, game: T, now: Instant) -> u64 {
+        self.insert_with(game, now, |_, _| ()).0
+    }
+
+    /// Adds a game like `insert`, and calls `f` with its id and the game
+    /// before another thread can use or remove it.
+    pub fn insert_with<R>(
+        &self,
+        game: T,
+        now: Instant,
+        f: impl FnOnce(u64, &mut T) -> R,
+    ) -> (u64, R) {
+        let mut games = lock(&self.games);
+        let id = games.next_id;
+        games.next_id += 1;
+        let entry = games.entries.entry(id).or_insert(Entry {
+            game,
+            last_used: now,
+        });
+        (id, f(id, &mut entry.game))
+    }
+
+    /// Calls `f` with the game `id`, if any, which counts as using it.
+    ///
+    /// Other games wait for `f` to return, so it should be quick.
+    pub fn with<R>(&self, id: u64, now: Instant, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        let mut games = lock(&self.games);
+        let entry = games.entries.get_mut(&id)?;
+        entry.last_used = now;
+        Some(f(&mut entry.game))
+    }
+
+    pub fn remove(&self, id: u64) -> Option<T> {
+        let mut games = lock(&self.games);
+        games.entries.remove(&id).map(|entry| entry.game)
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        lock(&self.games).entries.contains_key(&id)
+    }
+
+    /// The ids of the games, in the order they were added.
+    pub fn ids(&self) -> Vec<u64> {
+        let games = lock(&self.games);
+        let mut ids: Vec<u64> = games.entries.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    pub fn len(&self) -> usize {
+        lock(&self.games).entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes the games unused for longer than the idle timeout at `now`,
+    /// and returns them with their ids.
+    pub fn expire(&self, now: Instant) -> Vec<(u64, T)> {
+        let Some(timeout) = self.idle_timeout else {
+            return Vec::new();
+        };
+        let mut games = lock(&self.games);
+        let idle: Vec<u64> = games
+            .entries
+            .iter()
+            .filter(|(_, entry)| now.saturating_duration_since(entry.last_used) > timeout)
+            .map(|(&id, _)| id)
+            .collect();
+        let mut expired: Vec<(u64, T)> = idle
+            .into_iter()
+            .filter_map(|id| Some((id, games.entries.remove(&id)?.game)))
+            .collect();
+        expired.sort_unstable_by_key(|&(id, _)| id);
+        expired
+    }
+}
+
+#[cfg(test)]
+mod test_manager {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+    use crate::rules::Rules;
+
+    fn session() -> GameSession {
+        let max_round = NonZeroUsize::new(10).unwrap();
+        GameSession::new(Rules::default(), max_round, "ABCD".parse().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn is_shared_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SessionManager>();
+
+        let manager = SessionManager::new();
+        let start = Instant::now();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| manager.insert(session(), start));
+            }
+        });
+        assert_eq!(manager.ids(), [0, 1, 2, 3]);
+
+        let (id, rounds) = manager.insert_with(session(), start, |id, session| {
+            (
+                id,
+                session
+                    .guess("AABB".parse().unwrap())
+                    .map(|_| session.rounds().len()),
+            )
+        });
+        assert_eq!(id, 4);
+        assert_eq!(rounds, (4, Ok(1)));
+    }
+
+    #[test]
+    fn expires_idle_games() {
+        let start = Instant::now();
+        let minute = Duration::from_secs(60);
+        let manager = SessionManager::with_idle_timeout(2 * minute);
+        let first = manager.insert(session(), start);
+        let second = manager.insert(session(), start);
+
+        let guess = "ABCD".parse().unwrap();
+        let score = manager.with(second, start + minute, |session| session.guess(guess));
+        assert_eq!(score.unwrap().unwrap().matches(), 4);
+        assert_eq!(manager.with(7, start, |_| ()), None);
+
+        let expired = manager.expire(start + 2 * minute + Duration::from_secs(1));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].0, first);
+        assert!(!manager.contains(first));
+        assert_eq!(manager.ids(), [second]);
+
+        assert!(manager.expire(start + 4 * minute).len() == 1);
+        assert!(manager.is_empty());
+        assert!(SessionManager::<GameSession>::new()
+            .expire(start + 60 * minute)
+            .is_empty());
+    }
+}
+
+#[cfg(all(test, loom))]
+mod test_loom {
+    use loom::sync::Arc;
+    use loom::thread;
+
+    use super::*;
+
+    #[test]
+    fn gives_each_game_its_own_id() {
+        loom::model(|| {
+            let manager = Arc::new(SessionManager::new());
+            let now = Instant::now();
+            let other = {
+                let manager = Arc::clone(&manager);
+                thread::spawn(move || manager.insert("other", now))
+            };
+            let id = manager.insert("this", now);
+            let other = other.join().unwrap();
+            assert_ne!(id, other);
+            assert_eq!(manager.with(id, now, |game| *game), Some("this"));
+            assert_eq!(manager.with(other, now, |game| *game), Some("other"));
+        });
+    }
+
+    #[test]
+    fn gives_a_game_away_once() {
+        loom::model(|| {
+            let start = Instant::now();
+            let manager = Arc::new(SessionManager::with_idle_timeout(Duration::ZERO));
+            let id = manager.insert("game", start);
+            let later = start + Duration::from_secs(1);
+            let expired = {
+                let manager = Arc::clone(&manager);
+                thread::spawn(move || manager.expire(later))
+            };
+            let removed = manager.remove(id);
+            let expired = expired.join().unwrap();
+            assert_eq!(usize::from(removed.is_some()) + expired.len(), 1);
+            assert!(manager.is_empty());
+        });
+    }
+}
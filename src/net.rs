@@ -0,0 +1,1543 @@
+//! Playing over TCP, or over WebSocket with the `ws` feature.
+//!
+//! Messages are lines of text, or text frames over WebSocket. A client first
+//! says which version of the protocol it speaks and what it supports, and
+//! learns the same of the server:
+//!
+//! ```text
+//! client  HELLO <version> [<feature>...]
+//! server  HELLO <version> [<feature>...]   or ERROR if it cannot speak it
+//! ```
+//!
+//! The server answers with the version both speak, the lowest of theirs,
+//! and its features: the rules it hosts games with (`DUPLICATES`, `UNIQUE`
+//! and `COLORS=<n>`, the most colors allowed) and the requests it accepts
+//! (`WATCH` and `RESUME`). Features a peer does not know are ignored, so
+//! that new ones can be added without breaking older peers. Clients which
+//! do not say hello speak version 1, which is the same protocol without the
+//! handshake.
+//!
+//! The client then joins with its role; once a code maker and a code breaker
+//! have joined, the server starts the game:
+//!
+//! ```text
+//! client  JOIN <role> [<rounds> <colors> DUPLICATES|UNIQUE [STRICT]]
+//! server  START <rounds> <session> <token>   to each player
+//! maker   CODE <code>
+//! server  COMMIT <digest>             to the breaker
+//! breaker GUESS <code>                then, for each guess:
+//! server  SCORE <matched> <present>   to the breaker
+//! breaker RESIGN <token>              in place of a guess, to lose at once
+//! server  RESULT WON <rounds> | RESULT LOST <code>   to both players
+//! server  REVEAL <code> <salt>        to the breaker
+//! server  ERROR <message>             whenever a message is unexpected
+//! ```
+//!
+//! Spectators watch a game with its session instead of joining:
+//!
+//! ```text
+//! client  WATCH <session> [<token>]
+//! server  GUESS <code>, SCORE <matched> <present> and RESULT ...
+//!         as the game goes, from its start
+//! ```
+//!
+//! Each player gets their own token, which proves who sends the messages
+//! only some players may send (see `auth`): resigning, watching a private
+//! game, and resuming a game after losing the connection:
+//!
+//! ```text
+//! client  RESUME <session> <token>
+//! server  RESUMED <role> <rounds> <seconds>|- <digest>|- [<guess> <matched> <present>]...
+//! ```
+//!
+//! The player then carries on where they were, knowing the seconds they
+//! have for their next move if moves are limited, the commitment to the code
+//! once it is made, and the rounds played so far. A game can be resumed
+//! until it is over, within the grace period of the server.
+//!
+//! The role is `MAKER` or `BREAKER`. Players are paired with a player of the
+//! other role asking for the same rules, or the default rules of the server
+//! if they did not ask for any (see `lobby`). `STRICT` rules reject a guess
+//! played twice. Asking for rules the server does not host is an error.
+//!
+//! The digest is the SHA-256 of `<salt> <code>`, in hexadecimal, with a
+//! random 128-bit salt of 32 hexadecimal digits. Checking it against the
+//! revealed code proves that the code did not change during the game, and so
+//! that the scores were not made up to make the game harder.
+//!
+//! On the server, `RemoteCodeMaker` and `RemoteCodeBreaker` make the players
+//! look local to `Game`. On the clients, `join` then `play_maker` or
+//! `play_breaker` drive a local code maker or code breaker.
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+use crate::auth::{Capability, Token, Tokens};
+use crate::lobby::{SessionId, Setup};
+use crate::ratelimit::{Quota, RateLimiter};
+use crate::rules::Rules;
+use crate::sync::{lock, Mutex};
+use crate::{Code, CodeBreaker, CodeMaker, CodePeg, Score, Scorer, SIZE};
+
+/// The version of the protocol spoken by this crate.
+pub const PROTOCOL_VERSION: u32 = 2;
+/// The oldest version still spoken, by clients which do not say hello.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+/// The longest message received, in bytes, which leaves room for the state
+/// of games of hundreds of rounds.
+pub const MAX_MESSAGE_LEN: usize = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Role {
+    Maker,
+    Breaker,
+}
+
+/// How a game ended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Outcome {
+    /// The code breaker won in this number of rounds.
+    Won(usize),
+    /// The code breaker lost; this was the code.
+    Lost(Code),
+}
+
+/// Something a peer supports, announced in the handshake.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Feature {
+    /// Games whose codes may repeat colors.
+    Duplicates,
+    /// Games whose codes may not.
+    Unique,
+    /// Games with up to this number of colors.
+    Colors(usize),
+    Watch,
+    Resume,
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Feature::Duplicates => write!(f, "DUPLICATES"),
+            Feature::Unique => write!(f, "UNIQUE"),
+            Feature::Colors(colors) => write!(f, "COLORS={}", colors),
+            Feature::Watch => write!(f, "WATCH"),
+            Feature::Resume => write!(f, "RESUME"),
+        }
+    }
+}
+
+impl Feature {
+    /// Reads a feature, or returns `None` if it is unknown.
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "DUPLICATES" => Some(Feature::Duplicates),
+            "UNIQUE" => Some(Feature::Unique),
+            "WATCH" => Some(Feature::Watch),
+            "RESUME" => Some(Feature::Resume),
+            _ => s
+                .strip_prefix("COLORS=")
+                .and_then(|colors| colors.parse().ok())
+                .map(Feature::Colors),
+        }
+    }
+
+    /// The features of a server hosting games with any `Rules`.
+    pub fn all() -> Vec<Feature> {
+        vec![
+            Feature::Duplicates,
+            Feature::Unique,
+            Feature::Colors(CodePeg::ALL.len()),
+            Feature::Watch,
+            Feature::Resume,
+        ]
+    }
+}
+
+/// Checks that a peer with `features` hosts games with `setup`.
+pub fn supports(features: &[Feature], setup: &Setup) -> Result<(), Error> {
+    let rules = setup.rules;
+    let (variant, feature) = if rules.duplicates() {
+        ("whose codes repeat colors", Feature::Duplicates)
+    } else {
+        ("whose codes do not repeat colors", Feature::Unique)
+    };
+    if !features.contains(&feature) {
+        return Err(Error::Unsupported(format!("games {}", variant)));
+    }
+    let colors = features.iter().find_map(|feature| match feature {
+        Feature::Colors(colors) => Some(*colors),
+        _ => None,
+    });
+    match colors {
+        Some(colors) if rules.colors() > colors => Err(Error::Unsupported(format!(
+            "games with {} colors, at most {}",
+            rules.colors(),
+            colors
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// The version spoken with a peer speaking `theirs`.
+pub fn negotiate(theirs: u32) -> Result<u32, Error> {
+    if theirs < MIN_PROTOCOL_VERSION {
+        return Err(Error::Unsupported(format!(
+            "protocol version {}, at least {}",
+            theirs, MIN_PROTOCOL_VERSION
+        )));
+    }
+    Ok(theirs.min(PROTOCOL_VERSION))
+}
+
+/// The state of a game, told to a player who resumes it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Resumption {
+    pub role: Role,
+    pub max_round: NonZeroUsize,
+    /// The time the player has for their next move, if moves are limited.
+    pub move_time: Option<Duration>,
+    /// The digest of the commitment to the code, once it is made.
+    pub digest: Option<String>,
+    pub rounds: Vec<(Code, Score)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Message {
+    Hello(u32, Vec<Feature>),
+    Join(Role, Option<Setup>),
+    Watch(SessionId, Option<Token>),
+    Resume(SessionId, Token),
+    Resumed(Resumption),
+    Start(NonZeroUsize, SessionId, Token),
+    Code(Code),
+    Commit(String),
+    Guess(Code),
+    Resign(Token),
+    Score(Score),
+    Result(Outcome),
+    Reveal(Code, u128),
+    Error(String),
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Message::Hello(version, features) => {
+                write!(f, "HELLO {}", version)?;
+                for feature in features {
+                    write!(f, " {}", feature)?;
+                }
+                Ok(())
+            }
+            Message::Join(role, setup) => {
+                write!(f, "JOIN {}", role_name(*role))?;
+                if let Some(Setup { max_round, rules }) = setup {
+                    let duplicates = if rules.duplicates() {
+                        "DUPLICATES"
+                    } else {
+                        "UNIQUE"
+                    };
+                    write!(f, " {} {} {}", max_round, rules.colors(), duplicates)?;
+                    if rules.strict() {
+                        write!(f, " STRICT")?;
+                    }
+                }
+                Ok(())
+            }
+            Message::Watch(session, None) => write!(f, "WATCH {}", session),
+            Message::Watch(session, Some(token)) => write!(f, "WATCH {} {}", session, token),
+            Message::Resume(session, token) => write!(f, "RESUME {} {}", session, token),
+            Message::Resumed(resumption) => {
+                let Resumption {
+                    role,
+                    max_round,
+                    move_time,
+                    digest,
+                    rounds,
+                } = resumption;
+                write!(f, "RESUMED {} {}", role_name(*role), max_round)?;
+                match move_time {
+                    Some(time) => write!(f, " {}", time.as_secs())?,
+                    None => write!(f, " -")?,
+                }
+                write!(f, " {}", digest.as_deref().unwrap_or("-"))?;
+                for (guess, score) in rounds {
+                    write!(f, " {} {} {}", guess, score.matches(), score.presents())?;
+                }
+                Ok(())
+            }
+            Message::Start(rounds, session, token) => {
+                write!(f, "START {} {} {}", rounds, session, token)
+            }
+            Message::Code(code) => write!(f, "CODE {}", code),
+            Message::Commit(digest) => write!(f, "COMMIT {}", digest),
+            Message::Guess(code) => write!(f, "GUESS {}", code),
+            Message::Resign(token) => write!(f, "RESIGN {}", token),
+            Message::Score(score) => write!(f, "SCORE {} {}", score.matches(), score.presents()),
+            Message::Result(Outcome::Won(rounds)) => write!(f, "RESULT WON {}", rounds),
+            Message::Result(Outcome::Lost(code)) => write!(f, "RESULT LOST {}", code),
+            Message::Reveal(code, salt) => write!(f, "REVEAL {} {:032x}", code, salt),
+            Message::Error(message) => write!(f, "ERROR {}", message),
+        }
+    }
+}
+
+impl FromStr for Message {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::Protocol(format!("invalid message '{}'", s));
+        let code = |s: &str| s.parse::<Code>().map_err(|_| invalid());
+        let count = |s: &str| s.parse::<usize>().map_err(|_| invalid());
+        let max_round = |s: &str| s.parse::<NonZeroUsize>().map_err(|_| invalid());
+        let role = |s: &str| match s {
+            "MAKER" => Ok(Role::Maker),
+            "BREAKER" => Ok(Role::Breaker),
+            _ => Err(invalid()),
+        };
+        let score = |matches: &str, presents: &str| {
+            Score::from_counts(count(matches)?, count(presents)?).ok_or_else(invalid)
+        };
+
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words[..] {
+            ["HELLO", version, ref features @ ..] => Ok(Message::Hello(
+                version.parse().map_err(|_| invalid())?,
+                features.iter().filter_map(|f| Feature::parse(f)).collect(),
+            )),
+            ["JOIN", r, ref setup @ ..] => {
+                let role = role(r)?;
+                let setup = match setup[..] {
+                    [] => None,
+                    [rounds, colors, duplicates, ref strict @ ..] => {
+                        let duplicates = match duplicates {
+                            "DUPLICATES" => true,
+                            "UNIQUE" => false,
+                            _ => return Err(invalid()),
+                        };
+                        let strict = match strict {
+                            [] => false,
+                            ["STRICT"] => true,
+                            _ => return Err(invalid()),
+                        };
+                        let rules = Rules::new(count(colors)?, duplicates)
+                            .map_err(|err| Error::Protocol(err.to_string()))?
+                            .with_strict(strict);
+                        Some(Setup {
+                            max_round: max_round(rounds)?,
+                            rules,
+                        })
+                    }
+                    _ => return Err(invalid()),
+                };
+                Ok(Message::Join(role, setup))
+            }
+            ["WATCH", session] => Ok(Message::Watch(
+                SessionId(session.parse().map_err(|_| invalid())?),
+                None,
+            )),
+            ["WATCH", session, token] => Ok(Message::Watch(
+                SessionId(session.parse().map_err(|_| invalid())?),
+                Some(token.parse().map_err(|_| invalid())?),
+            )),
+            ["RESUME", session, token] => Ok(Message::Resume(
+                SessionId(session.parse().map_err(|_| invalid())?),
+                token.parse().map_err(|_| invalid())?,
+            )),
+            ["RESUMED", r, rounds_allowed, time, digest, ref rounds @ ..]
+                if rounds.len() % 3 == 0 =>
+            {
+                let move_time = match time {
+                    "-" => None,
+                    seconds => Some(Duration::from_secs(seconds.parse().map_err(|_| invalid())?)),
+                };
+                let digest = match digest {
+                    "-" => None,
+                    digest if is_digest(digest) => Some(digest.to_string()),
+                    _ => return Err(invalid()),
+                };
+                let rounds = rounds
+                    .chunks(3)
+                    .map(|round| Ok((code(round[0])?, score(round[1], round[2])?)))
+                    .collect::<Result<_, Error>>()?;
+                Ok(Message::Resumed(Resumption {
+                    role: role(r)?,
+                    max_round: max_round(rounds_allowed)?,
+                    move_time,
+                    digest,
+                    rounds,
+                }))
+            }
+            ["START", rounds, session, token] => Ok(Message::Start(
+                max_round(rounds)?,
+                SessionId(session.parse().map_err(|_| invalid())?),
+                token.parse().map_err(|_| invalid())?,
+            )),
+            ["CODE", c] => Ok(Message::Code(code(c)?)),
+            ["COMMIT", digest] if is_digest(digest) => Ok(Message::Commit(digest.to_string())),
+            ["GUESS", c] => Ok(Message::Guess(code(c)?)),
+            ["RESIGN", token] => Ok(Message::Resign(token.parse().map_err(|_| invalid())?)),
+            ["SCORE", matches, presents] => score(matches, presents).map(Message::Score),
+            ["RESULT", "WON", rounds] => Ok(Message::Result(Outcome::Won(count(rounds)?))),
+            ["RESULT", "LOST", c] => Ok(Message::Result(Outcome::Lost(code(c)?))),
+            ["REVEAL", c, salt] if salt.len() == 32 => Ok(Message::Reveal(
+                code(c)?,
+                u128::from_str_radix(salt, 16).map_err(|_| invalid())?,
+            )),
+            ["ERROR", ..] => Ok(Message::Error(
+                s.trim_start()["ERROR".len()..].trim().to_string(),
+            )),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::Maker => "MAKER",
+        Role::Breaker => "BREAKER",
+    }
+}
+
+fn is_digest(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// A code and the salt hiding it in its digest.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Commitment {
+    code: Code,
+    salt: u128,
+}
+
+impl Commitment {
+    /// A commitment to `code` with a random salt.
+    pub fn new(code: Code) -> Self {
+        Commitment::with_salt(code, rand::random())
+    }
+
+    pub fn with_salt(code: Code, salt: u128) -> Self {
+        Commitment { code, salt }
+    }
+
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    pub fn salt(&self) -> u128 {
+        self.salt
+    }
+
+    /// The digest to send before the game, which tells nothing about the code.
+    pub fn digest(&self) -> String {
+        Sha256::digest(format!("{:032x} {}", self.salt, self.code))
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    Io(io::Error),
+    /// The peer closed the connection.
+    Closed,
+    /// The peer sent a message which is invalid, or unexpected at this point.
+    Protocol(String),
+    /// The peer did not answer within the read timeout of the connection.
+    Timeout,
+    /// The peer does not support what was asked of it.
+    Unsupported(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Closed => write!(f, "connection closed"),
+            Error::Protocol(message) => write!(f, "protocol error: {}", message),
+            Error::Timeout => write!(f, "timed out"),
+            Error::Unsupported(what) => write!(f, "not supported: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            // Reads past the timeout of the socket fail with either kind,
+            // depending on the platform.
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => Error::Timeout,
+            _ => Error::Io(err),
+        }
+    }
+}
+
+/// Carries the messages of a connection as lines of text.
+pub trait Transport: Send {
+    fn send_line(&mut self, line: &str) -> Result<(), Error>;
+
+    /// Waits for the next line, or returns `None` once the peer closed the
+    /// connection.
+    fn receive_line(&mut self) -> Result<Option<String>, Error>;
+}
+
+/// One message per line of a TCP stream.
+struct Tcp {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Transport for Tcp {
+    fn send_line(&mut self, line: &str) -> Result<(), Error> {
+        writeln!(self.writer, "{}", line)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn receive_line(&mut self) -> Result<Option<String>, Error> {
+        let mut line = String::new();
+        let limit = MAX_MESSAGE_LEN as u64 + 1;
+        match self.reader.by_ref().take(limit).read_line(&mut line)? {
+            0 => Ok(None),
+            _ if line.trim_end().len() > MAX_MESSAGE_LEN => {
+                Err(Error::Protocol("message too long".to_string()))
+            }
+            _ => Ok(Some(line)),
+        }
+    }
+}
+
+/// One message per text frame, for browsers.
+#[cfg(feature = "ws")]
+impl<S: io::Read + Write + Send> Transport for tungstenite::WebSocket<S> {
+    fn send_line(&mut self, line: &str) -> Result<(), Error> {
+        self.send(tungstenite::Message::Text(line.to_string()))
+            .map_err(websocket_error)
+    }
+
+    fn receive_line(&mut self) -> Result<Option<String>, Error> {
+        loop {
+            match self.read() {
+                Ok(tungstenite::Message::Text(line)) => return Ok(Some(line)),
+                Ok(tungstenite::Message::Close(_)) => return Ok(None),
+                // Pings are answered by tungstenite itself.
+                Ok(_) => continue,
+                Err(tungstenite::Error::ConnectionClosed) => return Ok(None),
+                Err(err) => return Err(websocket_error(err)),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "ws")]
+fn websocket_error(err: tungstenite::Error) -> Error {
+    match err {
+        tungstenite::Error::Io(err) => Error::from(err),
+        err => Error::Protocol(err.to_string()),
+    }
+}
+
+/// A connection to a player, or to the server, exchanging messages.
+pub struct Connection {
+    transport: Box<dyn Transport>,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> Result<Self, Error> {
+        Ok(Connection::with_transport(Tcp {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        }))
+    }
+
+    pub fn with_transport<T: Transport + 'static>(transport: T) -> Self {
+        Connection {
+            transport: Box::new(transport),
+        }
+    }
+
+    pub fn connect<A: ToSocketAddrs>(address: A) -> Result<Self, Error> {
+        Connection::new(TcpStream::connect(address)?)
+    }
+
+    /// Completes the WebSocket handshake of a client which connected to the
+    /// server.
+    #[cfg(feature = "ws")]
+    pub fn accept_websocket(stream: TcpStream) -> Result<Self, Error> {
+        let config = tungstenite::protocol::WebSocketConfig {
+            max_message_size: Some(MAX_MESSAGE_LEN),
+            max_frame_size: Some(MAX_MESSAGE_LEN),
+            ..Default::default()
+        };
+        let websocket = tungstenite::accept_with_config(stream, Some(config))
+            .map_err(|err| Error::Protocol(err.to_string()))?;
+        Ok(Connection::with_transport(websocket))
+    }
+
+    /// Connects to a server at a `ws://` URL.
+    #[cfg(feature = "ws")]
+    pub fn connect_websocket(url: &str) -> Result<Self, Error> {
+        let (websocket, _) = tungstenite::connect(url).map_err(websocket_error)?;
+        Ok(Connection::with_transport(websocket))
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), Error> {
+        self.transport.send_line(&message.to_string())
+    }
+
+    /// Waits for the next message.
+    ///
+    /// An `ERROR` message from the peer is returned as a protocol error.
+    pub fn receive(&mut self) -> Result<Message, Error> {
+        let line = self.transport.receive_line()?.ok_or(Error::Closed)?;
+        match line.parse()? {
+            Message::Error(message) => Err(Error::Protocol(message)),
+            message => Ok(message),
+        }
+    }
+
+    /// Reports an unexpected message to the peer, and returns the error.
+    fn unexpected(&mut self, message: Message) -> Error {
+        let error = format!("unexpected message '{}'", message);
+        // The peer may already be gone; the error is returned anyway.
+        let _ = self.send(&Message::Error(error.clone()));
+        Error::Protocol(error)
+    }
+}
+
+/// How a player who lost their connection comes back to their game: the
+/// server sends the connection they resumed the game on, which their remote
+/// player waits `grace` for before giving up, and tells them the state of
+/// the game on it.
+pub struct Rejoin {
+    connections: Receiver<Connection>,
+    grace: Duration,
+    state: Box<dyn Fn() -> Resumption + Send>,
+}
+
+impl Rejoin {
+    pub fn new(
+        connections: Receiver<Connection>,
+        grace: Duration,
+        state: impl Fn() -> Resumption + Send + 'static,
+    ) -> Self {
+        Rejoin {
+            connections,
+            grace,
+            state: Box::new(state),
+        }
+    }
+}
+
+/// Whether the player is gone, rather than slow or misbehaving.
+fn is_lost(err: &Error) -> bool {
+    matches!(err, Error::Closed | Error::Io(_))
+}
+
+/// The connection to a remote player, replaced by the one they resume the
+/// game on if it is lost.
+struct Link {
+    connection: Connection,
+    rejoin: Option<Rejoin>,
+}
+
+impl Link {
+    fn new(connection: Connection) -> Self {
+        Link {
+            connection,
+            rejoin: None,
+        }
+    }
+
+    /// Waits for the player to resume the game, or fails with `lost`.
+    fn reconnect(&mut self, lost: Error) -> Result<(), Error> {
+        let Some(rejoin) = &self.rejoin else {
+            return Err(lost);
+        };
+        loop {
+            let Ok(mut connection) = rejoin.connections.recv_timeout(rejoin.grace) else {
+                return Err(lost);
+            };
+            #[cfg(feature = "tracing")]
+            tracing::info!("the player resumed the game");
+            if connection.send(&Message::Resumed((rejoin.state)())).is_ok() {
+                self.connection = connection;
+                return Ok(());
+            }
+        }
+    }
+
+    fn receive(&mut self) -> Result<Message, Error> {
+        loop {
+            match self.connection.receive() {
+                Err(err) if is_lost(&err) => self.reconnect(err)?,
+                result => return result,
+            }
+        }
+    }
+
+    /// Sends `message`, which is not sent again if the player resumes the
+    /// game instead: the state they are told includes it.
+    fn send(&mut self, message: &Message) -> Result<(), Error> {
+        match self.connection.send(message) {
+            Err(err) if is_lost(&err) => self.reconnect(err),
+            result => result,
+        }
+    }
+
+    fn unexpected(&mut self, message: Message) -> Error {
+        self.connection.unexpected(message)
+    }
+
+    /// The last connection the player resumed the game on, if any, or else
+    /// the first one.
+    fn into_connection(mut self) -> Connection {
+        if let Some(rejoin) = &self.rejoin {
+            for mut connection in rejoin.connections.try_iter() {
+                if connection.send(&Message::Resumed((rejoin.state)())).is_ok() {
+                    self.connection = connection;
+                }
+            }
+        }
+        self.connection
+    }
+}
+
+/// A code maker on the other end of a connection, as seen by the server.
+///
+/// The code received from the player is committed to, so that the code
+/// breaker can check it once the game is over.
+///
+/// The trait cannot report errors, so servers wait for the code with
+/// `commit` before the game starts. If the connection fails in `make_code`
+/// instead, the game is played against AAAA and `take_error` says why.
+///
+/// Once given a `Rejoin`, a player who lost their connection may resume the
+/// game instead. Having nothing left to do once the code is made, they are
+/// only told its state with the result.
+pub struct RemoteCodeMaker {
+    connection: RefCell<Link>,
+    commitment: Cell<Option<Commitment>>,
+    /// Why `make_code` could not get the code, if it failed.
+    error: RefCell<Option<Error>>,
+}
+
+impl RemoteCodeMaker {
+    pub fn new(connection: Connection) -> Self {
+        RemoteCodeMaker {
+            connection: RefCell::new(Link::new(connection)),
+            commitment: Cell::new(None),
+            error: RefCell::new(None),
+        }
+    }
+
+    /// Lets the player resume the game if their connection is lost.
+    pub fn with_rejoin(self, rejoin: Rejoin) -> Self {
+        self.connection.borrow_mut().rejoin = Some(rejoin);
+        self
+    }
+
+    /// Waits for the code of the player, and returns the commitment to it.
+    ///
+    /// The game then plays this code, instead of waiting for it.
+    pub fn commit(&self) -> Result<Commitment, Error> {
+        if let Some(commitment) = self.commitment.get() {
+            return Ok(commitment);
+        }
+        let mut connection = self.connection.borrow_mut();
+        let code = match connection.receive()? {
+            Message::Code(code) => code,
+            message => return Err(connection.unexpected(message)),
+        };
+        let commitment = Commitment::new(code);
+        self.commitment.set(Some(commitment));
+        Ok(commitment)
+    }
+
+    /// The code received from the player, once the game has started.
+    pub fn code(&self) -> Option<Code> {
+        self.commitment().map(|commitment| commitment.code())
+    }
+
+    pub fn commitment(&self) -> Option<Commitment> {
+        self.commitment.get()
+    }
+
+    /// Why `make_code` could not get the code of the player, if it failed.
+    pub fn take_error(&mut self) -> Option<Error> {
+        self.error.get_mut().take()
+    }
+
+    /// The connection to the player, the last one they resumed the game on
+    /// if any.
+    pub fn into_connection(self) -> Connection {
+        self.connection.into_inner().into_connection()
+    }
+}
+
+impl CodeMaker for RemoteCodeMaker {
+    fn make_code(&self) -> Code {
+        match self.commit() {
+            Ok(commitment) => commitment.code(),
+            Err(err) => {
+                *self.error.borrow_mut() = Some(err);
+                Code::new([CodePeg::A; SIZE])
+            }
+        }
+    }
+}
+
+/// A code breaker on the other end of a connection, as seen by the server.
+///
+/// Invalid or unexpected messages are reported to the player, who can try
+/// again a few times. The player forfeits once out of retries, or if the
+/// connection fails, including when they take longer than the read timeout
+/// of the socket (see `TcpStream::set_read_timeout`): `take_error` then
+/// says why.
+///
+/// The player may resign with a token allowing it, once the tokens of the
+/// server are given with `with_tokens`, and resume the game if their
+/// connection is lost, once given a `Rejoin`.
+pub struct RemoteCodeBreaker {
+    connection: RefCell<Link>,
+    retries: usize,
+    rounds: usize,
+    has_won: bool,
+    tokens: Option<(Arc<Mutex<Tokens>>, SessionId)>,
+    resigned: Cell<bool>,
+    limiter: Option<RateLimiter<()>>,
+    /// Why the player forfeited, if they did not resign.
+    error: RefCell<Option<Error>>,
+}
+
+impl RemoteCodeBreaker {
+    /// The number of invalid messages allowed for each guess by `new`.
+    pub const DEFAULT_RETRIES: usize = 3;
+
+    pub fn new(connection: Connection) -> Self {
+        RemoteCodeBreaker::with_retries(connection, RemoteCodeBreaker::DEFAULT_RETRIES)
+    }
+
+    /// A code breaker allowed `retries` invalid messages for each guess.
+    pub fn with_retries(connection: Connection, retries: usize) -> Self {
+        RemoteCodeBreaker {
+            connection: RefCell::new(Link::new(connection)),
+            retries,
+            rounds: 0,
+            has_won: false,
+            tokens: None,
+            resigned: Cell::new(false),
+            limiter: None,
+            error: RefCell::new(None),
+        }
+    }
+
+    /// Only lets the player send messages as often as `quota` allows: the
+    /// others are invalid.
+    pub fn with_rate_limit(mut self, quota: Quota) -> Self {
+        self.limiter = Some(RateLimiter::new(quota));
+        self
+    }
+
+    /// Lets the player resign in `session`, with a token from `tokens`.
+    pub fn with_tokens(mut self, tokens: Arc<Mutex<Tokens>>, session: SessionId) -> Self {
+        self.tokens = Some((tokens, session));
+        self
+    }
+
+    /// Lets the player resume the game if their connection is lost.
+    pub fn with_rejoin(self, rejoin: Rejoin) -> Self {
+        self.connection.borrow_mut().rejoin = Some(rejoin);
+        self
+    }
+
+    /// Whether the player resigned.
+    pub fn resigned(&self) -> bool {
+        self.resigned.get()
+    }
+
+    /// Why the player forfeited without resigning, if they did.
+    pub fn take_error(&mut self) -> Option<Error> {
+        self.error.get_mut().take()
+    }
+
+    /// Makes the player forfeit because of `err`, and returns the guess the
+    /// game ignores then.
+    fn fail(&self, err: Error) -> Code {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(error = %err, "the code breaker forfeits");
+        *self.error.borrow_mut() = Some(err);
+        Code::new([CodePeg::A; SIZE])
+    }
+
+    /// Checks that `token` lets the player resign.
+    fn authorize(&self, token: Token) -> Result<(), String> {
+        let (tokens, session) = self.tokens.as_ref().ok_or("resigning is not allowed")?;
+        match lock(tokens).authorize(token, *session, Capability::Resign) {
+            Ok(Role::Breaker) => Ok(()),
+            Ok(Role::Maker) => Err("only the code breaker can resign".to_string()),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    /// The number of rounds if the player won.
+    pub fn won_in(&self) -> Option<usize> {
+        self.has_won.then_some(self.rounds)
+    }
+
+    /// The connection to the player, the last one they resumed the game on
+    /// if any.
+    pub fn into_connection(self) -> Connection {
+        self.connection.into_inner().into_connection()
+    }
+}
+
+impl CodeBreaker for RemoteCodeBreaker {
+    fn guess_code(&self) -> Code {
+        if self.error.borrow().is_some() {
+            return Code::new([CodePeg::A; SIZE]);
+        }
+        let mut connection = self.connection.borrow_mut();
+        let mut error = None;
+        for _attempt in 0..=self.retries {
+            let limited = |limiter: &RateLimiter<()>| limiter.check((), Instant::now()).err();
+            let received = match (
+                connection.receive(),
+                self.limiter.as_ref().and_then(limited),
+            ) {
+                // Reported like any invalid message.
+                (Ok(_), Some(limited)) => Err(Error::Protocol(limited.to_string())),
+                (received, _) => received,
+            };
+            let err = match received {
+                Ok(Message::Guess(code)) => return code,
+                Ok(Message::Resign(token)) => match self.authorize(token) {
+                    Ok(()) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::info!("the code breaker resigned");
+                        self.resigned.set(true);
+                        // Ignored by the game, since the player forfeited.
+                        return Code::new([CodePeg::ALL[0]; SIZE]);
+                    }
+                    Err(message) => {
+                        let _ = connection.send(&Message::Error(message.clone()));
+                        Error::Protocol(message)
+                    }
+                },
+                Ok(message) => connection.unexpected(message),
+                Err(Error::Protocol(message)) => {
+                    let _ = connection.send(&Message::Error(message.clone()));
+                    Error::Protocol(message)
+                }
+                Err(err) => return self.fail(err),
+            };
+            #[cfg(feature = "tracing")]
+            tracing::warn!(error = %err, "invalid message from the code breaker");
+            error = Some(err);
+        }
+        self.fail(error.unwrap_or(Error::Closed))
+    }
+
+    fn set_score(&mut self, score: Score) {
+        self.rounds += 1;
+        if let Err(err) = self.connection.get_mut().send(&Message::Score(score)) {
+            self.fail(err);
+        }
+    }
+
+    fn loses(&mut self) {}
+
+    fn wins(&mut self, _rounds: usize) {
+        self.has_won = true;
+    }
+
+    fn forfeited(&self) -> bool {
+        self.resigned.get() || self.error.borrow().is_some()
+    }
+}
+
+/// Says hello to the server on `connection`, announcing `features`, and
+/// returns the version of the protocol spoken and the features of the
+/// server.
+///
+/// Servers which only speak version 1 answer with a protocol error and
+/// close the connection: clients then join on a new one without the
+/// handshake.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn hello(
+    connection: &mut Connection,
+    features: &[Feature],
+) -> Result<(u32, Vec<Feature>), Error> {
+    connection.send(&Message::Hello(PROTOCOL_VERSION, features.to_vec()))?;
+    match connection.receive()? {
+        Message::Hello(version, features) => {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(version, "handshake");
+            Ok((version, features))
+        }
+        message => Err(connection.unexpected(message)),
+    }
+}
+
+/// Joins a game on `connection`, with the default rules of the server unless
+/// `setup` is given, and waits for it to start.
+///
+/// Returns the number of rounds allowed, the session of the game and the
+/// token of the player.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(?role)))]
+pub fn join(
+    connection: &mut Connection,
+    role: Role,
+    setup: Option<Setup>,
+) -> Result<(NonZeroUsize, SessionId, Token), Error> {
+    connection.send(&Message::Join(role, setup))?;
+    match connection.receive()? {
+        Message::Start(rounds, session, token) => {
+            #[cfg(feature = "tracing")]
+            tracing::info!(%session, rounds = rounds.get(), "started");
+            Ok((rounds, session, token))
+        }
+        message => Err(connection.unexpected(message)),
+    }
+}
+
+/// Resumes a game on `connection`, after losing the connection it was
+/// played on, and returns its state.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(%session)))]
+pub fn resume(
+    connection: &mut Connection,
+    session: SessionId,
+    token: Token,
+) -> Result<Resumption, Error> {
+    connection.send(&Message::Resume(session, token))?;
+    match connection.receive()? {
+        Message::Resumed(resumption) => Ok(resumption),
+        message => Err(connection.unexpected(message)),
+    }
+}
+
+fn result(connection: &mut Connection) -> Result<Outcome, Error> {
+    match connection.receive()? {
+        Message::Result(outcome) => Ok(outcome),
+        message => Err(connection.unexpected(message)),
+    }
+}
+
+/// Plays a game joined as the code maker, with `code_maker` making the code.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn play_maker<T: CodeMaker>(
+    connection: &mut Connection,
+    code_maker: &T,
+) -> Result<Outcome, Error> {
+    connection.send(&Message::Code(code_maker.make_code()))?;
+    result(connection)
+}
+
+/// Carries on a game resumed as the code maker, with `code_maker` making
+/// the code unless it was made already.
+pub fn resume_maker<T: CodeMaker>(
+    connection: &mut Connection,
+    code_maker: &T,
+    resumption: &Resumption,
+) -> Result<Outcome, Error> {
+    match resumption.digest {
+        Some(_) => result(connection),
+        None => play_maker(connection, code_maker),
+    }
+}
+
+/// Plays a game joined as the code breaker, with `code_breaker` guessing,
+/// and resigning with `token` if it forfeits.
+///
+/// Once the game is over, the code revealed by the server is checked
+/// against its commitment and the scores, which fails with a protocol error
+/// if the server cheated.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(max_round = max_round.get())))]
+pub fn play_breaker<U: CodeBreaker>(
+    connection: &mut Connection,
+    code_breaker: &mut U,
+    max_round: NonZeroUsize,
+    token: Token,
+) -> Result<Outcome, Error> {
+    let digest = commit(connection)?;
+    play_rounds(
+        connection,
+        code_breaker,
+        max_round,
+        token,
+        digest,
+        Vec::new(),
+    )
+}
+
+/// Carries on a game resumed as the code breaker, with `code_breaker`
+/// guessing. It should know the rounds played so far, e.g. a `Solver`
+/// which recorded them.
+pub fn resume_breaker<U: CodeBreaker>(
+    connection: &mut Connection,
+    code_breaker: &mut U,
+    resumption: Resumption,
+    token: Token,
+) -> Result<Outcome, Error> {
+    let digest = match resumption.digest {
+        Some(digest) => digest,
+        None => commit(connection)?,
+    };
+    let max_round = resumption.max_round;
+    play_rounds(
+        connection,
+        code_breaker,
+        max_round,
+        token,
+        digest,
+        resumption.rounds,
+    )
+}
+
+fn commit(connection: &mut Connection) -> Result<String, Error> {
+    match connection.receive()? {
+        Message::Commit(digest) => Ok(digest),
+        message => Err(connection.unexpected(message)),
+    }
+}
+
+/// Plays the rounds left after `rounds`, then checks the revealed code.
+fn play_rounds<U: CodeBreaker>(
+    connection: &mut Connection,
+    code_breaker: &mut U,
+    max_round: NonZeroUsize,
+    token: Token,
+    digest: String,
+    mut rounds: Vec<(Code, Score)>,
+) -> Result<Outcome, Error> {
+    let won = rounds
+        .last()
+        .is_some_and(|(_, score)| score.matches() == SIZE);
+    let left = if won {
+        0
+    } else {
+        max_round.get().saturating_sub(rounds.len())
+    };
+    for _round in 0..left {
+        let guess = code_breaker.guess_code();
+        if code_breaker.forfeited() {
+            connection.send(&Message::Resign(token))?;
+            break;
+        }
+        connection.send(&Message::Guess(guess))?;
+        let score = match connection.receive()? {
+            Message::Score(score) => score,
+            message => return Err(connection.unexpected(message)),
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(round = rounds.len() + 1, %guess, matched = score.matches(), present = score.presents());
+        rounds.push((guess, score));
+        code_breaker.set_score(score);
+        if score.matches() == SIZE {
+            break;
+        }
+    }
+    let outcome = result(connection)?;
+    if let Outcome::Lost(_) = outcome {
+        code_breaker.loses();
+    }
+
+    let commitment = match connection.receive()? {
+        Message::Reveal(code, salt) => Commitment::with_salt(code, salt),
+        message => return Err(connection.unexpected(message)),
+    };
+    if commitment.digest() != digest {
+        return Err(Error::Protocol(
+            "the code does not match its commitment".to_string(),
+        ));
+    }
+    let scorer = Scorer::new(commitment.code());
+    if rounds
+        .iter()
+        .any(|&(guess, score)| scorer.score(guess) != score)
+        || matches!(outcome, Outcome::Lost(code) if code != commitment.code())
+    {
+        return Err(Error::Protocol(
+            "the scores do not match the code".to_string(),
+        ));
+    }
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod test_net {
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::solver::Solver;
+    use crate::testutil::DeterministicCodeMaker;
+    use crate::Game;
+
+    #[test]
+    fn messages() {
+        let messages = [
+            Message::Hello(2, Feature::all()),
+            Message::Hello(1, Vec::new()),
+            Message::Join(Role::Maker, None),
+            Message::Join(
+                Role::Breaker,
+                Some(Setup {
+                    max_round: NonZeroUsize::new(12).unwrap(),
+                    rules: Rules::new(5, false).unwrap(),
+                }),
+            ),
+            Message::Join(
+                Role::Maker,
+                Some(Setup {
+                    max_round: NonZeroUsize::new(10).unwrap(),
+                    rules: Rules::default().with_strict(true),
+                }),
+            ),
+            Message::Watch(SessionId(3), None),
+            Message::Watch(SessionId(3), Some(Token::random())),
+            Message::Resume(SessionId(3), Token::random()),
+            Message::Resumed(Resumption {
+                role: Role::Maker,
+                max_round: NonZeroUsize::new(8).unwrap(),
+                move_time: None,
+                digest: None,
+                rounds: Vec::new(),
+            }),
+            Message::Resumed(Resumption {
+                role: Role::Breaker,
+                max_round: NonZeroUsize::new(10).unwrap(),
+                move_time: Some(Duration::from_secs(30)),
+                digest: Some(Commitment::new("ABCD".parse().unwrap()).digest()),
+                rounds: vec![
+                    ("AABB".parse().unwrap(), Score::from_counts(1, 1).unwrap()),
+                    ("ABCC".parse().unwrap(), Score::from_counts(3, 0).unwrap()),
+                ],
+            }),
+            Message::Start(
+                NonZeroUsize::new(10).unwrap(),
+                SessionId(3),
+                Token::random(),
+            ),
+            Message::Code("ABCD".parse().unwrap()),
+            Message::Commit(Commitment::new("ABCD".parse().unwrap()).digest()),
+            Message::Guess("AABB".parse().unwrap()),
+            Message::Resign(Token::random()),
+            Message::Score(Score::from_counts(1, 2).unwrap()),
+            Message::Result(Outcome::Won(4)),
+            Message::Result(Outcome::Lost("FFFF".parse().unwrap())),
+            Message::Reveal("FFFF".parse().unwrap(), 0xdead_beef),
+            Message::Error("unexpected message".to_string()),
+        ];
+        for message in messages {
+            assert_eq!(message.to_string().parse::<Message>().unwrap(), message);
+        }
+        assert!("GUESS ABCX".parse::<Message>().is_err());
+        assert!("SCORE 3 1".parse::<Message>().is_err());
+        assert!("HELLO".parse::<Message>().is_err());
+        assert!("COMMIT abc".parse::<Message>().is_err());
+        assert!("JOIN MAKER 10 9 UNIQUE".parse::<Message>().is_err());
+        assert!("JOIN MAKER 10 6 UNIQUE LAX".parse::<Message>().is_err());
+        assert!("RESUMED BREAKER 10 - - ABCD 1".parse::<Message>().is_err());
+    }
+
+    #[test]
+    fn negotiates() {
+        assert_eq!(
+            "HELLO 3 UNIQUE TEAMS COLORS=4".parse::<Message>().unwrap(),
+            Message::Hello(3, vec![Feature::Unique, Feature::Colors(4)])
+        );
+        assert_eq!(negotiate(3).unwrap(), PROTOCOL_VERSION);
+        assert_eq!(negotiate(1).unwrap(), 1);
+        assert!(matches!(negotiate(0), Err(Error::Unsupported(_))));
+
+        let setup = |colors, duplicates| Setup {
+            max_round: NonZeroUsize::new(10).unwrap(),
+            rules: Rules::new(colors, duplicates).unwrap(),
+        };
+        let features = [Feature::Unique, Feature::Colors(5)];
+        assert!(supports(&features, &setup(5, false)).is_ok());
+        assert!(matches!(
+            supports(&features, &setup(6, false)),
+            Err(Error::Unsupported(_))
+        ));
+        assert!(matches!(
+            supports(&features, &setup(4, true)),
+            Err(Error::Unsupported(_))
+        ));
+        assert!(supports(&Feature::all(), &setup(6, true)).is_ok());
+    }
+
+    #[test]
+    fn commitments() {
+        let code = "ABCD".parse().unwrap();
+        let commitment = Commitment::with_salt(code, 1);
+        assert_eq!(commitment.digest().len(), 64);
+        assert_eq!(commitment.digest(), Commitment::with_salt(code, 1).digest());
+        assert_ne!(commitment.digest(), Commitment::with_salt(code, 2).digest());
+        let other = Commitment::with_salt("ABCE".parse().unwrap(), 1);
+        assert_ne!(commitment.digest(), other.digest());
+    }
+
+    #[test]
+    fn remote_game() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let secret: Code = "CAFE".parse().unwrap();
+        let maker = thread::spawn(move || {
+            let mut connection = Connection::connect(address).unwrap();
+            join(&mut connection, Role::Maker, None).unwrap();
+            play_maker(&mut connection, &DeterministicCodeMaker::new(secret)).unwrap()
+        });
+        let breaker = thread::spawn(move || {
+            let mut connection = Connection::connect(address).unwrap();
+            let (max_round, _, token) = join(&mut connection, Role::Breaker, None).unwrap();
+            play_breaker(&mut connection, &mut Solver::new(), max_round, token).unwrap()
+        });
+
+        // A minimal server: the clients may connect in any order.
+        let mut players: Vec<(Role, Connection)> = (0..2)
+            .map(|_| {
+                let mut connection = Connection::new(listener.accept().unwrap().0).unwrap();
+                match connection.receive().unwrap() {
+                    Message::Join(role, None) => (role, connection),
+                    message => panic!("unexpected {}", message),
+                }
+            })
+            .collect();
+        players.sort_by_key(|(role, _)| *role == Role::Breaker);
+        let (_, mut maker_connection) = players.remove(0);
+        let (_, mut breaker_connection) = players.remove(0);
+        maker_connection
+            .send(&Message::Start(
+                NonZeroUsize::new(10).unwrap(),
+                SessionId(0),
+                Token::random(),
+            ))
+            .unwrap();
+        breaker_connection
+            .send(&Message::Start(
+                NonZeroUsize::new(10).unwrap(),
+                SessionId(0),
+                Token::random(),
+            ))
+            .unwrap();
+
+        let code_maker = RemoteCodeMaker::new(maker_connection);
+        let commitment = code_maker.commit().unwrap();
+        assert_eq!(commitment.code(), secret);
+        breaker_connection
+            .send(&Message::Commit(commitment.digest()))
+            .unwrap();
+        let mut code_breaker = RemoteCodeBreaker::new(breaker_connection);
+        Game::new(
+            NonZeroUsize::new(10).unwrap(),
+            &code_maker,
+            &mut code_breaker,
+        )
+        .play();
+        let outcome = match code_breaker.won_in() {
+            Some(rounds) => Outcome::Won(rounds),
+            None => Outcome::Lost(code_maker.code().unwrap()),
+        };
+        code_maker
+            .into_connection()
+            .send(&Message::Result(outcome))
+            .unwrap();
+        let mut breaker_connection = code_breaker.into_connection();
+        breaker_connection.send(&Message::Result(outcome)).unwrap();
+        breaker_connection
+            .send(&Message::Reveal(commitment.code(), commitment.salt()))
+            .unwrap();
+
+        assert_eq!(maker.join().unwrap(), outcome);
+        assert_eq!(breaker.join().unwrap(), outcome);
+        assert!(matches!(outcome, Outcome::Won(_)));
+    }
+
+    fn pair() -> (Connection, Connection) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = Connection::connect(listener.local_addr().unwrap()).unwrap();
+        let server = listener.accept().unwrap().0;
+        server
+            .set_read_timeout(Some(Duration::from_millis(100)))
+            .unwrap();
+        (client, Connection::new(server).unwrap())
+    }
+
+    #[test]
+    fn retries_invalid_guesses() {
+        let (mut client, server) = pair();
+        let code_breaker = RemoteCodeBreaker::with_retries(server, 1);
+        client.transport.send_line("GUESS ABCX").unwrap();
+        client
+            .send(&Message::Guess("ABCD".parse().unwrap()))
+            .unwrap();
+        assert_eq!(code_breaker.guess_code(), "ABCD".parse().unwrap());
+        assert!(matches!(client.receive(), Err(Error::Protocol(_))));
+    }
+
+    #[test]
+    fn limits_messages() {
+        let (mut client, mut server) = pair();
+        let long = "A".repeat(MAX_MESSAGE_LEN + 1);
+        client.transport.send_line(&long).unwrap();
+        assert!(matches!(server.receive(), Err(Error::Protocol(_))));
+
+        let (mut client, server) = pair();
+        let mut code_breaker =
+            RemoteCodeBreaker::with_retries(server, 1).with_rate_limit(Quota::per_minute(1));
+        for _ in 0..3 {
+            client
+                .send(&Message::Guess("ABCD".parse().unwrap()))
+                .unwrap();
+        }
+        assert_eq!(code_breaker.guess_code(), "ABCD".parse().unwrap());
+        assert!(!code_breaker.forfeited());
+        code_breaker.guess_code();
+        assert!(code_breaker.forfeited());
+        assert!(matches!(
+            code_breaker.take_error(),
+            Some(Error::Protocol(_))
+        ));
+        let limited = client.receive().unwrap_err().to_string();
+        assert!(limited.contains("too many requests"), "{}", limited);
+    }
+
+    #[test]
+    fn resigns() {
+        let (mut client, server) = pair();
+        let tokens = Arc::new(Mutex::new(Tokens::new()));
+        let maker = tokens.lock().unwrap().issue(SessionId(0), Role::Maker);
+        let breaker = tokens.lock().unwrap().issue(SessionId(0), Role::Breaker);
+        let code_breaker =
+            RemoteCodeBreaker::with_retries(server, 1).with_tokens(tokens, SessionId(0));
+        client.send(&Message::Resign(maker)).unwrap();
+        client.send(&Message::Resign(breaker)).unwrap();
+        code_breaker.guess_code();
+        assert!(code_breaker.forfeited());
+        assert!(matches!(client.receive(), Err(Error::Protocol(_))));
+    }
+
+    #[test]
+    fn forfeits_when_disconnected() {
+        let (client, server) = pair();
+        let mut code_breaker = RemoteCodeBreaker::new(server);
+        drop(client);
+        code_breaker.guess_code();
+        assert!(code_breaker.forfeited());
+        assert!(matches!(code_breaker.take_error(), Some(Error::Closed)));
+
+        let (client, server) = pair();
+        let mut code_maker = RemoteCodeMaker::new(server);
+        drop(client);
+        assert_eq!(code_maker.make_code(), "AAAA".parse().unwrap());
+        assert!(code_maker.take_error().is_some());
+        assert_eq!(code_maker.code(), None);
+    }
+
+    #[test]
+    fn resumes() {
+        let (client, server) = pair();
+        let (rejoins, connections) = mpsc::channel();
+        let resumption = Resumption {
+            role: Role::Breaker,
+            max_round: NonZeroUsize::new(10).unwrap(),
+            move_time: Some(Duration::from_secs(30)),
+            digest: None,
+            rounds: vec![("AABB".parse().unwrap(), Score::from_counts(1, 1).unwrap())],
+        };
+        let state = resumption.clone();
+        let rejoin = Rejoin::new(connections, Duration::from_secs(5), move || state.clone());
+        let code_breaker = RemoteCodeBreaker::new(server).with_rejoin(rejoin);
+
+        drop(client);
+        let (mut client, server) = pair();
+        rejoins.send(server).unwrap();
+        client
+            .send(&Message::Guess("ABCD".parse().unwrap()))
+            .unwrap();
+        assert_eq!(code_breaker.guess_code(), "ABCD".parse().unwrap());
+        assert_eq!(client.receive().unwrap(), Message::Resumed(resumption));
+    }
+
+    #[test]
+    fn detects_cheating() {
+        let (mut client, mut server) = pair();
+        let breaker = thread::spawn(move || {
+            let mut solver = Solver::new();
+            play_breaker(
+                &mut client,
+                &mut solver,
+                NonZeroUsize::new(1).unwrap(),
+                Token::random(),
+            )
+        });
+        let commitment = Commitment::new("ABCD".parse().unwrap());
+        server.send(&Message::Commit(commitment.digest())).unwrap();
+        assert!(matches!(server.receive(), Ok(Message::Guess(_))));
+        // A score made up for the first guess, then a code which fits it.
+        let score = Score::from_counts(0, 0).unwrap();
+        let lost = Outcome::Lost("FFFF".parse().unwrap());
+        server.send(&Message::Score(score)).unwrap();
+        server.send(&Message::Result(lost)).unwrap();
+        server
+            .send(&Message::Reveal("FFFF".parse().unwrap(), commitment.salt()))
+            .unwrap();
+        assert!(matches!(breaker.join().unwrap(), Err(Error::Protocol(_))));
+    }
+
+    #[test]
+    fn times_out() {
+        let (_client, mut server) = pair();
+        assert!(matches!(server.receive(), Err(Error::Timeout)));
+    }
+
+    #[cfg(feature = "ws")]
+    #[test]
+    fn websocket() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            let mut connection =
+                Connection::connect_websocket(&format!("ws://{}", address)).unwrap();
+            connection
+                .send(&Message::Join(Role::Breaker, None))
+                .unwrap();
+            connection.receive().unwrap()
+        });
+
+        let mut connection = Connection::accept_websocket(listener.accept().unwrap().0).unwrap();
+        assert_eq!(
+            connection.receive().unwrap(),
+            Message::Join(Role::Breaker, None)
+        );
+        let start = Message::Start(
+            NonZeroUsize::new(10).unwrap(),
+            SessionId(0),
+            Token::random(),
+        );
+        connection.send(&start).unwrap();
+        assert_eq!(client.join().unwrap(), start);
+    }
+}
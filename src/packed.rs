@@ -0,0 +1,71 @@
+//! Codes packed in machine words, scored with a few bitwise operations, for
+//! the solver to weigh many guesses (see `solver::Backend`).
+//!
+//! A `PackedCode` keeps each peg in a nibble of `pegs`, and counts its colors
+//! in unary in `colors`: the nibble of each color has as many low bits set
+//! as the code has pegs of that color. The pegs two codes have in common are
+//! then the bits set in both their `colors`, whatever their position.
+
+use crate::{Code, CodePeg, Score, SIZE};
+
+/// The lowest bit of each peg's nibble.
+const LOW_BITS: u32 = 0x1111;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PackedCode {
+    pegs: u32,
+    colors: u32,
+}
+
+impl PackedCode {
+    pub fn code(&self) -> Code {
+        let mut pegs = [CodePeg::A; SIZE];
+        for (i, peg) in pegs.iter_mut().enumerate() {
+            *peg = CodePeg::ALL[(self.pegs >> (4 * i) & 0xf) as usize];
+        }
+        Code::new(pegs)
+    }
+
+    /// The score of `guess` if `self` is the code, like `Scorer::score`.
+    pub fn score(&self, guess: PackedCode) -> Score {
+        // A nibble is zero where the pegs match. Colors fit in three bits,
+        // which are folded onto the lowest one.
+        let diff = self.pegs ^ guess.pegs;
+        let diff = diff | diff >> 1 | diff >> 2;
+        let matches = SIZE - (diff & LOW_BITS).count_ones() as usize;
+        let common = (self.colors & guess.colors).count_ones() as usize;
+        Score::from_index(matches * (SIZE + 1) + common - matches)
+    }
+}
+
+impl From<Code> for PackedCode {
+    fn from(code: Code) -> Self {
+        let mut packed = PackedCode { pegs: 0, colors: 0 };
+        for (i, peg) in code.pegs().into_iter().enumerate() {
+            packed.pegs |= (peg as u32) << (4 * i);
+            // The next unset bit of the color's nibble.
+            let nibble = packed.colors >> (4 * peg as u32) & 0xf;
+            packed.colors |= (nibble + 1) << (4 * peg as u32);
+        }
+        packed
+    }
+}
+
+#[cfg(test)]
+mod test_packed {
+    use super::*;
+    use crate::Scorer;
+
+    #[test]
+    fn scores_like_the_scorer() {
+        let codes = Code::all();
+        let packed: Vec<PackedCode> = codes.iter().map(|&code| code.into()).collect();
+        for (&code, packed_code) in codes.iter().zip(&packed) {
+            assert_eq!(packed_code.code(), code);
+            let scorer = Scorer::new(code);
+            for (&guess, &packed_guess) in codes.iter().zip(&packed) {
+                assert_eq!(packed_code.score(packed_guess), scorer.score(guess));
+            }
+        }
+    }
+}
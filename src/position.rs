@@ -0,0 +1,214 @@
+//! Games in progress written on a single line, e.g. `6d/10/AABB10/CCDE02`,
+//! to paste them in chats, URLs and bug reports.
+//!
+//! A position is what the code breaker knows: the rules, the number of
+//! rounds and the rounds played, but not the code.
+
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+
+use crate::rules::{Rules, RulesError};
+use crate::{Code, Score, SIZE};
+
+/// A game seen by its code breaker.
+///
+/// It is written as the rules, the number of rounds and each round,
+/// separated by slashes. The rules are the number of colors, then `d` if
+/// codes may repeat them or `u` if not, then `s` if the rules are strict.
+/// Each round is the guess followed by the numbers of matched and present
+/// pegs. `4us/8/ABCD21` is a game of 8 rounds under strict rules with 4
+/// colors which do not repeat, in which ABCD got 2 matches and 1 present.
+/// The number of rounds is `-` in a game which goes on until the code is
+/// broken.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Position {
+    pub rules: Rules,
+    /// `None` if the game goes on until the code is broken.
+    pub max_round: Option<NonZeroUsize>,
+    /// The guesses played so far, with their scores.
+    pub rounds: Vec<(Code, Score)>,
+}
+
+impl Position {
+    pub fn encode(&self) -> String {
+        self.to_string()
+    }
+
+    /// Reads a position, checking that its guesses are allowed by its rules
+    /// and that it could be reached in a game.
+    pub fn decode(s: &str) -> Result<Self, ParsePositionError> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let duplicates = if self.rules.duplicates() { "d" } else { "u" };
+        let strict = if self.rules.strict() { "s" } else { "" };
+        write!(f, "{}{}{}/", self.rules.colors(), duplicates, strict)?;
+        match self.max_round {
+            Some(max_round) => write!(f, "{}", max_round)?,
+            None => write!(f, "-")?,
+        }
+        for (guess, score) in &self.rounds {
+            write!(f, "/{}{}{}", guess, score.matches(), score.presents())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ParsePositionError {
+    /// The part of the position which cannot be read.
+    Invalid { position: String, part: String },
+    /// The rules are not supported, or do not allow a guess.
+    Rules(RulesError),
+    /// The round comes after the code was broken or the last round.
+    AfterEnd(String),
+}
+
+impl fmt::Display for ParsePositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParsePositionError::Invalid { position, part } => {
+                write!(f, "invalid position '{}' at '{}'", position, part)
+            }
+            ParsePositionError::Rules(err) => write!(f, "{}", err),
+            ParsePositionError::AfterEnd(round) => {
+                write!(f, "round '{}' is played after the end", round)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParsePositionError {}
+
+impl FromStr for Position {
+    type Err = ParsePositionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |part: &str| ParsePositionError::Invalid {
+            position: s.trim().to_string(),
+            part: part.to_string(),
+        };
+        let mut parts = s.trim().split('/');
+        let rules = parts.next().unwrap_or_default();
+        let (colors, flags) = rules.split_at(
+            rules
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rules.len()),
+        );
+        let colors = colors.parse().map_err(|_| invalid(rules))?;
+        let (duplicates, strict) = match flags {
+            "d" => (true, false),
+            "u" => (false, false),
+            "ds" => (true, true),
+            "us" => (false, true),
+            _ => return Err(invalid(rules)),
+        };
+        let rules = Rules::new(colors, duplicates)
+            .map_err(ParsePositionError::Rules)?
+            .with_strict(strict);
+        let max_round = parts.next().unwrap_or_default();
+        let max_round = match max_round {
+            "-" => None,
+            _ => Some(max_round.parse().map_err(|_| invalid(max_round))?),
+        };
+
+        let mut rounds: Vec<(Code, Score)> = Vec::new();
+        for round in parts {
+            if max_round.map(NonZeroUsize::get) == Some(rounds.len())
+                || rounds.last().is_some_and(|(_, s)| s.matches() == SIZE)
+            {
+                return Err(ParsePositionError::AfterEnd(round.to_string()));
+            }
+            let (guess, counts) = round.split_at_checked(SIZE).ok_or_else(|| invalid(round))?;
+            let guess: Code = guess.parse().map_err(|_| invalid(round))?;
+            let counts: Vec<usize> = counts
+                .chars()
+                .map(|c| c.to_digit(10).map(|n| n as usize))
+                .collect::<Option<_>>()
+                .ok_or_else(|| invalid(round))?;
+            let score = match counts[..] {
+                [matches, presents] => Score::from_counts(matches, presents),
+                _ => None,
+            }
+            .ok_or_else(|| invalid(round))?;
+            rules
+                .check_guess(guess, rounds.iter().map(|&(played, _)| played))
+                .map_err(ParsePositionError::Rules)?;
+            rounds.push((guess, score));
+        }
+        Ok(Position {
+            rules,
+            max_round,
+            rounds,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_position {
+    use super::*;
+    use crate::session::GameSession;
+
+    #[test]
+    fn round_trips() {
+        let max_round = NonZeroUsize::new(10).unwrap();
+        let mut session =
+            GameSession::new(Rules::default(), max_round, "CCDE".parse().unwrap()).unwrap();
+        assert_eq!(session.position().encode(), "6d/10");
+        session.guess("AABB".parse().unwrap()).unwrap();
+        session.guess("CCDF".parse().unwrap()).unwrap();
+        let position = session.position();
+        assert_eq!(position.encode(), "6d/10/AABB00/CCDF30");
+        assert_eq!(Position::decode(&position.encode()), Ok(position));
+
+        let position = Position::decode("4us/8/ABCD21").unwrap();
+        assert_eq!(
+            position.rules,
+            Rules::new(4, false).unwrap().with_strict(true)
+        );
+        assert_eq!(position.max_round, NonZeroUsize::new(8));
+        assert_eq!(position.encode(), "4us/8/ABCD21");
+
+        let position = Position::decode("6d/-/AABB00").unwrap();
+        assert_eq!(position.max_round, None);
+        assert_eq!(position.encode(), "6d/-/AABB00");
+    }
+
+    #[test]
+    fn decode_errors() {
+        for position in [
+            "",
+            "6/10",
+            "6x/10",
+            "7d/10",
+            "6d",
+            "6d/ten",
+            "6d/0",
+            "6d/10/AABB",
+            "6d/10/AABB50",
+            "6d/10/AABB001",
+            "6d/10/AAGB00",
+            "6d/1/AABB00/CCDD00",
+            "6d/10/AABB40/CCDD00",
+            "4u/10/AABB00",
+            "6ds/10/AABB00/AABB00",
+        ] {
+            assert!(Position::decode(position).is_err(), "{}", position);
+        }
+        assert_eq!(
+            Position::decode("6ds/10/AABB00/AABB00"),
+            Err(ParsePositionError::Rules(RulesError::Repeated(
+                "AABB".parse().unwrap()
+            )))
+        );
+        assert_eq!(
+            Position::decode("6d/1/AABB00/CCDD00"),
+            Err(ParsePositionError::AfterEnd("CCDD00".to_string()))
+        );
+    }
+}
@@ -0,0 +1,390 @@
+//! A Python module, with the `python` feature, to run simulations from
+//! notebooks. Build it with `maturin develop`, as set up in
+//! `pyproject.toml`.
+//!
+//! Codes can be passed as `Code`s or as strings such as `"ABCD"`, and
+//! invalid arguments raise `ValueError`.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::rules::Rules;
+use crate::solver::{self, Strategy};
+use crate::{session, CodeBreaker, CodeMaker, RandomCodeMaker, Scorer};
+
+fn value_error(err: impl fmt::Display) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+fn rules(colors: usize, duplicates: bool) -> PyResult<Rules> {
+    Rules::new(colors, duplicates).map_err(value_error)
+}
+
+fn max_round(max_round: usize) -> PyResult<NonZeroUsize> {
+    NonZeroUsize::new(max_round).ok_or_else(|| value_error("a game needs at least one round"))
+}
+
+fn strategy(name: &str) -> PyResult<Strategy> {
+    match name {
+        "first-candidate" => Ok(Strategy::FirstCandidate),
+        "minimax" => Ok(Strategy::Minimax),
+        _ => Err(value_error(format!("unknown strategy '{}'", name))),
+    }
+}
+
+fn strategy_name(strategy: Strategy) -> &'static str {
+    match strategy {
+        Strategy::FirstCandidate => "first-candidate",
+        Strategy::Minimax => "minimax",
+    }
+}
+
+#[pyclass(module = "mastermind", frozen, eq)]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Code {
+    code: crate::Code,
+}
+
+impl From<crate::Code> for Code {
+    fn from(code: crate::Code) -> Self {
+        Code { code }
+    }
+}
+
+/// A `Code`, or a string to parse as one.
+#[derive(FromPyObject)]
+pub enum AnyCode {
+    Code(Code),
+    Str(String),
+}
+
+impl AnyCode {
+    fn code(self) -> PyResult<crate::Code> {
+        match self {
+            AnyCode::Code(code) => Ok(code.code),
+            AnyCode::Str(s) => s.parse().map_err(value_error),
+        }
+    }
+}
+
+#[pymethods]
+impl Code {
+    #[new]
+    fn new(code: &str) -> PyResult<Self> {
+        Ok(code.parse::<crate::Code>().map_err(value_error)?.into())
+    }
+
+    /// A random code allowed by the rules.
+    #[staticmethod]
+    #[pyo3(signature = (colors = 6, duplicates = true))]
+    fn random(colors: usize, duplicates: bool) -> PyResult<Self> {
+        let maker = RandomCodeMaker::new(rules(colors, duplicates)?);
+        Ok(maker.make_code().into())
+    }
+
+    /// All the codes allowed by the rules, in lexicographic order.
+    #[staticmethod]
+    #[pyo3(signature = (colors = 6, duplicates = true))]
+    fn all(colors: usize, duplicates: bool) -> PyResult<Vec<Self>> {
+        let rules = rules(colors, duplicates)?;
+        let codes = crate::Code::all().into_iter();
+        Ok(codes
+            .filter(|&code| rules.check(code).is_ok())
+            .map(Code::from)
+            .collect())
+    }
+
+    fn __str__(&self) -> String {
+        self.code.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Code('{}')", self.code)
+    }
+
+    fn __hash__(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.code.to_string().hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[pyclass(module = "mastermind", frozen, eq, hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Score {
+    score: crate::Score,
+}
+
+impl From<crate::Score> for Score {
+    fn from(score: crate::Score) -> Self {
+        Score { score }
+    }
+}
+
+#[pymethods]
+impl Score {
+    #[new]
+    fn new(matched: usize, present: usize) -> PyResult<Self> {
+        let score = crate::Score::from_counts(matched, present)
+            .ok_or_else(|| value_error("invalid score"))?;
+        Ok(score.into())
+    }
+
+    /// Pegs of the right color in the right place.
+    #[getter]
+    fn matched(&self) -> usize {
+        self.score.matches()
+    }
+
+    /// Pegs of the right color in the wrong place.
+    #[getter]
+    fn present(&self) -> usize {
+        self.score.presents()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Score(matched={}, present={})",
+            self.matched(),
+            self.present()
+        )
+    }
+}
+
+/// The score of `guess` against `secret`.
+#[pyfunction]
+fn score(secret: AnyCode, guess: AnyCode) -> PyResult<Score> {
+    Ok(Scorer::new(secret.code()?).score(guess.code()?).into())
+}
+
+/// A game against a code, random unless given, played one guess at a time.
+/// `seed` draws the random code, so that the same seed hides the same code,
+/// and `strict` rules reject a guess played twice.
+#[pyclass(module = "mastermind")]
+pub struct GameSession {
+    session: session::GameSession,
+}
+
+#[pymethods]
+impl GameSession {
+    #[new]
+    #[pyo3(signature = (colors = 6, duplicates = true, max_round = 10, secret = None, seed = None, strict = false))]
+    fn new(
+        colors: usize,
+        duplicates: bool,
+        max_round: usize,
+        secret: Option<AnyCode>,
+        seed: Option<u64>,
+        strict: bool,
+    ) -> PyResult<Self> {
+        let rules = rules(colors, duplicates)?.with_strict(strict);
+        let max_round = self::max_round(max_round)?;
+        let session = match secret {
+            Some(secret) => {
+                let secret = secret.code()?;
+                session::GameSession::new(rules, max_round, secret).map_err(value_error)?
+            }
+            None => match seed {
+                Some(seed) => session::GameSession::seeded(rules, max_round, seed),
+                None => session::GameSession::random(rules, max_round),
+            },
+        };
+        Ok(GameSession { session })
+    }
+
+    /// `None` if the game goes on until the code is broken.
+    #[getter]
+    fn max_round(&self) -> Option<usize> {
+        self.session.max_round().map(NonZeroUsize::get)
+    }
+
+    #[getter]
+    fn colors(&self) -> usize {
+        self.session.rules().colors()
+    }
+
+    #[getter]
+    fn duplicates(&self) -> bool {
+        self.session.rules().duplicates()
+    }
+
+    /// Whether a guess played twice is rejected.
+    #[getter]
+    fn strict(&self) -> bool {
+        self.session.rules().strict()
+    }
+
+    /// `"playing"`, `"won"` or `"lost"`.
+    #[getter]
+    fn state(&self) -> &'static str {
+        match self.session.state() {
+            session::State::Playing => "playing",
+            session::State::Won => "won",
+            session::State::Lost => "lost",
+        }
+    }
+
+    /// The guesses played so far, with their scores.
+    #[getter]
+    fn rounds(&self) -> Vec<(Code, Score)> {
+        let rounds = self.session.rounds().iter();
+        rounds
+            .map(|&(guess, score)| (guess.into(), score.into()))
+            .collect()
+    }
+
+    /// The code, once the game is over.
+    #[getter]
+    fn secret(&self) -> Option<Code> {
+        self.session.secret().map(Code::from)
+    }
+
+    /// Plays `guess` and returns its score.
+    fn guess(&mut self, guess: AnyCode) -> PyResult<Score> {
+        let score = self.session.guess(guess.code()?).map_err(value_error)?;
+        Ok(score.into())
+    }
+}
+
+/// A solver, which breaks codes chosen elsewhere.
+#[pyclass(module = "mastermind")]
+pub struct Solver {
+    solver: solver::Solver,
+}
+
+#[pymethods]
+impl Solver {
+    #[new]
+    #[pyo3(signature = (colors = 6, duplicates = true, strategy = "first-candidate"))]
+    fn new(colors: usize, duplicates: bool, strategy: &str) -> PyResult<Self> {
+        Ok(Solver {
+            solver: solver::Solver::with_rules(
+                rules(colors, duplicates)?,
+                self::strategy(strategy)?,
+            ),
+        })
+    }
+
+    #[getter]
+    fn strategy(&self) -> &'static str {
+        strategy_name(self.solver.strategy())
+    }
+
+    /// The codes still possible.
+    #[getter]
+    fn candidates(&self) -> Vec<Code> {
+        let candidates = self.solver.candidates().iter();
+        candidates.map(|code| code.into()).collect()
+    }
+
+    /// Keeps the codes which would give `score` to `guess`.
+    fn record(&mut self, guess: AnyCode, score: Score) -> PyResult<()> {
+        self.solver.record(guess.code()?, score.score);
+        Ok(())
+    }
+
+    /// The code to play next, or `None` if the scores contradict each
+    /// other.
+    fn guess(&self) -> Option<Code> {
+        self.solver.guess().map(Code::from)
+    }
+}
+
+/// Lets a solver with `strategy` break `secret`, and returns the rounds it
+/// played.
+#[pyfunction]
+#[pyo3(signature = (secret, strategy = "first-candidate", colors = 6, duplicates = true, max_round = 10))]
+fn play(
+    secret: AnyCode,
+    strategy: &str,
+    colors: usize,
+    duplicates: bool,
+    max_round: usize,
+) -> PyResult<Vec<(Code, Score)>> {
+    let mut solver = Solver::new(colors, duplicates, strategy)?;
+    let mut session = GameSession::new(colors, duplicates, max_round, Some(secret), None, false)?;
+    while session.session.state() == session::State::Playing {
+        let guess = solver.solver.guess_code();
+        let score = session.session.guess(guess).map_err(value_error)?;
+        solver.solver.record(guess, score);
+    }
+    Ok(session.rounds())
+}
+
+#[pymodule]
+fn mastermind(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Code>()?;
+    m.add_class::<Score>()?;
+    m.add_class::<GameSession>()?;
+    m.add_class::<Solver>()?;
+    m.add_function(wrap_pyfunction!(score, m)?)?;
+    m.add_function(wrap_pyfunction!(play, m)?)?;
+    m.add("STRATEGIES", Strategy::ALL.map(strategy_name).to_vec())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_python {
+    use pyo3::ffi::c_str;
+    use pyo3::types::IntoPyDict;
+
+    use super::*;
+
+    #[test]
+    fn plays() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let mastermind = pyo3::wrap_pymodule!(mastermind)(py).into_bound(py);
+            let modules = py.import("sys")?.getattr("modules")?;
+            modules.set_item("mastermind", &mastermind)?;
+            let globals = [("mastermind", mastermind)].into_py_dict(py)?;
+            py.run(
+                c_str!(
+                    r#"
+from mastermind import Code, GameSession, Score, Solver, play, score
+
+assert score("ABCD", Code("AFBC")) == Score(1, 2)
+assert repr(Score(1, 2)) == "Score(matched=1, present=2)"
+assert {Code("ABCD"), Code("ABCD")} == {Code("ABCD")}
+assert len(Code.all(colors=4, duplicates=False)) == 24
+
+game = GameSession(colors=1, max_round=3, strict=True)
+assert game.strict
+assert game.max_round == 3
+assert game.guess("AAAA").matched == 4
+assert game.state == "won"
+assert str(game.secret) == "AAAA"
+try:
+    game.guess("AAAA")
+    assert False
+except ValueError:
+    pass
+try:
+    GameSession(max_round=0)
+    assert False
+except ValueError:
+    pass
+
+solver = Solver(strategy="minimax")
+solver.record("AABB", Score(0, 0))
+assert len(solver.candidates) == 256
+assert solver.guess() is not None
+
+rounds = play("ABCD", strategy="minimax")
+assert rounds[-1] == (Code("ABCD"), Score(4, 0))
+assert len(rounds) <= 5
+assert mastermind.STRATEGIES == ["first-candidate", "minimax"]
+"#
+                ),
+                Some(&globals),
+                None,
+            )
+        })
+        .unwrap();
+    }
+}
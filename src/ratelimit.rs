@@ -0,0 +1,188 @@
+//! Rate limits, for public servers not to be flooded by a single client.
+//!
+//! Each key, such as the address of a client or the id of a game, has a
+//! bucket of requests which refills over time. Like `Clock`, the limiter is
+//! given the time rather than reading it.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+use crate::sync::{lock, Mutex};
+
+/// How often a key may make requests: `burst` at once, then one more each
+/// `period`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quota {
+    pub burst: u32,
+    pub period: Duration,
+}
+
+impl Quota {
+    /// `requests` a minute, which may all be made at once.
+    pub fn per_minute(requests: u32) -> Self {
+        Quota {
+            burst: requests.max(1),
+            period: Duration::from_secs(60) / requests.max(1),
+        }
+    }
+}
+
+/// The quotas of a public server, `None` being unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Limits {
+    /// Games, or connections, each client may start.
+    pub games: Option<Quota>,
+    /// Guesses each game may receive, counting the invalid ones.
+    pub guesses: Option<Quota>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimited {
+    /// When the next request will be allowed.
+    pub retry_after: Duration,
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "too many requests, retry in {} seconds",
+            self.retry_after.as_secs_f64().ceil()
+        )
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+/// The requests left to a key at `refilled`.
+struct Bucket {
+    left: u32,
+    refilled: Instant,
+}
+
+/// Buckets below this many are never pruned.
+const PRUNE_AT: usize = 1024;
+
+struct Buckets<K> {
+    buckets: HashMap<K, Bucket>,
+    prune_at: usize,
+}
+
+/// The buckets of the keys, which can be shared between threads.
+///
+/// Keys whose bucket is full again are forgotten now and then, so that
+/// clients passing by do not take memory forever.
+pub struct RateLimiter<K> {
+    quota: Quota,
+    buckets: Mutex<Buckets<K>>,
+}
+
+impl<K: Eq + Hash> RateLimiter<K> {
+    pub fn new(quota: Quota) -> Self {
+        RateLimiter {
+            quota,
+            buckets: Mutex::new(Buckets {
+                buckets: HashMap::new(),
+                prune_at: PRUNE_AT,
+            }),
+        }
+    }
+
+    pub fn quota(&self) -> Quota {
+        self.quota
+    }
+
+    /// Counts a request of `key` at `now`, unless it exceeds the quota.
+    pub fn check(&self, key: K, now: Instant) -> Result<(), RateLimited> {
+        let Quota { burst, period } = self.quota;
+        let mut buckets = lock(&self.buckets);
+        if buckets.buckets.len() >= buckets.prune_at {
+            buckets
+                .buckets
+                .retain(|_, bucket| refill(bucket, self.quota, now) < burst);
+            buckets.prune_at = PRUNE_AT.max(2 * buckets.buckets.len());
+        }
+        let bucket = buckets.buckets.entry(key).or_insert(Bucket {
+            left: burst,
+            refilled: now,
+        });
+        bucket.left = refill(bucket, self.quota, now);
+        if bucket.left == 0 {
+            let next = bucket.refilled + period;
+            return Err(RateLimited {
+                retry_after: next.saturating_duration_since(now),
+            });
+        }
+        if bucket.left == burst {
+            bucket.refilled = now;
+        }
+        bucket.left -= 1;
+        Ok(())
+    }
+
+    /// The number of keys remembered.
+    pub fn len(&self) -> usize {
+        lock(&self.buckets).buckets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The requests left to `bucket` at `now`, moving its refill time forward
+/// by the periods counted.
+fn refill(bucket: &mut Bucket, quota: Quota, now: Instant) -> u32 {
+    let elapsed = now.saturating_duration_since(bucket.refilled);
+    let periods = (elapsed.as_nanos() / quota.period.as_nanos().max(1)) as u32;
+    bucket.refilled += quota.period * periods;
+    bucket.left.saturating_add(periods).min(quota.burst)
+}
+
+#[cfg(test)]
+mod test_ratelimit {
+    use super::*;
+
+    #[test]
+    fn limits() {
+        let start = Instant::now();
+        let second = Duration::from_secs(1);
+        let limiter = RateLimiter::new(Quota::per_minute(3));
+        assert_eq!(limiter.quota().period, 20 * second);
+        for _ in 0..3 {
+            assert!(limiter.check("a", start).is_ok());
+        }
+        assert_eq!(
+            limiter.check("a", start + 5 * second),
+            Err(RateLimited {
+                retry_after: 15 * second
+            })
+        );
+        assert!(limiter.check("b", start + 5 * second).is_ok());
+
+        assert!(limiter.check("a", start + 20 * second).is_ok());
+        assert!(limiter.check("a", start + 21 * second).is_err());
+        // Waiting longer never gives more than the burst.
+        let later = start + 3600 * second;
+        for _ in 0..3 {
+            assert!(limiter.check("a", later).is_ok());
+        }
+        assert!(limiter.check("a", later).is_err());
+    }
+
+    #[test]
+    fn forgets_full_buckets() {
+        let start = Instant::now();
+        let limiter = RateLimiter::new(Quota::per_minute(60));
+        for key in 0..PRUNE_AT {
+            limiter.check(key, start).unwrap();
+        }
+        assert_eq!(limiter.len(), PRUNE_AT);
+        limiter
+            .check(PRUNE_AT, start + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(limiter.len(), 1);
+    }
+}
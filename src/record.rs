@@ -0,0 +1,553 @@
+//! Games recorded move by move, so that a failing game can be replayed
+//! exactly, e.g. from a bug report.
+//!
+//! The code and the guesses themselves are kept, rather than the seeds or
+//! the states they came from, so a replay does not depend on the timing of
+//! the players or on how their threads were scheduled.
+
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::rules::{ParseRulesError, Rules, RulesError};
+use crate::sync::{lock, Mutex};
+use crate::{
+    Code, CodeBreaker, CodeMaker, CodePeg, Game, GameOutcome, ParseCodeError, Score, SIZE,
+};
+
+/// The rules and the moves of a game.
+///
+/// It is written one item per line, the rules, the number of rounds and
+/// the code first:
+///
+/// ```text
+/// rules 6 duplicates
+/// rounds 10
+/// code FDCA
+/// guess AABB
+/// guess CCDA
+/// forfeit
+/// ```
+///
+/// A last guess which the rules rejected is followed by a `rejected` line
+/// with the reason, e.g. `rejected: guess CCDA was already played`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub rules: Rules,
+    pub max_round: NonZeroUsize,
+    pub code: Code,
+    /// The guesses in the order they were played, including a last one
+    /// which was rejected or forfeited.
+    pub guesses: Vec<Code>,
+    /// Whether the code breaker forfeited with its last guess.
+    pub forfeited: bool,
+    /// Why the rules rejected the last guess, if they did.
+    pub rejected: Option<RulesError>,
+}
+
+/// A record plays its code again as the code maker of a replay.
+impl CodeMaker for Record {
+    fn make_code(&self) -> Code {
+        self.code
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "rules {}", self.rules)?;
+        writeln!(f, "rounds {}", self.max_round)?;
+        writeln!(f, "code {}", self.code)?;
+        for guess in &self.guesses {
+            writeln!(f, "guess {}", guess)?;
+        }
+        if self.forfeited {
+            writeln!(f, "forfeit")?;
+        }
+        if let Some(err) = self.rejected {
+            writeln!(f, "rejected: {}", err)?;
+        }
+        Ok(())
+    }
+}
+
+/// Why a record could not be read, with the number of the line at fault
+/// from 1.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ParseRecordError {
+    /// The record ends before this item of its header.
+    Missing(&'static str),
+    /// The line is not this item of the header.
+    Expected {
+        line: usize,
+        item: &'static str,
+    },
+    Rules {
+        line: usize,
+        err: ParseRulesError,
+    },
+    Rounds {
+        line: usize,
+        rounds: String,
+    },
+    Code {
+        line: usize,
+        err: ParseCodeError,
+    },
+    /// A move follows a forfeit.
+    Forfeited {
+        line: usize,
+    },
+    /// A move follows the rejection of the last guess.
+    Rejected {
+        line: usize,
+    },
+    /// A rejection comes before any guess.
+    NoGuess {
+        line: usize,
+    },
+    /// The rules allow the guess said to be rejected.
+    Allowed {
+        line: usize,
+        guess: Code,
+    },
+    /// The line is not a move.
+    Move {
+        line: usize,
+        text: String,
+    },
+}
+
+impl fmt::Display for ParseRecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseRecordError::Missing(item) => write!(f, "expected the {}", item),
+            ParseRecordError::Expected { line, item } => {
+                write!(f, "line {}: expected the {}", line, item)
+            }
+            ParseRecordError::Rules { line, err } => write!(f, "line {}: {}", line, err),
+            ParseRecordError::Rounds { line, rounds } => {
+                write!(f, "line {}: invalid number of rounds '{}'", line, rounds)
+            }
+            ParseRecordError::Code { line, err } => write!(f, "line {}: {}", line, err),
+            ParseRecordError::Forfeited { line } => {
+                write!(f, "line {}: the code breaker already forfeited", line)
+            }
+            ParseRecordError::Rejected { line } => {
+                write!(f, "line {}: the last guess was already rejected", line)
+            }
+            ParseRecordError::NoGuess { line } => write!(f, "line {}: no guess to reject", line),
+            ParseRecordError::Allowed { line, guess } => {
+                write!(f, "line {}: the rules allow guess {}", line, guess)
+            }
+            ParseRecordError::Move { line, text } => {
+                write!(f, "line {}: invalid move '{}'", line, text)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseRecordError {}
+
+impl FromStr for Record {
+    type Err = ParseRecordError;
+
+    /// Reads a record, ignoring blank lines and lines starting with `#`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+        let mut header = |item: &'static str| match lines.next() {
+            Some((line, text)) => match text.split_once(char::is_whitespace) {
+                Some((key, value)) if key == item => Ok((line, value.trim())),
+                _ => Err(ParseRecordError::Expected { line, item }),
+            },
+            None => Err(ParseRecordError::Missing(item)),
+        };
+        let (line, rules) = header("rules")?;
+        let rules = rules
+            .parse()
+            .map_err(|err| ParseRecordError::Rules { line, err })?;
+        let (line, max_round) = header("rounds")?;
+        let max_round = max_round.parse().map_err(|_| ParseRecordError::Rounds {
+            line,
+            rounds: max_round.to_string(),
+        })?;
+        let (line, code) = header("code")?;
+        let code = code
+            .parse()
+            .map_err(|err| ParseRecordError::Code { line, err })?;
+        let mut record = Record {
+            rules,
+            max_round,
+            code,
+            guesses: Vec::new(),
+            forfeited: false,
+            rejected: None,
+        };
+        for (i, line) in lines {
+            if record.forfeited {
+                return Err(ParseRecordError::Forfeited { line: i });
+            }
+            if record.rejected.is_some() {
+                return Err(ParseRecordError::Rejected { line: i });
+            }
+            // The reason is only written for the reader: it is checked
+            // against the rules again.
+            if line == "rejected" || line.starts_with("rejected:") {
+                let (&guess, played) = record
+                    .guesses
+                    .split_last()
+                    .ok_or(ParseRecordError::NoGuess { line: i })?;
+                let err = rules
+                    .check_guess(guess, played.iter().copied())
+                    .err()
+                    .ok_or(ParseRecordError::Allowed { line: i, guess })?;
+                record.rejected = Some(err);
+                continue;
+            }
+            match line.split_whitespace().collect::<Vec<_>>()[..] {
+                ["guess", guess] => record.guesses.push(
+                    guess
+                        .parse()
+                        .map_err(|err| ParseRecordError::Code { line: i, err })?,
+                ),
+                ["forfeit"] if !record.guesses.is_empty() => record.forfeited = true,
+                _ => {
+                    return Err(ParseRecordError::Move {
+                        line: i,
+                        text: line.to_string(),
+                    })
+                }
+            }
+        }
+        Ok(record)
+    }
+}
+
+#[derive(Default)]
+struct Moves {
+    code: Option<Code>,
+    guesses: Vec<Code>,
+    forfeited: bool,
+    rejected: Option<RulesError>,
+}
+
+/// Gathers the moves of the players of a game, each wrapped in a
+/// `Recording`.
+///
+/// Clones record the same game.
+#[derive(Clone)]
+pub struct Recorder {
+    rules: Rules,
+    max_round: NonZeroUsize,
+    moves: Arc<Mutex<Moves>>,
+}
+
+impl Recorder {
+    /// Records a game played under `rules`, in at most `max_round` rounds.
+    pub fn new(rules: Rules, max_round: NonZeroUsize) -> Self {
+        Recorder {
+            rules,
+            max_round,
+            moves: Arc::default(),
+        }
+    }
+
+    /// The moves so far, or `None` until the code is made.
+    pub fn record(&self) -> Option<Record> {
+        let moves = lock(&self.moves);
+        Some(Record {
+            rules: self.rules,
+            max_round: self.max_round,
+            code: moves.code?,
+            guesses: moves.guesses.clone(),
+            forfeited: moves.forfeited,
+            rejected: moves.rejected,
+        })
+    }
+}
+
+/// A code maker or code breaker whose moves are recorded.
+pub struct Recording<P> {
+    player: P,
+    recorder: Recorder,
+}
+
+impl<P> Recording<P> {
+    pub fn new(player: P, recorder: &Recorder) -> Self {
+        Recording {
+            player,
+            recorder: recorder.clone(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &P {
+        &self.player
+    }
+
+    pub fn into_inner(self) -> P {
+        self.player
+    }
+}
+
+impl<P: CodeMaker> CodeMaker for Recording<P> {
+    fn make_code(&self) -> Code {
+        let code = self.player.make_code();
+        lock(&self.recorder.moves).code = Some(code);
+        code
+    }
+}
+
+impl<P: CodeBreaker> CodeBreaker for Recording<P> {
+    fn guess_code(&self) -> Code {
+        let guess = self.player.guess_code();
+        let mut moves = lock(&self.recorder.moves);
+        if moves.rejected.is_none() {
+            let played = moves.guesses.iter().copied();
+            moves.rejected = self.recorder.rules.check_guess(guess, played).err();
+        }
+        moves.guesses.push(guess);
+        guess
+    }
+
+    fn set_score(&mut self, score: Score) {
+        self.player.set_score(score);
+    }
+
+    fn loses(&mut self) {
+        self.player.loses();
+    }
+
+    fn wins(&mut self, rounds: usize) {
+        self.player.wins(rounds);
+    }
+
+    fn forfeited(&self) -> bool {
+        let forfeited = self.player.forfeited();
+        if forfeited {
+            let mut moves = lock(&self.recorder.moves);
+            moves.forfeited = true;
+            // The game ends before checking the guess.
+            moves.rejected = None;
+        }
+        forfeited
+    }
+}
+
+/// A code breaker playing the guesses of a record again, against its code.
+///
+/// The record itself is the code maker, and the game is played under its
+/// rules and number of rounds. If the game asks for more guesses than were
+/// recorded, it forfeits.
+pub struct Replay {
+    record: Record,
+    rounds: Vec<(Code, Score)>,
+}
+
+impl Replay {
+    pub fn new(record: Record) -> Self {
+        Replay {
+            record,
+            rounds: Vec::new(),
+        }
+    }
+
+    /// The record, which plays its code as the code maker.
+    pub fn record(&self) -> &Record {
+        &self.record
+    }
+
+    /// Plays the record again, and returns how the game ended.
+    pub fn play(&mut self) -> GameOutcome {
+        let record = self.record.clone();
+        Game::new(record.max_round, &record, self)
+            .with_rules(record.rules)
+            .play()
+    }
+
+    /// The guesses replayed so far, with their scores.
+    pub fn rounds(&self) -> &[(Code, Score)] {
+        &self.rounds
+    }
+}
+
+impl CodeBreaker for Replay {
+    fn guess_code(&self) -> Code {
+        match self.record.guesses.get(self.rounds.len()) {
+            Some(&guess) => guess,
+            // Ignored by the game, since the replay forfeits.
+            None => Code::new([CodePeg::ALL[0]; SIZE]),
+        }
+    }
+
+    fn set_score(&mut self, score: Score) {
+        let guess = self.guess_code();
+        self.rounds.push((guess, score));
+    }
+
+    fn loses(&mut self) {}
+
+    fn forfeited(&self) -> bool {
+        let played = self.rounds.len() + 1;
+        played > self.record.guesses.len()
+            || (self.record.forfeited && played == self.record.guesses.len())
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test_record {
+    use super::*;
+    use crate::solver::{Solver, Strategy};
+    use crate::RandomCodeMaker;
+
+    const HEADER: &str = "rules 6 duplicates\nrounds 10\n";
+
+    #[test]
+    fn replays_games() {
+        let rules = Rules::new(6, false).unwrap();
+        let recorder = Recorder::new(rules, NonZeroUsize::new(10).unwrap());
+        assert_eq!(recorder.record(), None);
+        let code_maker = Recording::new(RandomCodeMaker::new(rules), &recorder);
+        let mut code_breaker =
+            Recording::new(Solver::with_rules(rules, Strategy::Minimax), &recorder);
+        let outcome = Game::new(
+            NonZeroUsize::new(10).unwrap(),
+            &code_maker,
+            &mut code_breaker,
+        )
+        .with_rules(rules)
+        .play();
+        let record: Record = recorder.record().unwrap().to_string().parse().unwrap();
+        assert_eq!(record.rules, rules);
+        assert!(!record.forfeited);
+        let mut replay = Replay::new(record.clone());
+        assert_eq!(replay.play(), outcome);
+        let guesses: Vec<Code> = replay.rounds().iter().map(|&(guess, _)| guess).collect();
+        assert_eq!(guesses, record.guesses);
+    }
+
+    #[test]
+    fn replays_with_the_recorded_rules() {
+        let record: Record =
+            "rules 6 duplicates strict\nrounds 2\ncode ABCD\nguess AABB\nguess AABB"
+                .parse()
+                .unwrap();
+        let outcome = Replay::new(record.clone()).play();
+        assert!(
+            matches!(outcome, GameOutcome::Rejected(..)),
+            "{:?}",
+            outcome
+        );
+
+        let record: Record =
+            "rules 6 duplicates\nrounds 2\ncode ABCD\nguess AABB\nguess AABB\nguess ABCD"
+                .parse()
+                .unwrap();
+        let mut replay = Replay::new(record);
+        assert_eq!(replay.play(), GameOutcome::Lost("ABCD".parse().unwrap()));
+        assert_eq!(replay.rounds().len(), 2);
+    }
+
+    #[test]
+    fn records_rejected_guesses() {
+        struct Repeating;
+
+        impl CodeBreaker for Repeating {
+            fn guess_code(&self) -> Code {
+                "AABB".parse().unwrap()
+            }
+
+            fn set_score(&mut self, _score: Score) {}
+
+            fn loses(&mut self) {}
+        }
+
+        let rules = Rules::default().with_strict(true);
+        let recorder = Recorder::new(rules, NonZeroUsize::new(10).unwrap());
+        let code_maker = Recording::new(RandomCodeMaker::new(rules), &recorder);
+        let mut code_breaker = Recording::new(Repeating, &recorder);
+        let outcome = Game::new(
+            NonZeroUsize::new(10).unwrap(),
+            &code_maker,
+            &mut code_breaker,
+        )
+        .with_rules(rules)
+        .play();
+        let guess = "AABB".parse().unwrap();
+        assert_eq!(
+            outcome,
+            GameOutcome::Rejected(guess, RulesError::Repeated(guess))
+        );
+
+        let record = recorder.record().unwrap();
+        assert_eq!(record.rejected, Some(RulesError::Repeated(guess)));
+        let text = record.to_string();
+        assert!(text.ends_with("guess AABB\nguess AABB\nrejected: guess AABB was already played\n"));
+        let record: Record = text.parse().unwrap();
+        assert_eq!(record.rejected, Some(RulesError::Repeated(guess)));
+        assert_eq!(Replay::new(record).play(), outcome);
+    }
+
+    #[test]
+    fn replays_forfeits() {
+        let code: Code = "ABCD".parse().unwrap();
+        let record: Record = format!(
+            "{}code ABCD\nguess AABB\n\n# resigned\nguess CCDD\nforfeit\n",
+            HEADER
+        )
+        .parse()
+        .unwrap();
+        assert!(record.forfeited);
+        let mut replay = Replay::new(record);
+        assert_eq!(replay.play(), GameOutcome::Lost(code));
+        assert_eq!(replay.rounds().len(), 1);
+
+        // Running out of guesses forfeits as well.
+        let record = format!("{}code ABCD\nguess AABB", HEADER).parse().unwrap();
+        assert_eq!(Replay::new(record).play(), GameOutcome::Lost(code));
+    }
+
+    #[test]
+    fn parse_errors() {
+        for record in [
+            "",
+            "code ABCD",
+            "rules 6 duplicates\ncode ABCD",
+            "rules 7 duplicates\nrounds 10\ncode ABCD",
+            "rules 6 duplicates\nrounds 0\ncode ABCD",
+            "rules 6 duplicates\nrounds 10",
+        ] {
+            assert!(record.parse::<Record>().is_err(), "{}", record);
+        }
+        for moves in [
+            "guess AABB",
+            "code ABCG",
+            "code ABCD\nforfeit",
+            "code ABCD\nguess AABB\nforfeit\nguess CCDD",
+            "code ABCD\nrejected",
+            "code ABCD\nguess AABB\nrejected",
+            "code ABCD\nguess AAGB\nrejected\nguess CCDD",
+        ] {
+            let record = format!("{}{}", HEADER, moves);
+            assert!(record.parse::<Record>().is_err(), "{}", record);
+        }
+        assert_eq!(
+            format!("{}code ABCD\nguess AABB\nrejected", HEADER).parse::<Record>(),
+            Err(ParseRecordError::Allowed {
+                line: 5,
+                guess: "AABB".parse().unwrap()
+            })
+        );
+        assert_eq!(
+            "rules 6 duplicates\ncode ABCD".parse::<Record>(),
+            Err(ParseRecordError::Expected {
+                line: 2,
+                item: "rounds"
+            })
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Terminal rendering of codes, scores and boards with ANSI colors.
+
+use std::time::Duration;
+
+use crate::{Code, CodePeg, Score, ScorePeg, SIZE};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+
+/// How pegs are told apart.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Theme {
+    /// Colored discs.
+    #[default]
+    Color,
+    /// A distinct shape per peg and no color, for color-blind players.
+    Symbols,
+    /// Distinct shapes in bold bright colors.
+    HighContrast,
+}
+
+/// The glyph drawn before the peg's letter.
+pub fn glyph(peg: CodePeg, theme: Theme) -> char {
+    if theme == Theme::Color {
+        return '●';
+    }
+    match peg {
+        CodePeg::A => '●',
+        CodePeg::B => '▲',
+        CodePeg::C => '■',
+        CodePeg::D => '◆',
+        CodePeg::E => '★',
+        CodePeg::F => '✚',
+    }
+}
+
+/// Index of the peg's color in the 16 colors ANSI palette, if the theme uses colors.
+pub fn color(peg: CodePeg, theme: Theme) -> Option<u8> {
+    let color = match peg {
+        CodePeg::A => 1,
+        CodePeg::B => 2,
+        CodePeg::C => 3,
+        CodePeg::D => 4,
+        CodePeg::E => 5,
+        CodePeg::F => 6,
+    };
+    match theme {
+        Theme::Color => Some(color),
+        Theme::Symbols => None,
+        Theme::HighContrast => Some(color + 8),
+    }
+}
+
+/// A glyph followed by the peg's letter, e.g. `●A`.
+pub fn peg(peg: CodePeg, theme: Theme) -> String {
+    let glyph = glyph(peg, theme);
+    let style = match color(peg, theme) {
+        Some(color) if color < 8 => format!("\x1b[3{}m", color),
+        Some(color) => format!("{}\x1b[9{}m", BOLD, color - 8),
+        None => return format!("{}{}", glyph, peg),
+    };
+    format!("{}{}{}{}", style, glyph, peg, RESET)
+}
+
+pub fn code(code: Code, theme: Theme) -> String {
+    code.pegs()
+        .iter()
+        .map(|&p| peg(p, theme))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `●` for a matched peg, `○` for a present peg and `·` for no peg.
+pub fn score_glyph(peg: Option<ScorePeg>) -> char {
+    match peg {
+        Some(ScorePeg::Match) => '●',
+        Some(ScorePeg::Present) => '○',
+        None => '·',
+    }
+}
+
+pub fn score(score: Score, theme: Theme) -> String {
+    let pegs: String = score.pegs().iter().map(|&p| score_glyph(p)).collect();
+    match theme {
+        Theme::HighContrast => format!("{}{}{}", BOLD, pegs, RESET),
+        Theme::Color | Theme::Symbols => pegs,
+    }
+}
+
+/// Draws one line per round, leaving empty lines for the rounds still to play.
+pub fn board(rounds: &[(Code, Score)], max_round: usize, theme: Theme) -> String {
+    let width = max_round.to_string().len();
+    let mut lines = Vec::with_capacity(max_round);
+    for i in 0..max_round.max(rounds.len()) {
+        let line = match rounds.get(i) {
+            Some((guess, guess_score)) => format!(
+                "{:>width$} │ {} │ {}",
+                i + 1,
+                code(*guess, theme),
+                score(*guess_score, theme),
+                width = width
+            ),
+            None => format!(
+                "{:>width$} │ {} │ {}",
+                i + 1,
+                ["··"; SIZE].join(" "),
+                "·".repeat(SIZE),
+                width = width
+            ),
+        };
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+/// A countdown as minutes and seconds, rounded up so that it only shows 0:00
+/// once the time is over.
+pub fn clock(remaining: Duration) -> String {
+    let seconds = remaining.as_millis().div_ceil(1000);
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod test_render {
+    use super::*;
+
+    #[test]
+    fn score() {
+        let score = Score::from_counts(1, 2).unwrap();
+        assert_eq!(super::score(score, Theme::Color), "●○○·");
+        assert_eq!(
+            super::score(score, Theme::HighContrast),
+            "\x1b[1m●○○·\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn peg() {
+        assert_eq!(super::peg(CodePeg::B, Theme::Color), "\x1b[32m●B\x1b[0m");
+        assert_eq!(super::peg(CodePeg::B, Theme::Symbols), "▲B");
+        assert_eq!(
+            super::peg(CodePeg::B, Theme::HighContrast),
+            "\x1b[1m\x1b[92m▲B\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn symbols_are_distinct() {
+        for (i, &a) in CodePeg::ALL.iter().enumerate() {
+            for &b in &CodePeg::ALL[i + 1..] {
+                assert_ne!(glyph(a, Theme::Symbols), glyph(b, Theme::Symbols));
+            }
+        }
+    }
+
+    #[test]
+    fn board() {
+        let guess = Code::new([CodePeg::A, CodePeg::B, CodePeg::C, CodePeg::D]);
+        let rounds = [(guess, Score::from_counts(0, 1).unwrap())];
+        let board = super::board(&rounds, 10, Theme::Color);
+        let lines: Vec<&str> = board.lines().collect();
+        assert_eq!(lines.len(), 10);
+        assert_eq!(
+            lines[0],
+            " 1 │ \x1b[31m●A\x1b[0m \x1b[32m●B\x1b[0m \x1b[33m●C\x1b[0m \x1b[34m●D\x1b[0m │ ○···"
+        );
+        assert_eq!(lines[9], "10 │ ·· ·· ·· ·· │ ····");
+    }
+
+    #[test]
+    fn clock() {
+        assert_eq!(super::clock(Duration::from_secs(125)), "2:05");
+        assert_eq!(super::clock(Duration::from_millis(100)), "0:01");
+        assert_eq!(super::clock(Duration::ZERO), "0:00");
+    }
+}
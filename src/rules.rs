@@ -0,0 +1,280 @@
+//! Variants of the game: how many colors are used and whether a code may
+//! repeat them.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Code, CodePeg, SIZE};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rules {
+    colors: usize,
+    duplicates: bool,
+    /// Whether playing a guess again breaks the rules.
+    strict: bool,
+}
+
+impl Rules {
+    /// Rules using the first `colors` pegs, which may repeat in a code only if
+    /// `duplicates` is true.
+    pub fn new(colors: usize, duplicates: bool) -> Result<Self, RulesError> {
+        if colors == 0 || colors > CodePeg::ALL.len() {
+            return Err(RulesError::Colors(colors));
+        }
+        if !duplicates && colors < SIZE {
+            return Err(RulesError::NotEnoughColors(colors));
+        }
+        Ok(Rules {
+            colors,
+            duplicates,
+            strict: false,
+        })
+    }
+
+    /// The same rules, under which playing the same guess twice breaks the
+    /// rules if `strict` is true, e.g. to catch broken solvers early.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn colors(&self) -> usize {
+        self.colors
+    }
+
+    pub fn duplicates(&self) -> bool {
+        self.duplicates
+    }
+
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+
+    /// The pegs a code may use.
+    pub fn palette(&self) -> &'static [CodePeg] {
+        &CodePeg::ALL[..self.colors]
+    }
+
+    /// Checks that `code` only uses the palette, and repeats no peg if
+    /// duplicates are not allowed.
+    pub fn check(&self, code: Code) -> Result<(), RulesError> {
+        let pegs = code.pegs();
+        if let Some(&peg) = pegs.iter().find(|peg| !self.palette().contains(peg)) {
+            return Err(RulesError::UnusedPeg(peg));
+        }
+        if !self.duplicates {
+            for (i, &peg) in pegs.iter().enumerate() {
+                if pegs[i + 1..].contains(&peg) {
+                    return Err(RulesError::Duplicate(peg));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks `guess` like `check`, and, under strict rules, that it is not
+    /// one of the guesses already `played`.
+    pub fn check_guess(
+        &self,
+        guess: Code,
+        played: impl IntoIterator<Item = Code>,
+    ) -> Result<(), RulesError> {
+        self.check(guess)?;
+        if self.strict && played.into_iter().any(|code| code == guess) {
+            return Err(RulesError::Repeated(guess));
+        }
+        Ok(())
+    }
+
+    /// All the codes allowed by the rules, in lexicographic order.
+    pub fn codes(&self) -> Vec<Code> {
+        Code::all()
+            .into_iter()
+            .filter(|&code| self.check(code).is_ok())
+            .collect()
+    }
+}
+
+impl Default for Rules {
+    /// The classic game: six colors which may repeat.
+    fn default() -> Self {
+        Rules {
+            colors: CodePeg::ALL.len(),
+            duplicates: true,
+            strict: false,
+        }
+    }
+}
+
+impl fmt::Display for Rules {
+    /// Writes the rules as their colors and whether codes may repeat them,
+    /// e.g. `6 duplicates` or `4 unique strict`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let duplicates = if self.duplicates {
+            "duplicates"
+        } else {
+            "unique"
+        };
+        write!(f, "{} {}", self.colors, duplicates)?;
+        if self.strict {
+            write!(f, " strict")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Rules {
+    type Err = ParseRulesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseRulesError::Invalid(s.trim().to_string());
+        let (colors, duplicates, strict) = match s.split_whitespace().collect::<Vec<_>>()[..] {
+            [colors, duplicates] => (colors, duplicates, false),
+            [colors, duplicates, "strict"] => (colors, duplicates, true),
+            _ => return Err(invalid()),
+        };
+        let duplicates = match duplicates {
+            "duplicates" => true,
+            "unique" => false,
+            _ => return Err(invalid()),
+        };
+        let colors = colors.parse().map_err(|_| invalid())?;
+        Ok(Rules::new(colors, duplicates)
+            .map_err(ParseRulesError::Rules)?
+            .with_strict(strict))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum RulesError {
+    /// The number of colors is not supported.
+    Colors(usize),
+    /// Too few colors to fill a code without duplicates.
+    NotEnoughColors(usize),
+    /// The number of pegs is not supported.
+    Pegs(usize),
+    /// The code uses a peg outside of the palette.
+    UnusedPeg(CodePeg),
+    /// The code repeats a peg although duplicates are not allowed.
+    Duplicate(CodePeg),
+    /// The guess was already played, under strict rules.
+    Repeated(Code),
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RulesError::Colors(colors) => write!(
+                f,
+                "{} colors are not supported, expected 1 to {}",
+                colors,
+                CodePeg::ALL.len()
+            ),
+            RulesError::NotEnoughColors(colors) => write!(
+                f,
+                "{} colors are not enough for {} pegs without duplicates",
+                colors, SIZE
+            ),
+            RulesError::Pegs(pegs) => {
+                write!(f, "{} pegs are not supported, only {}", pegs, SIZE)
+            }
+            RulesError::UnusedPeg(peg) => write!(f, "peg '{}' is not used in this game", peg),
+            RulesError::Duplicate(peg) => write!(f, "peg '{}' is repeated", peg),
+            RulesError::Repeated(guess) => write!(f, "guess {} was already played", guess),
+        }
+    }
+}
+
+impl std::error::Error for RulesError {}
+
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ParseRulesError {
+    /// The text is not `<colors> duplicates|unique [strict]`.
+    Invalid(String),
+    /// The rules read are not supported.
+    Rules(RulesError),
+}
+
+impl fmt::Display for ParseRulesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseRulesError::Invalid(rules) => write!(f, "invalid rules '{}'", rules),
+            ParseRulesError::Rules(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ParseRulesError {}
+
+#[cfg(test)]
+mod test_rules {
+    use super::*;
+
+    #[test]
+    fn validation() {
+        assert_eq!(Rules::new(0, true), Err(RulesError::Colors(0)));
+        assert_eq!(Rules::new(7, true), Err(RulesError::Colors(7)));
+        assert_eq!(Rules::new(3, false), Err(RulesError::NotEnoughColors(3)));
+        assert!(Rules::new(3, true).is_ok());
+        assert_eq!(Rules::new(6, true), Ok(Rules::default()));
+    }
+
+    #[test]
+    fn check() {
+        let rules = Rules::new(4, false).unwrap();
+        assert_eq!(rules.check("ABCD".parse().unwrap()), Ok(()));
+        assert_eq!(
+            rules.check("ABCE".parse().unwrap()),
+            Err(RulesError::UnusedPeg(CodePeg::E))
+        );
+        assert_eq!(
+            rules.check("ABCA".parse().unwrap()),
+            Err(RulesError::Duplicate(CodePeg::A))
+        );
+    }
+
+    #[test]
+    fn check_guess() {
+        let guess = "ABCD".parse().unwrap();
+        let played = ["AABB".parse().unwrap(), guess];
+        assert_eq!(Rules::default().check_guess(guess, played), Ok(()));
+        let strict = Rules::default().with_strict(true);
+        assert!(strict.strict());
+        assert_eq!(
+            strict.check_guess(guess, played),
+            Err(RulesError::Repeated(guess))
+        );
+        assert_eq!(strict.check_guess(guess, [played[0]]), Ok(()));
+    }
+
+    #[test]
+    fn text() {
+        for rules in [
+            Rules::default(),
+            Rules::new(4, false).unwrap(),
+            Rules::new(3, true).unwrap().with_strict(true),
+        ] {
+            assert_eq!(rules.to_string().parse(), Ok(rules));
+        }
+        assert_eq!(Rules::default().to_string(), "6 duplicates");
+        assert!("4 unique strict".parse::<Rules>().unwrap().strict());
+        assert_eq!(
+            "3 unique".parse::<Rules>(),
+            Err(ParseRulesError::Rules(RulesError::NotEnoughColors(3)))
+        );
+        assert_eq!(
+            "6 duplicates lenient".parse::<Rules>(),
+            Err(ParseRulesError::Invalid("6 duplicates lenient".to_string()))
+        );
+        assert!("six duplicates".parse::<Rules>().is_err());
+    }
+
+    #[test]
+    fn codes() {
+        assert_eq!(Rules::default().codes(), Code::all());
+        assert_eq!(Rules::new(6, false).unwrap().codes().len(), 360);
+        assert_eq!(Rules::new(3, true).unwrap().codes().len(), 81);
+    }
+}
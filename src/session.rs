@@ -0,0 +1,278 @@
+//! Games played one guess at a time, for front ends which cannot hand the
+//! control over to `Game`, such as servers answering requests.
+
+use std::fmt;
+use std::num::NonZeroUsize;
+
+use crate::position::Position;
+use crate::rules::{Rules, RulesError};
+#[cfg(feature = "metrics")]
+use crate::telemetry;
+use crate::{Code, CodeMaker, RandomCodeMaker, Score, Scorer, SIZE};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum State {
+    Playing,
+    Won,
+    Lost,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum GuessError {
+    /// The game is already over.
+    Over,
+    /// The guess is not allowed by the rules of the game.
+    Rules(RulesError),
+}
+
+impl fmt::Display for GuessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GuessError::Over => write!(f, "the game is over"),
+            GuessError::Rules(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for GuessError {}
+
+pub struct GameSession {
+    rules: Rules,
+    /// `None` if the game goes on until the code is broken.
+    max_round: Option<NonZeroUsize>,
+    secret: Code,
+    rounds: Vec<(Code, Score)>,
+    violations: Vec<(Code, RulesError)>,
+    /// When the last guess was played, or the game started.
+    #[cfg(feature = "metrics")]
+    last_move: std::time::Instant,
+}
+
+impl GameSession {
+    /// A game of at most `max_round` rounds against `secret`, or an error if
+    /// the rules do not allow it.
+    pub fn new(rules: Rules, max_round: NonZeroUsize, secret: Code) -> Result<Self, RulesError> {
+        rules.check(secret)?;
+        Ok(GameSession::allowed(rules, Some(max_round), secret))
+    }
+
+    /// A game against `secret` which goes on until the code is broken, or an
+    /// error if the rules do not allow it.
+    pub fn unlimited(rules: Rules, secret: Code) -> Result<Self, RulesError> {
+        rules.check(secret)?;
+        Ok(GameSession::allowed(rules, None, secret))
+    }
+
+    /// A game against a random code.
+    pub fn random(rules: Rules, max_round: NonZeroUsize) -> Self {
+        let secret = RandomCodeMaker::new(rules).make_code();
+        GameSession::allowed(rules, Some(max_round), secret)
+    }
+
+    /// A game against a random code drawn from `seed`, so that the same seed
+    /// hides the same code.
+    pub fn seeded(rules: Rules, max_round: NonZeroUsize, seed: u64) -> Self {
+        let secret = RandomCodeMaker::seeded(rules, seed).make_code();
+        GameSession::allowed(rules, Some(max_round), secret)
+    }
+
+    /// A game against `secret`, which the rules allow.
+    fn allowed(rules: Rules, max_round: Option<NonZeroUsize>, secret: Code) -> Self {
+        #[cfg(feature = "metrics")]
+        telemetry::game_started();
+        GameSession {
+            rules,
+            max_round,
+            secret,
+            rounds: Vec::new(),
+            violations: Vec::new(),
+            #[cfg(feature = "metrics")]
+            last_move: std::time::Instant::now(),
+        }
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// The number of rounds, or `None` if the game goes on until the code is
+    /// broken.
+    pub fn max_round(&self) -> Option<NonZeroUsize> {
+        self.max_round
+    }
+
+    /// The guesses played so far, with their scores.
+    pub fn rounds(&self) -> &[(Code, Score)] {
+        &self.rounds
+    }
+
+    /// The guesses which the rules rejected, with the reason, in the order
+    /// they were played.
+    pub fn violations(&self) -> &[(Code, RulesError)] {
+        &self.violations
+    }
+
+    /// The game as its code breaker sees it, to write it on one line.
+    pub fn position(&self) -> Position {
+        Position {
+            rules: self.rules,
+            max_round: self.max_round,
+            rounds: self.rounds.clone(),
+        }
+    }
+
+    pub fn state(&self) -> State {
+        match self.rounds.last() {
+            Some((_, score)) if score.matches() == SIZE => State::Won,
+            _ if self.max_round.map(NonZeroUsize::get) == Some(self.rounds.len()) => State::Lost,
+            _ => State::Playing,
+        }
+    }
+
+    /// The code, once the game is over.
+    pub fn secret(&self) -> Option<Code> {
+        (self.state() != State::Playing).then_some(self.secret)
+    }
+
+    /// The code, even if the game is not over, for players who give up.
+    pub fn reveal(&self) -> Code {
+        self.secret
+    }
+
+    /// Plays `guess` and returns its score.
+    pub fn guess(&mut self, guess: Code) -> Result<Score, GuessError> {
+        if self.state() != State::Playing {
+            return Err(GuessError::Over);
+        }
+        if let Err(err) = self
+            .rules
+            .check_guess(guess, self.rounds.iter().map(|&(played, _)| played))
+        {
+            self.violations.push((guess, err));
+            return Err(GuessError::Rules(err));
+        }
+        let score = Scorer::new(self.secret).score(guess);
+        self.rounds.push((guess, score));
+        #[cfg(feature = "metrics")]
+        {
+            telemetry::moved(self.last_move.elapsed());
+            self.last_move = std::time::Instant::now();
+            match self.state() {
+                State::Playing => {}
+                State::Won => telemetry::game_finished("won", Some(self.rounds.len())),
+                State::Lost => telemetry::game_finished("lost", Some(self.rounds.len())),
+            }
+        }
+        Ok(score)
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for GameSession {
+    fn drop(&mut self) {
+        if self.state() == State::Playing {
+            telemetry::game_finished("abandoned", None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_session {
+    use super::*;
+    use crate::CodePeg;
+
+    fn code(s: &str) -> Code {
+        s.parse().unwrap()
+    }
+
+    fn rounds(max_round: usize) -> NonZeroUsize {
+        NonZeroUsize::new(max_round).unwrap()
+    }
+
+    #[test]
+    fn wins() {
+        let mut session = GameSession::new(Rules::default(), rounds(10), code("ABCD")).unwrap();
+        assert_eq!(
+            session.guess(code("AABB")),
+            Ok(Score::from_counts(1, 1).unwrap())
+        );
+        assert_eq!(session.state(), State::Playing);
+        assert_eq!(session.secret(), None);
+
+        session.guess(code("ABCD")).unwrap();
+        assert_eq!(session.state(), State::Won);
+        assert_eq!(session.secret(), Some(code("ABCD")));
+        assert_eq!(session.rounds().len(), 2);
+        assert_eq!(session.guess(code("ABCD")), Err(GuessError::Over));
+    }
+
+    #[test]
+    fn loses() {
+        let mut session = GameSession::new(Rules::default(), rounds(2), code("ABCD")).unwrap();
+        session.guess(code("AAAA")).unwrap();
+        session.guess(code("BBBB")).unwrap();
+        assert_eq!(session.state(), State::Lost);
+        assert_eq!(session.secret(), Some(code("ABCD")));
+    }
+
+    #[test]
+    fn plays_unlimited_games() {
+        let mut session = GameSession::unlimited(Rules::default(), code("ABCD")).unwrap();
+        assert_eq!(session.max_round(), None);
+        for guess in Rules::default().codes().into_iter().take(20) {
+            if guess != code("ABCD") {
+                session.guess(guess).unwrap();
+            }
+        }
+        assert_eq!(session.state(), State::Playing);
+        session.guess(code("ABCD")).unwrap();
+        assert_eq!(session.state(), State::Won);
+    }
+
+    #[test]
+    fn seeded_games_hide_the_same_code() {
+        let rules = Rules::new(4, false).unwrap();
+        for seed in 0..10 {
+            let session = GameSession::seeded(rules, rounds(10), seed);
+            assert_eq!(
+                session.reveal(),
+                GameSession::seeded(rules, rounds(10), seed).reveal()
+            );
+            assert_eq!(rules.check(session.reveal()), Ok(()));
+        }
+    }
+
+    #[test]
+    fn checks_rules() {
+        let rules = Rules::new(4, false).unwrap();
+        assert_eq!(
+            GameSession::new(rules, rounds(10), code("ABCE")).err(),
+            Some(RulesError::UnusedPeg(CodePeg::E))
+        );
+        let mut session = GameSession::new(rules, rounds(10), code("ABCD")).unwrap();
+        assert_eq!(
+            session.guess(code("ABCE")),
+            Err(GuessError::Rules(RulesError::UnusedPeg(CodePeg::E)))
+        );
+        assert!(session.rounds().is_empty());
+        assert_eq!(
+            session.violations(),
+            [(code("ABCE"), RulesError::UnusedPeg(CodePeg::E))]
+        );
+
+        let mut session =
+            GameSession::new(Rules::default().with_strict(true), rounds(10), code("ABCD")).unwrap();
+        session.guess(code("AABB")).unwrap();
+        assert_eq!(
+            session.guess(code("AABB")),
+            Err(GuessError::Rules(RulesError::Repeated(code("AABB"))))
+        );
+        assert_eq!(session.rounds().len(), 1);
+        assert_eq!(
+            session.violations(),
+            [(code("AABB"), RulesError::Repeated(code("AABB")))]
+        );
+    }
+}
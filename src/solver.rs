@@ -0,0 +1,866 @@
+//! Code breakers which solve the code on their own.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+#[cfg(feature = "batch")]
+use crate::batch::CodeBatch;
+use crate::book::Book;
+use crate::candidates::CandidateSet;
+#[cfg(feature = "packed")]
+use crate::packed::PackedCode;
+use crate::rules::{ParseRulesError, Rules};
+#[cfg(feature = "table")]
+use crate::table::TableScorer;
+use crate::{Code, CodeBreaker, CodePeg, Score, Scorer, SIZE};
+
+/// How the solver picks its next guess among all the codes.
+///
+/// Equally good guesses are told apart by their order in `Code::all`, the
+/// first one being played, so that a solver plays the same guesses whatever
+/// its backend, its pool or the platform.
+///
+/// Either way, a wrong guess rules out at least one candidate, so that the
+/// solver breaks any code within `Code::COUNT` rounds, and ends the games
+/// with no limit of rounds (see `Game::unlimited`).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Strategy {
+    /// Plays the first code which is consistent with all the scores so far.
+    #[default]
+    FirstCandidate,
+    /// Plays the code which minimizes the number of candidates left in the
+    /// worst case, preferring candidates, as in Knuth's algorithm.
+    ///
+    /// Solvers open with the guesses of `Book::minimax`, which are the ones
+    /// they would compute.
+    Minimax,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 2] = [Strategy::FirstCandidate, Strategy::Minimax];
+}
+
+/// How the solver scores codes, which only changes how fast it plays.
+///
+/// Each backend but the scorer needs the feature of the same name.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Backend {
+    #[default]
+    Scorer,
+    /// Packs the codes first (see `packed`), which pays off when each code
+    /// is scored many times, as with `Strategy::Minimax`.
+    #[cfg(feature = "packed")]
+    Packed,
+    /// Looks the scores up in the table shared by all solvers (see `table`),
+    /// built by the first one.
+    #[cfg(feature = "table")]
+    Table,
+    /// Scores the candidates many at a time (see `batch`), with SIMD
+    /// instructions if the `simd` feature is enabled.
+    #[cfg(feature = "batch")]
+    Batch,
+}
+
+/// The threads a solver weighs its guesses on (see `Solver::with_pool`).
+#[cfg(feature = "rayon")]
+pub type Pool = rayon::ThreadPool;
+/// Without the `rayon` feature, there is no pool.
+#[cfg(not(feature = "rayon"))]
+pub type Pool = std::convert::Infallible;
+
+pub struct Solver {
+    rules: Rules,
+    strategy: Strategy,
+    backend: Backend,
+    pool: Option<Arc<Pool>>,
+    book: Option<Book>,
+    /// The guesses recorded so far, with their scores.
+    history: Vec<(Code, Score)>,
+    candidates: CandidateSet,
+    guess: Option<Code>,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Solver::with_strategy(Strategy::default())
+    }
+
+    pub fn with_strategy(strategy: Strategy) -> Self {
+        Solver::with_rules(Rules::default(), strategy)
+    }
+
+    /// A solver which only considers, and only plays, codes allowed by `rules`.
+    pub fn with_rules(rules: Rules, strategy: Strategy) -> Self {
+        Solver::with_backend(rules, strategy, Backend::default())
+    }
+
+    /// Like `with_rules`, scoring codes with `backend`.
+    pub fn with_backend(rules: Rules, strategy: Strategy, backend: Backend) -> Self {
+        Solver::build(rules, strategy, backend, None)
+    }
+
+    /// Like `with_backend`, weighing the guesses of `Strategy::Minimax` on
+    /// the threads of `pool`, which many solvers may share, rather than on
+    /// the caller's thread alone.
+    #[cfg(feature = "rayon")]
+    pub fn with_pool(
+        rules: Rules,
+        strategy: Strategy,
+        backend: Backend,
+        pool: Arc<rayon::ThreadPool>,
+    ) -> Self {
+        Solver::build(rules, strategy, backend, Some(pool))
+    }
+
+    fn build(rules: Rules, strategy: Strategy, backend: Backend, pool: Option<Arc<Pool>>) -> Self {
+        let mut solver = Solver {
+            rules,
+            strategy,
+            backend,
+            pool,
+            book: default_book(rules, strategy),
+            history: Vec::new(),
+            candidates: CandidateSet::with_rules(rules),
+            guess: None,
+        };
+        solver.guess = solver.next_guess();
+        solver
+    }
+
+    /// Plays the guesses of `book` first, or only its own guesses if `book`
+    /// is `None`. Minimax solvers use `Book::minimax` unless told otherwise.
+    pub fn with_book(mut self, book: Option<Book>) -> Self {
+        self.book = book;
+        self.guess = self.next_guess();
+        self
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// The code to play next, or `None` if the scores contradict each other.
+    pub fn guess(&self) -> Option<Code> {
+        self.guess
+    }
+
+    /// Codes which are still consistent with all the scores received so far.
+    ///
+    /// It is empty only if the code maker gave contradictory scores.
+    pub fn candidates(&self) -> &CandidateSet {
+        &self.candidates
+    }
+
+    /// Keeps only the candidates which would give `score` to `guess`.
+    ///
+    /// Unlike `set_score`, the guess does not have to be the solver's own.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(%guess))
+    )]
+    pub fn record(&mut self, guess: Code, score: Score) {
+        match self.backend {
+            Backend::Scorer => self
+                .candidates
+                .retain(|candidate| Scorer::new(candidate).score(guess) == score),
+            #[cfg(feature = "packed")]
+            Backend::Packed => {
+                let guess = PackedCode::from(guess);
+                self.candidates
+                    .retain(|candidate| PackedCode::from(candidate).score(guess) == score);
+            }
+            #[cfg(feature = "table")]
+            Backend::Table => {
+                let table = TableScorer::shared();
+                self.candidates
+                    .retain(|candidate| table.score(candidate, guess) == score);
+            }
+            #[cfg(feature = "batch")]
+            Backend::Batch => {
+                let batch = CodeBatch::new(&self.candidates);
+                // The scores come in the order of the candidates.
+                let mut indices = batch.score_indices(guess);
+                self.candidates
+                    .retain(|_| indices.next() == Some(score.index()));
+            }
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(candidates = self.candidates.len(), "filtered");
+        self.history.push((guess, score));
+        self.guess = self.next_guess();
+    }
+
+    /// What the solver needs to carry on later, e.g. to resume a
+    /// correspondence game days later with `restore`.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            rules: self.rules,
+            strategy: self.strategy,
+            backend: self.backend,
+            book: self.book.clone(),
+            rounds: self.history.clone(),
+        }
+    }
+
+    /// A solver in the state `checkpoint` was taken in, with its backend and
+    /// its book, which plays the same guesses from there on.
+    ///
+    /// The candidates and the next guess are worked out again from the
+    /// rounds. A pool is not part of the state, and is not restored.
+    pub fn restore(checkpoint: &Checkpoint) -> Self {
+        let mut solver =
+            Solver::with_backend(checkpoint.rules, checkpoint.strategy, checkpoint.backend)
+                .with_book(checkpoint.book.clone());
+        for &(guess, score) in &checkpoint.rounds {
+            solver.record(guess, score);
+        }
+        solver
+    }
+
+    /// The candidates which `score` rules out for `guess`, each with the
+    /// score it would have given instead.
+    pub fn eliminated(&self, guess: Code, score: Score) -> Vec<(Code, Score)> {
+        self.candidates
+            .iter()
+            .map(|candidate| (candidate, Scorer::new(candidate).score(guess)))
+            .filter(|&(_, candidate_score)| candidate_score != score)
+            .collect()
+    }
+
+    /// The largest number of candidates which may be left after playing `guess`.
+    pub fn worst_case(&self, guess: Code) -> usize {
+        partitions(&self.candidates, guess, self.backend)
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or(0)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(strategy = ?self.strategy, candidates = self.candidates.len())
+        )
+    )]
+    fn next_guess(&self) -> Option<Code> {
+        if let Some(guess) = self.book_guess() {
+            return Some(guess);
+        }
+        match self.strategy {
+            Strategy::FirstCandidate => self.candidates.first(),
+            Strategy::Minimax => minimax_guess(
+                &self.candidates,
+                &self.rules.codes(),
+                self.backend,
+                self.pool.as_deref(),
+            ),
+        }
+    }
+
+    /// The guess of the book, if the game is still in it.
+    fn book_guess(&self) -> Option<Code> {
+        let book = self.book.as_ref()?;
+        match self.history[..] {
+            [] => Some(book.opening()),
+            [(guess, score)] if guess == book.opening() => book.reply(score),
+            _ => None,
+        }
+    }
+}
+
+/// The book solvers with `strategy` use unless told otherwise.
+fn default_book(rules: Rules, strategy: Strategy) -> Option<Book> {
+    match strategy {
+        Strategy::Minimax => Book::minimax(rules),
+        Strategy::FirstCandidate => None,
+    }
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Solver::new()
+    }
+}
+
+/// The solver forfeits once no candidate is left, i.e. if the scores were
+/// contradictory.
+impl CodeBreaker for Solver {
+    fn guess_code(&self) -> Code {
+        // Ignored by the game once the solver forfeits.
+        self.guess.unwrap_or(Code::new([CodePeg::A; SIZE]))
+    }
+
+    fn set_score(&mut self, score: Score) {
+        if let Some(guess) = self.guess {
+            self.record(guess, score);
+        }
+    }
+
+    fn loses(&mut self) {}
+
+    fn forfeited(&self) -> bool {
+        self.guess.is_none()
+    }
+}
+
+/// The state of a solver, taken with `Solver::checkpoint`.
+///
+/// It is written one item per line, the rules first:
+///
+/// ```text
+/// rules 6 duplicates
+/// strategy minimax
+/// backend scorer
+/// book default
+/// round AABB 1 0
+/// round CCDE 0 2
+/// ```
+///
+/// The book is `default` for the one of the strategy, `none`, or the
+/// opening of a book of its own followed by its replies, e.g.
+/// `book ABCD` then `reply 0 0 EEFF`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checkpoint {
+    pub rules: Rules,
+    pub strategy: Strategy,
+    pub backend: Backend,
+    pub book: Option<Book>,
+    /// The guesses recorded so far, with their scores.
+    pub rounds: Vec<(Code, Score)>,
+}
+
+fn backend_name(backend: Backend) -> &'static str {
+    match backend {
+        Backend::Scorer => "scorer",
+        #[cfg(feature = "packed")]
+        Backend::Packed => "packed",
+        #[cfg(feature = "table")]
+        Backend::Table => "table",
+        #[cfg(feature = "batch")]
+        Backend::Batch => "batch",
+    }
+}
+
+/// The backend named `name`, if its feature is enabled.
+fn backend(name: &str) -> Option<Backend> {
+    match name {
+        "scorer" => Some(Backend::Scorer),
+        #[cfg(feature = "packed")]
+        "packed" => Some(Backend::Packed),
+        #[cfg(feature = "table")]
+        "table" => Some(Backend::Table),
+        #[cfg(feature = "batch")]
+        "batch" => Some(Backend::Batch),
+        _ => None,
+    }
+}
+
+impl fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "rules {}", self.rules)?;
+        let strategy = match self.strategy {
+            Strategy::FirstCandidate => "first-candidate",
+            Strategy::Minimax => "minimax",
+        };
+        writeln!(f, "strategy {}", strategy)?;
+        writeln!(f, "backend {}", backend_name(self.backend))?;
+        match &self.book {
+            book if *book == default_book(self.rules, self.strategy) => {
+                writeln!(f, "book default")?
+            }
+            None => writeln!(f, "book none")?,
+            Some(book) => {
+                writeln!(f, "book {}", book.opening())?;
+                for (score, reply) in book.replies() {
+                    writeln!(
+                        f,
+                        "reply {} {} {}",
+                        score.matches(),
+                        score.presents(),
+                        reply
+                    )?;
+                }
+            }
+        }
+        for (guess, score) in &self.rounds {
+            writeln!(
+                f,
+                "round {} {} {}",
+                guess,
+                score.matches(),
+                score.presents()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ParseCheckpointError {
+    /// The line naming this item is missing or out of order.
+    Missing(&'static str),
+    Rules(ParseRulesError),
+    /// The backend is unknown, or its feature is not enabled.
+    Backend(String),
+    Opening(String),
+    /// A reply or round line cannot be read, or is out of order.
+    Line(String),
+}
+
+impl fmt::Display for ParseCheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseCheckpointError::Missing(item) => write!(f, "expected the {}", item),
+            ParseCheckpointError::Rules(err) => write!(f, "{}", err),
+            ParseCheckpointError::Backend(name) => write!(f, "unknown backend '{}'", name),
+            ParseCheckpointError::Opening(opening) => {
+                write!(f, "invalid opening '{}'", opening)
+            }
+            ParseCheckpointError::Line(line) => write!(f, "invalid line '{}'", line),
+        }
+    }
+}
+
+impl std::error::Error for ParseCheckpointError {}
+
+impl FromStr for Checkpoint {
+    type Err = ParseCheckpointError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+        let rules = match lines.next().and_then(|line| line.strip_prefix("rules ")) {
+            Some(rules) => rules.parse().map_err(ParseCheckpointError::Rules)?,
+            None => return Err(ParseCheckpointError::Missing("rules")),
+        };
+        let strategy = match lines.next() {
+            Some("strategy first-candidate") => Strategy::FirstCandidate,
+            Some("strategy minimax") => Strategy::Minimax,
+            _ => return Err(ParseCheckpointError::Missing("strategy")),
+        };
+        let backend = match lines.next().and_then(|line| line.strip_prefix("backend ")) {
+            Some(name) => {
+                backend(name).ok_or_else(|| ParseCheckpointError::Backend(name.to_string()))?
+            }
+            None => return Err(ParseCheckpointError::Missing("backend")),
+        };
+        // Only books of their own are followed by their replies.
+        let (mut book, custom) = match lines.next().and_then(|line| line.strip_prefix("book ")) {
+            Some("default") => (default_book(rules, strategy), false),
+            Some("none") => (None, false),
+            Some(opening) => {
+                let opening = opening
+                    .parse()
+                    .map_err(|_| ParseCheckpointError::Opening(opening.to_string()))?;
+                (Some(Book::new(opening)), true)
+            }
+            None => return Err(ParseCheckpointError::Missing("book")),
+        };
+        let mut rounds = Vec::new();
+        for line in lines {
+            let invalid = || ParseCheckpointError::Line(line.to_string());
+            let (reply, guess, matches, presents) =
+                match line.split_whitespace().collect::<Vec<_>>()[..] {
+                    ["reply", matches, presents, guess] if custom && rounds.is_empty() => {
+                        (true, guess, matches, presents)
+                    }
+                    ["round", guess, matches, presents] => (false, guess, matches, presents),
+                    _ => return Err(invalid()),
+                };
+            let guess = guess.parse().map_err(|_| invalid())?;
+            let matches = matches.parse().map_err(|_| invalid())?;
+            let presents = presents.parse().map_err(|_| invalid())?;
+            let score = Score::from_counts(matches, presents).ok_or_else(invalid)?;
+            if reply {
+                book = book.map(|book| book.with_reply(score, guess));
+            } else {
+                rounds.push((guess, score));
+            }
+        }
+        Ok(Checkpoint {
+            rules,
+            strategy,
+            backend,
+            book,
+            rounds,
+        })
+    }
+}
+
+/// Number of candidates which would give each score to `guess`, indexed by
+/// `Score::index`.
+fn partitions(candidates: &CandidateSet, guess: Code, backend: Backend) -> Partitions {
+    match backend {
+        Backend::Scorer => count(
+            candidates
+                .iter()
+                .map(|candidate| Scorer::new(candidate).score(guess).index()),
+        ),
+        #[cfg(feature = "packed")]
+        Backend::Packed => {
+            let guess = PackedCode::from(guess);
+            count(
+                candidates
+                    .iter()
+                    .map(|candidate| PackedCode::from(candidate).score(guess).index()),
+            )
+        }
+        #[cfg(feature = "table")]
+        Backend::Table => {
+            let table = TableScorer::shared();
+            count(
+                candidates
+                    .iter()
+                    .map(|candidate| table.score_index(candidate, guess)),
+            )
+        }
+        #[cfg(feature = "batch")]
+        Backend::Batch => count(CodeBatch::new(candidates).score_indices(guess)),
+    }
+}
+
+type Partitions = [usize; (SIZE + 1) * (SIZE + 1)];
+
+/// Counts the scores given by their `Score::index`.
+fn count(indices: impl Iterator<Item = usize>) -> Partitions {
+    let mut partitions = [0; (SIZE + 1) * (SIZE + 1)];
+    for index in indices {
+        partitions[index] += 1;
+    }
+    partitions
+}
+
+/// The code among `guesses` whose largest partition of the candidates is the
+/// smallest, or `None` if there are no candidates or no guesses.
+///
+/// Ties are broken by preferring candidates, then the first code in
+/// lexicographic order.
+fn minimax_guess(
+    candidates: &CandidateSet,
+    guesses: &[Code],
+    backend: Backend,
+    pool: Option<&Pool>,
+) -> Option<Code> {
+    // With one or two candidates, playing the first one is optimal.
+    if candidates.len() <= 2 {
+        return candidates.first();
+    }
+    match backend {
+        #[cfg(feature = "packed")]
+        Backend::Packed => {
+            // Packed once for all the guesses.
+            let candidates: Vec<PackedCode> = candidates.iter().map(PackedCode::from).collect();
+            minimax_by(guesses, pool, |guess| {
+                let guess = PackedCode::from(guess);
+                count(
+                    candidates
+                        .iter()
+                        .map(|candidate| candidate.score(guess).index()),
+                )
+            })
+        }
+        #[cfg(feature = "batch")]
+        Backend::Batch => {
+            let batch = CodeBatch::new(candidates);
+            minimax_by(guesses, pool, |guess| count(batch.score_indices(guess)))
+        }
+        // The other backends need no setup.
+        _ => minimax_by(guesses, pool, |guess| {
+            partitions(candidates, guess, backend)
+        }),
+    }
+}
+
+fn minimax_by(
+    guesses: &[Code],
+    pool: Option<&Pool>,
+    partitions: impl Fn(Code) -> Partitions + Sync,
+) -> Option<Code> {
+    let win_index = (SIZE + 1) * SIZE;
+    // The smallest key wins, and the guesses' positions break the ties
+    // whatever the order the threads weigh them in.
+    let key = |(i, &guess): (usize, &Code)| {
+        let partitions = partitions(guess);
+        let worst = partitions.iter().max().copied().unwrap_or(0);
+        let is_candidate = partitions[win_index] > 0;
+        (worst, !is_candidate, i)
+    };
+    let best = match pool {
+        #[cfg(feature = "rayon")]
+        Some(pool) => pool.install(|| guesses.par_iter().enumerate().map(key).min()),
+        _ => guesses.iter().enumerate().map(key).min(),
+    };
+    best.map(|(_, _, i)| guesses[i])
+}
+
+#[cfg(test)]
+mod test_solver {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+    use crate::testutil::DeterministicCodeMaker;
+    use crate::{CodePeg, Game, SIZE};
+
+    #[test]
+    fn resumes_from_checkpoints() {
+        let code: Code = "FDCA".parse().unwrap();
+        let scorer = Scorer::new(code);
+        let rules = Rules::default().with_strict(true);
+        let mut solver = Solver::with_rules(rules, Strategy::Minimax);
+        for _ in 0..2 {
+            let score = scorer.score(solver.guess_code());
+            solver.set_score(score);
+        }
+        let checkpoint: Checkpoint = solver.checkpoint().to_string().parse().unwrap();
+        assert_eq!(checkpoint, solver.checkpoint());
+        let mut restored = Solver::restore(&checkpoint);
+        assert_eq!(restored.rules(), rules);
+        assert_eq!(restored.candidates(), solver.candidates());
+        while solver.guess_code() != code {
+            assert_eq!(restored.guess_code(), solver.guess_code());
+            let score = scorer.score(solver.guess_code());
+            solver.set_score(score);
+            restored.set_score(score);
+        }
+        assert_eq!(restored.guess_code(), code);
+
+        assert_eq!(
+            "strategy minimax".parse::<Checkpoint>(),
+            Err(ParseCheckpointError::Missing("rules"))
+        );
+        assert_eq!(
+            "rules 6 duplicates\nstrategy minimax\nbackend scorer\nbook default\nround AABB 5 0"
+                .parse::<Checkpoint>(),
+            Err(ParseCheckpointError::Line("round AABB 5 0".to_string()))
+        );
+        assert_eq!(
+            "rules 6 duplicates\nstrategy minimax\nbackend scorer\nbook default\nreply 0 0 EEFF"
+                .parse::<Checkpoint>(),
+            Err(ParseCheckpointError::Line("reply 0 0 EEFF".to_string()))
+        );
+    }
+
+    #[test]
+    fn keeps_books_in_checkpoints() {
+        let code: Code = "EFFA".parse().unwrap();
+        let scorer = Scorer::new(code);
+        let opening: Code = "ABCD".parse().unwrap();
+        let reply: Code = "EEFF".parse().unwrap();
+        let book = Book::new(opening).with_reply(scorer.score(opening), reply);
+        for book in [Some(book), None] {
+            let mut solver = Solver::with_strategy(Strategy::Minimax).with_book(book.clone());
+            let text = solver.checkpoint().to_string();
+            let checkpoint: Checkpoint = text.parse().unwrap();
+            assert_eq!(checkpoint, solver.checkpoint(), "{}", text);
+            let mut restored = Solver::restore(&checkpoint);
+            while solver.guess_code() != code {
+                assert_eq!(restored.guess_code(), solver.guess_code());
+                let score = scorer.score(solver.guess_code());
+                solver.set_score(score);
+                restored.set_score(score);
+            }
+            assert_eq!(restored.guess_code(), code);
+        }
+    }
+
+    #[test]
+    fn solves_every_code() {
+        for code in Code::all() {
+            let code_maker = DeterministicCodeMaker::new(code);
+            let mut solver = Solver::new();
+            Game::new(NonZeroUsize::new(10).unwrap(), &code_maker, &mut solver).play();
+            assert_eq!(solver.candidates().to_vec(), [code], "code {}", code);
+        }
+    }
+
+    #[test]
+    fn minimax_opens_with_aabb() {
+        let solver = Solver::with_strategy(Strategy::Minimax);
+        assert_eq!(
+            solver.guess_code(),
+            Code::new([CodePeg::A, CodePeg::A, CodePeg::B, CodePeg::B])
+        );
+    }
+
+    #[test]
+    fn minimax_breaks_ties_by_code_order() {
+        // Many openings leave at most 256 candidates, AABB being the first.
+        let solver = Solver::with_strategy(Strategy::Minimax).with_book(None);
+        assert_eq!(
+            solver.guess_code(),
+            Code::new([CodePeg::A, CodePeg::A, CodePeg::B, CodePeg::B])
+        );
+        let guesses = Rules::default().codes();
+        let aabb = guesses.iter().position(|&code| code == solver.guess_code());
+        let worst = |guess| {
+            let mut partitions = [0; (SIZE + 1) * (SIZE + 1)];
+            for &code in &guesses {
+                partitions[Scorer::new(code).score(guess).index()] += 1;
+            }
+            partitions.into_iter().max().unwrap()
+        };
+        let ties: Vec<usize> = (0..guesses.len())
+            .filter(|&i| worst(guesses[i]) == 256)
+            .collect();
+        assert!(ties.len() > 1);
+        assert_eq!(ties.first().copied(), aabb);
+    }
+
+    #[test]
+    fn minimax_solves_in_five_rounds() {
+        let codes = [
+            Code::new([CodePeg::A, CodePeg::B, CodePeg::C, CodePeg::D]),
+            Code::new([CodePeg::F, CodePeg::E, CodePeg::F, CodePeg::E]),
+            Code::new([CodePeg::C, CodePeg::C, CodePeg::C, CodePeg::A]),
+        ];
+        for code in codes {
+            let code_maker = DeterministicCodeMaker::new(code);
+            let mut solver = Solver::with_strategy(Strategy::Minimax);
+            Game::new(NonZeroUsize::new(5).unwrap(), &code_maker, &mut solver).play();
+            assert_eq!(solver.candidates().to_vec(), [code], "code {}", code);
+        }
+    }
+
+    #[test]
+    fn solves_custom_rules() {
+        let rules = Rules::new(5, false).unwrap();
+        for strategy in [Strategy::FirstCandidate, Strategy::Minimax] {
+            for code in rules.codes() {
+                let code_maker = DeterministicCodeMaker::new(code);
+                let mut solver = Solver::with_rules(rules, strategy);
+                Game::new(NonZeroUsize::new(10).unwrap(), &code_maker, &mut solver).play();
+                assert_eq!(solver.candidates().to_vec(), [code], "code {}", code);
+            }
+        }
+    }
+
+    #[test]
+    fn backends_play_the_same_guesses() {
+        let backends: Vec<Backend> = vec![
+            #[cfg(feature = "packed")]
+            Backend::Packed,
+            #[cfg(feature = "table")]
+            Backend::Table,
+            #[cfg(feature = "batch")]
+            Backend::Batch,
+        ];
+
+        let rules = Rules::new(4, true).unwrap();
+        for code in rules.codes().into_iter().step_by(13) {
+            let scorer = Scorer::new(code);
+            let guesses = |backend| {
+                let mut solver = Solver::with_backend(rules, Strategy::Minimax, backend);
+                let mut guesses = vec![solver.guess_code()];
+                while guesses.last() != Some(&code) {
+                    solver.set_score(scorer.score(solver.guess_code()));
+                    guesses.push(solver.guess_code());
+                }
+                guesses
+            };
+            let by_scorer = guesses(Backend::Scorer);
+            for &backend in &backends {
+                assert_eq!(guesses(backend), by_scorer, "{:?}, code {}", backend, code);
+            }
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn plays_the_same_guesses_on_a_pool() {
+        let rules = Rules::new(5, true).unwrap();
+        let pool = Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(3)
+                .build()
+                .unwrap(),
+        );
+        for code in rules.codes().into_iter().step_by(29) {
+            let scorer = Scorer::new(code);
+            let mut solver = Solver::with_rules(rules, Strategy::Minimax);
+            let mut pooled = Solver::with_pool(
+                rules,
+                Strategy::Minimax,
+                Backend::default(),
+                Arc::clone(&pool),
+            );
+            while solver.guess_code() != code {
+                assert_eq!(pooled.guess_code(), solver.guess_code(), "code {}", code);
+                let score = scorer.score(solver.guess_code());
+                solver.set_score(score);
+                pooled.set_score(score);
+            }
+            assert_eq!(pooled.guess_code(), code);
+        }
+    }
+
+    #[test]
+    fn worst_case() {
+        let solver = Solver::new();
+        let aabb = Code::new([CodePeg::A, CodePeg::A, CodePeg::B, CodePeg::B]);
+        let aaaa = Code::new([CodePeg::A; SIZE]);
+        assert_eq!(solver.worst_case(aabb), 256);
+        assert_eq!(solver.worst_case(aaaa), 625);
+    }
+
+    #[test]
+    fn records_other_guesses() {
+        let mut solver = Solver::new();
+        let guess = Code::new([CodePeg::A, CodePeg::B, CodePeg::C, CodePeg::D]);
+        solver.record(guess, Score::from_counts(0, 0).unwrap());
+        assert_eq!(solver.candidates().len(), 16);
+        assert_eq!(solver.guess_code(), Code::new([CodePeg::E; SIZE]));
+    }
+
+    #[test]
+    fn explains_eliminated_codes() {
+        let mut solver = Solver::new();
+        let guess = Code::new([CodePeg::A, CodePeg::B, CodePeg::C, CodePeg::D]);
+        let score = Score::from_counts(0, 0).unwrap();
+        let eliminated = solver.eliminated(guess, score);
+        assert_eq!(eliminated.len(), 1296 - 16);
+        assert_eq!(
+            eliminated[0],
+            (
+                Code::new([CodePeg::A; SIZE]),
+                Score::from_counts(1, 0).unwrap()
+            )
+        );
+
+        solver.record(guess, score);
+        assert!(solver.eliminated(guess, score).is_empty());
+    }
+
+    #[test]
+    fn detects_contradictory_scores() {
+        let mut solver = Solver::new();
+        let guess = solver.guess_code();
+        assert_eq!(guess, Code::new([CodePeg::A; SIZE]),);
+        // Claim that none of A, B, C, D and E is in the code: only FFFF is left.
+        for _ in 0..5 {
+            solver.set_score(Score::from_counts(0, 0).unwrap());
+        }
+        assert_eq!(
+            solver.candidates().to_vec(),
+            [Code::new([CodePeg::F; SIZE])]
+        );
+
+        solver.set_score(Score::from_counts(2, 0).unwrap());
+        assert!(solver.candidates().is_empty());
+        assert_eq!(solver.guess(), None);
+        assert!(solver.forfeited());
+    }
+}
@@ -0,0 +1,193 @@
+//! Live games broadcast to any number of spectators.
+
+use crate::net::{Message, Outcome};
+use crate::sync::mpsc::{self, Receiver, Sender};
+use crate::sync::{lock, Arc, Mutex};
+use crate::{Code, CodeBreaker, Score};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Event {
+    Guess(Code),
+    Score(Score),
+    Over(Outcome),
+}
+
+impl From<Event> for Message {
+    fn from(event: Event) -> Self {
+        match event {
+            Event::Guess(code) => Message::Guess(code),
+            Event::Score(score) => Message::Score(score),
+            Event::Over(outcome) => Message::Result(outcome),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    events: Vec<Event>,
+    spectators: Vec<Sender<Event>>,
+}
+
+/// The events of a game, sent to each of its spectators.
+///
+/// Publishing never waits for the spectators: their events queue until
+/// they read them, and the spectators who left are forgotten.
+#[derive(Clone, Default)]
+pub struct Broadcast {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Broadcast {
+    pub fn new() -> Self {
+        Broadcast::default()
+    }
+
+    /// Adds a spectator, who first receives the events published so far.
+    ///
+    /// The channel closes once every clone of the broadcast is dropped.
+    pub fn subscribe(&self) -> Receiver<Event> {
+        let (sender, receiver) = mpsc::channel();
+        let mut shared = lock(&self.shared);
+        for &event in &shared.events {
+            // Cannot fail, since the receiver is returned below.
+            let _ = sender.send(event);
+        }
+        shared.spectators.push(sender);
+        receiver
+    }
+
+    pub fn publish(&self, event: Event) {
+        let mut shared = lock(&self.shared);
+        shared.events.push(event);
+        shared
+            .spectators
+            .retain(|spectator| spectator.send(event).is_ok());
+    }
+
+    /// The events published so far.
+    pub fn events(&self) -> Vec<Event> {
+        lock(&self.shared).events.clone()
+    }
+
+    /// The number of spectators, counting those who left since the last
+    /// event.
+    pub fn spectators(&self) -> usize {
+        lock(&self.shared).spectators.len()
+    }
+}
+
+/// A code breaker whose guesses and scores are broadcast.
+///
+/// The end of the game is left to publish by whoever knows the code.
+pub struct Broadcasting<U> {
+    code_breaker: U,
+    broadcast: Broadcast,
+}
+
+impl<U> Broadcasting<U> {
+    pub fn new(code_breaker: U, broadcast: Broadcast) -> Self {
+        Broadcasting {
+            code_breaker,
+            broadcast,
+        }
+    }
+
+    pub fn get_ref(&self) -> &U {
+        &self.code_breaker
+    }
+
+    pub fn into_inner(self) -> U {
+        self.code_breaker
+    }
+}
+
+impl<U: CodeBreaker> CodeBreaker for Broadcasting<U> {
+    fn guess_code(&self) -> Code {
+        let guess = self.code_breaker.guess_code();
+        self.broadcast.publish(Event::Guess(guess));
+        guess
+    }
+
+    fn set_score(&mut self, score: Score) {
+        self.broadcast.publish(Event::Score(score));
+        self.code_breaker.set_score(score);
+    }
+
+    fn loses(&mut self) {
+        self.code_breaker.loses();
+    }
+
+    fn wins(&mut self, rounds: usize) {
+        self.code_breaker.wins(rounds);
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test_spectate {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+    use crate::testutil::{DeterministicCodeMaker, ScriptedCodeBreaker};
+    use crate::Game;
+
+    #[test]
+    fn broadcasts() {
+        let broadcast = Broadcast::new();
+        let early = broadcast.subscribe();
+        let left = broadcast.subscribe();
+        drop(left);
+
+        let code: Code = "ABCD".parse().unwrap();
+        let guesses = vec!["AABB".parse().unwrap(), code];
+        let code_breaker = ScriptedCodeBreaker::new(guesses);
+        let mut code_breaker = Broadcasting::new(code_breaker, broadcast.clone());
+        Game::new(
+            NonZeroUsize::new(10).unwrap(),
+            &DeterministicCodeMaker::new(code),
+            &mut code_breaker,
+        )
+        .play();
+        broadcast.publish(Event::Over(Outcome::Won(2)));
+        assert_eq!(broadcast.spectators(), 1);
+        assert!(code_breaker.get_ref().has_won);
+
+        let late = broadcast.subscribe();
+        drop((broadcast, code_breaker));
+        let events: Vec<Event> = early.iter().collect();
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[2], Event::Guess(code));
+        assert_eq!(events[4], Event::Over(Outcome::Won(2)));
+        assert_eq!(late.iter().collect::<Vec<_>>(), events);
+    }
+}
+
+#[cfg(all(test, loom))]
+mod test_loom {
+    use loom::thread;
+
+    use super::*;
+
+    #[test]
+    fn late_spectators_miss_no_event() {
+        loom::model(|| {
+            let broadcast = Broadcast::new();
+            let score = Score::from_counts(0, 1).unwrap();
+            let events = [Event::Score(score), Event::Over(Outcome::Won(2))];
+            let publisher = {
+                let broadcast = broadcast.clone();
+                thread::spawn(move || {
+                    for event in events {
+                        broadcast.publish(event);
+                    }
+                })
+            };
+            let spectator = broadcast.subscribe();
+            publisher.join().unwrap();
+            // Every event once and in order, whether it was published before
+            // or after subscribing.
+            let received: Vec<Event> = std::iter::from_fn(|| spectator.try_recv().ok()).collect();
+            assert_eq!(received, events);
+        });
+    }
+}
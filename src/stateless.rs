@@ -0,0 +1,91 @@
+//! Code breakers which guess from the rounds played so far, so that simple
+//! solvers can be pure functions instead of keeping their own state.
+
+use crate::{Code, CodeBreaker, Score};
+
+/// Guesses from the rounds played so far alone: given the same rounds, it
+/// makes the same guess.
+///
+/// Any `Fn(&[(Code, Score)]) -> Code` is one.
+pub trait StatelessCodeBreaker {
+    fn guess_code(&self, rounds: &[(Code, Score)]) -> Code;
+}
+
+impl<F: Fn(&[(Code, Score)]) -> Code> StatelessCodeBreaker for F {
+    fn guess_code(&self, rounds: &[(Code, Score)]) -> Code {
+        self(rounds)
+    }
+}
+
+/// Plays a `StatelessCodeBreaker` as a `CodeBreaker`, keeping the rounds
+/// for it.
+pub struct Stateless<B> {
+    code_breaker: B,
+    rounds: Vec<(Code, Score)>,
+}
+
+impl<B> Stateless<B> {
+    pub fn new(code_breaker: B) -> Self {
+        Stateless {
+            code_breaker,
+            rounds: Vec::new(),
+        }
+    }
+
+    /// The guesses played so far, with their scores.
+    pub fn rounds(&self) -> &[(Code, Score)] {
+        &self.rounds
+    }
+
+    pub fn into_inner(self) -> B {
+        self.code_breaker
+    }
+}
+
+impl<B: StatelessCodeBreaker> CodeBreaker for Stateless<B> {
+    fn guess_code(&self) -> Code {
+        self.code_breaker.guess_code(&self.rounds)
+    }
+
+    /// Guesses again to learn which guess was scored, since the guess only
+    /// depends on the rounds.
+    fn set_score(&mut self, score: Score) {
+        let guess = CodeBreaker::guess_code(self);
+        self.rounds.push((guess, score));
+    }
+
+    fn loses(&mut self) {}
+}
+
+#[cfg(test)]
+mod test_stateless {
+    use super::*;
+    use crate::rules::Rules;
+    use crate::testutil::DeterministicCodeMaker;
+    use crate::{Game, Scorer, SIZE};
+
+    /// The first code which would have got every score so far.
+    fn first_candidate(rounds: &[(Code, Score)]) -> Code {
+        Rules::default()
+            .codes()
+            .into_iter()
+            .find(|&code| {
+                let scorer = Scorer::new(code);
+                rounds
+                    .iter()
+                    .all(|&(guess, score)| scorer.score(guess) == score)
+            })
+            .expect("the scores are consistent")
+    }
+
+    #[test]
+    fn plays_pure_functions() {
+        let code: Code = "FDCA".parse().unwrap();
+        let mut code_breaker = Stateless::new(first_candidate);
+        Game::unlimited(&DeterministicCodeMaker::new(code), &mut code_breaker).play();
+        let rounds = code_breaker.rounds();
+        assert_eq!(rounds[0].0, "AAAA".parse().unwrap());
+        assert_eq!(rounds.last().map(|&(guess, _)| guess), Some(code));
+        assert_eq!(rounds.last().map(|(_, score)| score.matches()), Some(SIZE));
+    }
+}
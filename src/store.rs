@@ -0,0 +1,627 @@
+//! Finished games and the ratings and streaks of their players, with the
+//! `store` feature.
+//!
+//! Games are kept with their transcript, for players to replay them.
+//! `GameStore` and `StatsStore` are all the server and the command line
+//! need, so that embedders can keep games in their own database. The
+//! default `JsonFileStore` keeps them in a JSON file; with the `sqlite`
+//! feature, `SqliteStore` keeps them in a SQLite database.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "sqlite")]
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::{Code, Score, SIZE};
+
+/// The rating of players before their first game.
+pub const INITIAL_RATING: u32 = 1500;
+
+/// How much a single game can change a rating.
+const K_FACTOR: f64 = 32.0;
+
+/// A game between two named players.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    /// Given by the store when the game is saved.
+    #[serde(default)]
+    pub id: u64,
+    pub maker: String,
+    pub breaker: String,
+    pub max_round: usize,
+    /// The number of rounds the code breaker won in, or `None` if the code
+    /// was not broken.
+    pub won_in: Option<usize>,
+    /// Seconds since the Unix epoch.
+    pub finished_at: u64,
+    /// The rounds, written by `transcript`. Empty for games recorded
+    /// without them.
+    #[serde(default)]
+    pub transcript: String,
+}
+
+impl GameRecord {
+    /// The winner and the loser of the game.
+    fn players(&self) -> (&str, &str) {
+        match self.won_in {
+            Some(_) => (&self.breaker, &self.maker),
+            None => (&self.maker, &self.breaker),
+        }
+    }
+}
+
+/// Writes each round on its own line as `<guess> <matched> <present>`,
+/// e.g. `AABB 1 0`, which `mastermind replay` reads.
+pub fn transcript(rounds: &[(Code, Score)]) -> String {
+    rounds
+        .iter()
+        .map(|(guess, score)| format!("{} {} {}\n", guess, score.matches(), score.presents()))
+        .collect()
+}
+
+/// Reads the rounds written by `transcript`, ignoring blank lines and lines
+/// starting with `#`, such as the guesses a server rejected.
+pub fn parse_transcript(transcript: &str) -> Result<Vec<(Code, Score)>, String> {
+    transcript_rounds(transcript).collect()
+}
+
+/// Reads the rounds written by `transcript` one at a time, without copying
+/// the text, so that long logs need not be collected.
+pub fn transcript_rounds(
+    transcript: &str,
+) -> impl Iterator<Item = Result<(Code, Score), String>> + '_ {
+    transcript
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| {
+            let mut words = line.split_whitespace();
+            let round = match (words.next(), words.next(), words.next(), words.next()) {
+                (Some(guess), Some(matched), Some(present), None) => guess.parse().ok().zip(
+                    matched
+                        .parse()
+                        .ok()
+                        .zip(present.parse().ok())
+                        .and_then(|(matched, present)| Score::from_counts(matched, present)),
+                ),
+                _ => None,
+            };
+            round.ok_or_else(|| format!("line {}: not a round: {}", i + 1, line))
+        })
+}
+
+/// The number of rounds `rounds` were won in, or `None` if the code was not
+/// broken.
+pub fn won_in(rounds: &[(Code, Score)]) -> Option<usize> {
+    let (_, score) = rounds.last()?;
+    (score.matches() == SIZE).then_some(rounds.len())
+}
+
+/// The games of a player, in either role.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlayerStats {
+    pub name: String,
+    pub rating: u32,
+    pub played: usize,
+    pub won: usize,
+    /// The number of games won in a row, up to the last one.
+    pub streak: usize,
+    pub best_streak: usize,
+}
+
+impl PlayerStats {
+    /// A player who has not played yet.
+    pub fn new(name: &str) -> Self {
+        PlayerStats {
+            name: name.to_string(),
+            rating: INITIAL_RATING,
+            played: 0,
+            won: 0,
+            streak: 0,
+            best_streak: 0,
+        }
+    }
+}
+
+/// Counts a game between `winner` and `loser`.
+pub fn update(winner: &mut PlayerStats, loser: &mut PlayerStats) {
+    (winner.rating, loser.rating) = rate(winner.rating, loser.rating);
+    winner.played += 1;
+    winner.won += 1;
+    winner.streak += 1;
+    winner.best_streak = winner.best_streak.max(winner.streak);
+    loser.played += 1;
+    loser.streak = 0;
+}
+
+/// The new ratings of the winner and the loser of a game (Elo).
+fn rate(winner: u32, loser: u32) -> (u32, u32) {
+    let expected = 1.0 / (1.0 + 10f64.powf((loser as f64 - winner as f64) / 400.0));
+    let change = (K_FACTOR * (1.0 - expected)).round() as u32;
+    (winner + change, loser.saturating_sub(change))
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum StoreError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    #[cfg(feature = "sqlite")]
+    Sqlite(rusqlite::Error),
+    /// The store needs a feature which is not enabled.
+    Unsupported(String),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::Io(err) => write!(f, "{}", err),
+            StoreError::Json(err) => write!(f, "{}", err),
+            #[cfg(feature = "sqlite")]
+            StoreError::Sqlite(err) => write!(f, "{}", err),
+            StoreError::Unsupported(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<io::Error> for StoreError {
+    fn from(err: io::Error) -> Self {
+        StoreError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for StoreError {
+    fn from(err: serde_json::Error) -> Self {
+        StoreError::Json(err)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for StoreError {
+    fn from(err: rusqlite::Error) -> Self {
+        StoreError::Sqlite(err)
+    }
+}
+
+/// Where finished games are kept.
+pub trait GameStore {
+    /// Saves a game, and returns the id it is given.
+    fn save_game(&mut self, game: &GameRecord) -> Result<u64, StoreError>;
+
+    /// The game saved with `id`, if any.
+    fn load_game(&self, id: u64) -> Result<Option<GameRecord>, StoreError>;
+
+    /// The last `limit` games of a player, the last one first.
+    fn load_history(&self, name: &str, limit: usize) -> Result<Vec<GameRecord>, StoreError>;
+}
+
+/// Where the ratings and streaks of the players are kept.
+pub trait StatsStore {
+    /// Updates the statistics of the players of a finished game (see
+    /// `update`).
+    fn append_result(&mut self, game: &GameRecord) -> Result<(), StoreError>;
+
+    /// The statistics of a player, or `None` if they never played.
+    fn stats(&self, name: &str) -> Result<Option<PlayerStats>, StoreError>;
+
+    /// The `limit` best rated players, best first.
+    fn leaderboard(&self, limit: usize) -> Result<Vec<PlayerStats>, StoreError>;
+}
+
+/// A store for both, as the server needs.
+pub trait Store: GameStore + StatsStore + Send {
+    /// Saves a finished game and counts it in the statistics, and returns
+    /// the id it is given.
+    fn record(&mut self, game: &GameRecord) -> Result<u64, StoreError> {
+        let id = self.save_game(game)?;
+        self.append_result(game)?;
+        Ok(id)
+    }
+}
+
+impl<T: GameStore + StatsStore + Send> Store for T {}
+
+/// Opens the store at `path`: a SQLite database if its extension is `db`
+/// or `sqlite`, or else a JSON file.
+pub fn open(path: &Path) -> Result<Box<dyn Store>, StoreError> {
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    match extension {
+        #[cfg(feature = "sqlite")]
+        Some("db" | "sqlite") => Ok(Box::new(SqliteStore::open(path)?)),
+        #[cfg(not(feature = "sqlite"))]
+        Some("db" | "sqlite") => Err(StoreError::Unsupported(
+            "SQLite databases need the sqlite feature".to_string(),
+        )),
+        _ => Ok(Box::new(JsonFileStore::open(path)?)),
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Data {
+    games: Vec<GameRecord>,
+    players: BTreeMap<String, PlayerStats>,
+}
+
+/// Keeps everything in memory, and writes it all to a JSON file after each
+/// change. Fine for the games of a single server or player.
+pub struct JsonFileStore {
+    path: PathBuf,
+    data: Data,
+}
+
+impl JsonFileStore {
+    /// Reads the file at `path`, which is created with the first game if it
+    /// does not exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, StoreError> {
+        let path = path.as_ref().to_path_buf();
+        let mut data: Data = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Data::default(),
+            Err(err) => return Err(err.into()),
+        };
+        // Files written before games had ids number them in order.
+        for (i, game) in data.games.iter_mut().enumerate() {
+            if game.id == 0 {
+                game.id = i as u64 + 1;
+            }
+        }
+        Ok(JsonFileStore { path, data })
+    }
+
+    fn write(&self) -> Result<(), StoreError> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        // Written aside first, not to lose everything if writing fails.
+        let temporary = self.path.with_extension("tmp");
+        fs::write(&temporary, serde_json::to_string_pretty(&self.data)?)?;
+        fs::rename(&temporary, &self.path)?;
+        Ok(())
+    }
+}
+
+impl GameStore for JsonFileStore {
+    fn save_game(&mut self, game: &GameRecord) -> Result<u64, StoreError> {
+        let id = self.data.games.last().map_or(1, |last| last.id + 1);
+        self.data.games.push(GameRecord { id, ..game.clone() });
+        self.write()?;
+        Ok(id)
+    }
+
+    fn load_game(&self, id: u64) -> Result<Option<GameRecord>, StoreError> {
+        Ok(self.data.games.iter().find(|game| game.id == id).cloned())
+    }
+
+    fn load_history(&self, name: &str, limit: usize) -> Result<Vec<GameRecord>, StoreError> {
+        let games = self.data.games.iter().rev();
+        Ok(games
+            .filter(|game| game.maker == name || game.breaker == name)
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+}
+
+impl StatsStore for JsonFileStore {
+    fn append_result(&mut self, game: &GameRecord) -> Result<(), StoreError> {
+        let (winner, loser) = game.players();
+        let players = &mut self.data.players;
+        let mut stats = |name: &str| {
+            players
+                .remove(name)
+                .unwrap_or_else(|| PlayerStats::new(name))
+        };
+        let (mut winner, mut loser) = (stats(winner), stats(loser));
+        update(&mut winner, &mut loser);
+        for player in [winner, loser] {
+            players.insert(player.name.clone(), player);
+        }
+        self.write()
+    }
+
+    fn stats(&self, name: &str) -> Result<Option<PlayerStats>, StoreError> {
+        Ok(self.data.players.get(name).cloned())
+    }
+
+    fn leaderboard(&self, limit: usize) -> Result<Vec<PlayerStats>, StoreError> {
+        let mut players: Vec<PlayerStats> = self.data.players.values().cloned().collect();
+        // Sorted by name already, which breaks the ties like in SQLite.
+        players.sort_by_key(|player| Reverse((player.rating, player.won)));
+        players.truncate(limit);
+        Ok(players)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS players (
+        name TEXT PRIMARY KEY,
+        rating INTEGER NOT NULL,
+        played INTEGER NOT NULL,
+        won INTEGER NOT NULL,
+        streak INTEGER NOT NULL,
+        best_streak INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS games (
+        id INTEGER PRIMARY KEY,
+        maker TEXT NOT NULL,
+        breaker TEXT NOT NULL,
+        max_round INTEGER NOT NULL,
+        won_in INTEGER,
+        finished_at INTEGER NOT NULL
+    );
+";
+
+/// The changes to `SCHEMA` since the first version, by `user_version`.
+#[cfg(feature = "sqlite")]
+const MIGRATIONS: &[&str] = &["ALTER TABLE games ADD COLUMN transcript TEXT NOT NULL DEFAULT ''"];
+
+#[cfg(feature = "sqlite")]
+const GAME_COLUMNS: &str = "id, maker, breaker, max_round, won_in, finished_at, transcript";
+
+/// A SQLite database, with the `sqlite` feature.
+#[cfg(feature = "sqlite")]
+pub struct SqliteStore {
+    connection: Connection,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStore {
+    /// Opens the database at `path`, creating it if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, StoreError> {
+        SqliteStore::with_connection(Connection::open(path)?)
+    }
+
+    /// A database which lasts as long as the store, e.g. for tests.
+    pub fn in_memory() -> Result<Self, StoreError> {
+        SqliteStore::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, StoreError> {
+        connection.execute_batch(SCHEMA)?;
+        let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            connection.execute_batch(migration)?;
+            connection.pragma_update(None, "user_version", i + 1)?;
+        }
+        Ok(SqliteStore { connection })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl GameStore for SqliteStore {
+    fn save_game(&mut self, game: &GameRecord) -> Result<u64, StoreError> {
+        self.connection.execute(
+            "INSERT INTO games (maker, breaker, max_round, won_in, finished_at, transcript)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                game.maker,
+                game.breaker,
+                game.max_round as i64,
+                game.won_in.map(|rounds| rounds as i64),
+                game.finished_at as i64,
+                game.transcript,
+            ],
+        )?;
+        Ok(self.connection.last_insert_rowid() as u64)
+    }
+
+    fn load_game(&self, id: u64) -> Result<Option<GameRecord>, StoreError> {
+        let game = self
+            .connection
+            .query_row(
+                &format!("SELECT {} FROM games WHERE id = ?1", GAME_COLUMNS),
+                [id as i64],
+                game_record,
+            )
+            .optional()?;
+        Ok(game)
+    }
+
+    fn load_history(&self, name: &str, limit: usize) -> Result<Vec<GameRecord>, StoreError> {
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT {} FROM games
+             WHERE maker = ?1 OR breaker = ?1 ORDER BY id DESC LIMIT ?2",
+            GAME_COLUMNS
+        ))?;
+        let games = statement.query_map(params![name, limit as i64], game_record)?;
+        Ok(games.collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl StatsStore for SqliteStore {
+    fn append_result(&mut self, game: &GameRecord) -> Result<(), StoreError> {
+        let transaction = self.connection.transaction()?;
+        let (winner, loser) = game.players();
+        let stats = |name: &str| {
+            Ok::<_, StoreError>(
+                stats(&transaction, name)?.unwrap_or_else(|| PlayerStats::new(name)),
+            )
+        };
+        let (mut winner, mut loser) = (stats(winner)?, stats(loser)?);
+        update(&mut winner, &mut loser);
+        for player in [winner, loser] {
+            transaction.execute(
+                "INSERT OR REPLACE INTO players
+                 (name, rating, played, won, streak, best_streak)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    player.name,
+                    player.rating,
+                    player.played as i64,
+                    player.won as i64,
+                    player.streak as i64,
+                    player.best_streak as i64,
+                ],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn stats(&self, name: &str) -> Result<Option<PlayerStats>, StoreError> {
+        stats(&self.connection, name)
+    }
+
+    fn leaderboard(&self, limit: usize) -> Result<Vec<PlayerStats>, StoreError> {
+        let mut statement = self.connection.prepare(
+            "SELECT name, rating, played, won, streak, best_streak FROM players
+             ORDER BY rating DESC, won DESC, name LIMIT ?1",
+        )?;
+        let players = statement.query_map([limit as i64], player_stats)?;
+        Ok(players.collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn stats(connection: &Connection, name: &str) -> Result<Option<PlayerStats>, StoreError> {
+    let stats = connection
+        .query_row(
+            "SELECT name, rating, played, won, streak, best_streak FROM players
+             WHERE name = ?1",
+            [name],
+            player_stats,
+        )
+        .optional()?;
+    Ok(stats)
+}
+
+#[cfg(feature = "sqlite")]
+fn game_record(row: &rusqlite::Row) -> rusqlite::Result<GameRecord> {
+    Ok(GameRecord {
+        id: row.get::<_, i64>(0)? as u64,
+        maker: row.get(1)?,
+        breaker: row.get(2)?,
+        max_round: row.get::<_, i64>(3)? as usize,
+        won_in: row.get::<_, Option<i64>>(4)?.map(|rounds| rounds as usize),
+        finished_at: row.get::<_, i64>(5)? as u64,
+        transcript: row.get(6)?,
+    })
+}
+
+#[cfg(feature = "sqlite")]
+fn player_stats(row: &rusqlite::Row) -> rusqlite::Result<PlayerStats> {
+    Ok(PlayerStats {
+        name: row.get(0)?,
+        rating: row.get(1)?,
+        played: row.get::<_, i64>(2)? as usize,
+        won: row.get::<_, i64>(3)? as usize,
+        streak: row.get::<_, i64>(4)? as usize,
+        best_streak: row.get::<_, i64>(5)? as usize,
+    })
+}
+
+#[cfg(test)]
+mod test_store {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    fn game(maker: &str, breaker: &str, won_in: Option<usize>) -> GameRecord {
+        GameRecord {
+            id: 0,
+            maker: maker.to_string(),
+            breaker: breaker.to_string(),
+            max_round: 10,
+            won_in,
+            finished_at: 0,
+            transcript: String::new(),
+        }
+    }
+
+    /// Plays the same games with any store.
+    fn records_games(store: &mut dyn Store) {
+        let won = GameRecord {
+            transcript: "AABB 1 0\nABCD 4 0\n".to_string(),
+            ..game("alice", "bob", Some(5))
+        };
+        assert_eq!(store.record(&won).unwrap(), 1);
+        assert_eq!(store.record(&game("carol", "bob", Some(4))).unwrap(), 2);
+        assert_eq!(store.record(&game("bob", "alice", None)).unwrap(), 3);
+        assert_eq!(
+            store.load_game(1).unwrap(),
+            Some(GameRecord {
+                id: 1,
+                ..won.clone()
+            })
+        );
+        assert_eq!(store.load_game(4).unwrap(), None);
+
+        let bob = store.stats("bob").unwrap().unwrap();
+        assert_eq!(
+            (bob.played, bob.won, bob.streak, bob.best_streak),
+            (3, 3, 3, 3)
+        );
+        let alice = store.stats("alice").unwrap().unwrap();
+        assert_eq!((alice.played, alice.won, alice.streak), (2, 0, 0));
+        assert!(alice.rating < INITIAL_RATING && INITIAL_RATING < bob.rating);
+        assert_eq!(store.stats("dave").unwrap(), None);
+
+        let leaderboard = store.leaderboard(2).unwrap();
+        let names: Vec<&str> = leaderboard.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["bob", "carol"]);
+        let history = store.load_history("alice", 10).unwrap();
+        assert_eq!(
+            history,
+            [
+                GameRecord {
+                    id: 3,
+                    ..game("bob", "alice", None)
+                },
+                GameRecord { id: 1, ..won }
+            ]
+        );
+    }
+
+    #[test]
+    fn json_file() {
+        let path = env::temp_dir().join(format!("mastermind-store-{}.json", process::id()));
+        records_games(&mut JsonFileStore::open(&path).unwrap());
+        let reopened = open(&path).unwrap();
+        assert_eq!(reopened.stats("carol").unwrap().unwrap().played, 1);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite() {
+        records_games(&mut SqliteStore::in_memory().unwrap());
+    }
+
+    #[test]
+    fn transcripts() {
+        let rounds = parse_transcript("AABB 1 0\n\nABCD 4 0\n").unwrap();
+        assert_eq!(transcript(&rounds), "AABB 1 0\nABCD 4 0\n");
+        assert_eq!(won_in(&rounds), Some(2));
+        assert_eq!(won_in(&rounds[..1]), None);
+        assert_eq!(
+            parse_transcript("AABB 1 0\nABCD 3 1"),
+            Err("line 2: not a round: ABCD 3 1".to_string())
+        );
+        // Rounds are read up to the first error only when asked for.
+        let mut rounds = transcript_rounds("AABB 1 0\nABCD 1 0 0\n");
+        assert!(rounds.next().unwrap().is_ok());
+        assert_eq!(
+            rounds.next(),
+            Some(Err("line 2: not a round: ABCD 1 0 0".to_string()))
+        );
+    }
+
+    #[test]
+    fn rates() {
+        assert_eq!(rate(1500, 1500), (1516, 1484));
+        // Beating a much weaker player gains little.
+        let (strong, weak) = rate(2000, 1200);
+        assert!(strong - 2000 < 2 && 1200 - weak < 2);
+    }
+}
@@ -0,0 +1,26 @@
+//! The synchronization primitives of the crate, taken from loom instead of
+//! the standard library when built with `--cfg loom`, so that tests can
+//! check every interleaving of the threads sharing the server's state:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test --release --features net --lib test_loom
+//! ```
+
+use std::sync::PoisonError;
+
+#[cfg(all(loom, feature = "net"))]
+pub(crate) use loom::sync::{mpsc, Arc};
+#[cfg(loom)]
+pub(crate) use loom::sync::{Mutex, MutexGuard};
+#[cfg(all(not(loom), feature = "net"))]
+pub(crate) use std::sync::{mpsc, Arc};
+#[cfg(not(loom))]
+pub(crate) use std::sync::{Mutex, MutexGuard};
+
+/// Locks `mutex`, even if a thread panicked while holding it: the state the
+/// crate keeps behind a mutex is valid between any two of its statements,
+/// so one panicking caller, e.g. in `SessionManager::with`, does not take
+/// the others down.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,67 @@
+//! The scores of every pair of codes, computed once, for solvers to look
+//! scores up instead of computing them (see `solver::Backend`).
+//!
+//! The table covers the codes of the classic rules, which include the codes
+//! of any other rules. It takes about 1.7 MB.
+
+use std::sync::OnceLock;
+
+use crate::{Code, Score, Scorer};
+
+pub struct TableScorer {
+    /// The index of the score of each guess for each code, both indexed by
+    /// `Code::index`, as in `indices[code * Code::COUNT + guess]`.
+    indices: Vec<u8>,
+}
+
+impl TableScorer {
+    /// Scores every pair of codes, which takes a few dozen milliseconds.
+    pub fn new() -> Self {
+        let codes = Code::all();
+        let mut indices = Vec::with_capacity(Code::COUNT * Code::COUNT);
+        for &code in &codes {
+            let scorer = Scorer::new(code);
+            indices.extend(codes.iter().map(|&guess| scorer.score(guess).index() as u8));
+        }
+        TableScorer { indices }
+    }
+
+    /// A table built the first time it is asked for, and then shared.
+    pub fn shared() -> &'static TableScorer {
+        static TABLE: OnceLock<TableScorer> = OnceLock::new();
+        TABLE.get_or_init(TableScorer::new)
+    }
+
+    /// The score of `guess` if `code` is the code, like `Scorer::score`.
+    pub fn score(&self, code: Code, guess: Code) -> Score {
+        Score::from_index(self.score_index(code, guess))
+    }
+
+    /// The index of the score of `guess` for `code`, as `Score::index` gives.
+    pub(crate) fn score_index(&self, code: Code, guess: Code) -> usize {
+        self.indices[code.index() * Code::COUNT + guess.index()] as usize
+    }
+}
+
+impl Default for TableScorer {
+    fn default() -> Self {
+        TableScorer::new()
+    }
+}
+
+#[cfg(test)]
+mod test_table {
+    use super::*;
+
+    #[test]
+    fn scores_like_the_scorer() {
+        let codes = Code::all();
+        let table = TableScorer::shared();
+        for &code in &codes {
+            let scorer = Scorer::new(code);
+            for &guess in &codes {
+                assert_eq!(table.score(code, guess), scorer.score(guess));
+            }
+        }
+    }
+}
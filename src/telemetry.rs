@@ -0,0 +1,170 @@
+//! Metrics about the games played, with the `metrics` feature, recorded
+//! through the `metrics` facade: servers install the exporter of their
+//! choice, and may call `describe` to document the metrics below.
+//!
+//! Both `Game` and `GameSession` record them, so that they cover the
+//! network, HTTP and gRPC servers alike.
+
+use std::time::Duration;
+
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram};
+
+pub const GAMES_STARTED: &str = "mastermind_games_started_total";
+/// Labelled with the `outcome` of the game: `won`, `lost`, `forfeited`,
+/// `rejected`, `aborted` or `abandoned`.
+pub const GAMES_FINISHED: &str = "mastermind_games_finished_total";
+pub const ROUNDS_PER_GAME: &str = "mastermind_rounds_per_game";
+pub const MOVE_LATENCY: &str = "mastermind_move_latency_seconds";
+pub const ACTIVE_SESSIONS: &str = "mastermind_active_sessions";
+
+/// Describes the metrics to the installed recorder.
+pub fn describe() {
+    describe_counter!(GAMES_STARTED, "Games started.");
+    describe_counter!(GAMES_FINISHED, "Games finished, by outcome.");
+    describe_histogram!(
+        ROUNDS_PER_GAME,
+        "Guesses played in each finished game, including the last one."
+    );
+    describe_histogram!(
+        MOVE_LATENCY,
+        metrics::Unit::Seconds,
+        "Time taken by code breakers to guess."
+    );
+    describe_gauge!(ACTIVE_SESSIONS, "Games being played.");
+}
+
+pub(crate) fn game_started() {
+    counter!(GAMES_STARTED).increment(1);
+    gauge!(ACTIVE_SESSIONS).increment(1.0);
+}
+
+/// Only games which were played to their end record their `rounds`.
+pub(crate) fn game_finished(outcome: &'static str, rounds: Option<usize>) {
+    counter!(GAMES_FINISHED, "outcome" => outcome).increment(1);
+    if let Some(rounds) = rounds {
+        histogram!(ROUNDS_PER_GAME).record(rounds as f64);
+    }
+    gauge!(ACTIVE_SESSIONS).decrement(1.0);
+}
+
+pub(crate) fn moved(latency: Duration) {
+    histogram!(MOVE_LATENCY).record(latency);
+}
+
+#[cfg(test)]
+mod test_telemetry {
+    use std::num::NonZeroUsize;
+
+    use std::collections::HashMap;
+    use std::future::Future;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+
+    use metrics::{
+        Counter, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+    };
+
+    use super::*;
+    use crate::rules::Rules;
+    use crate::session::GameSession;
+    use crate::solver::Solver;
+    use crate::testutil::{DeterministicCodeMaker, ScriptedCodeBreaker};
+    use crate::Game;
+
+    /// Counters and gauges by name and labels, ignoring histograms.
+    #[derive(Default)]
+    struct Values(Mutex<HashMap<String, Arc<AtomicU64>>>);
+
+    impl Values {
+        fn register(&self, key: &Key) -> Arc<AtomicU64> {
+            let labels = key.labels().map(|label| format!(" {}", label.value()));
+            let name = key.name().to_string() + &labels.collect::<String>();
+            self.0.lock().unwrap().entry(name).or_default().clone()
+        }
+
+        fn counter(&self, name: &str) -> u64 {
+            let values = self.0.lock().unwrap();
+            values.get(name).map_or(0, |v| v.load(Ordering::Relaxed))
+        }
+
+        fn gauge(&self, name: &str) -> f64 {
+            let values = self.0.lock().unwrap();
+            values
+                .get(name)
+                .map_or(0.0, |v| f64::from_bits(v.load(Ordering::Relaxed)))
+        }
+    }
+
+    impl Recorder for Values {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::from_arc(self.register(key))
+        }
+
+        fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::from_arc(self.register(key))
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn records() {
+        let values = Values::default();
+        metrics::with_local_recorder(&values, || {
+            let code = "ABCD".parse().unwrap();
+            let mut solver = Solver::new();
+            Game::new(
+                NonZeroUsize::new(10).unwrap(),
+                &DeterministicCodeMaker::new(code),
+                &mut solver,
+            )
+            .play();
+
+            let mut session =
+                GameSession::new(Rules::default(), NonZeroUsize::new(1).unwrap(), code).unwrap();
+            assert_eq!(values.gauge(ACTIVE_SESSIONS), 1.0);
+            session.guess("AAAA".parse().unwrap()).unwrap();
+            drop(GameSession::new(Rules::default(), NonZeroUsize::new(1).unwrap(), code).unwrap());
+        });
+        assert_eq!(values.counter(GAMES_STARTED), 3);
+        assert_eq!(values.counter(&format!("{} won", GAMES_FINISHED)), 1);
+        assert_eq!(values.counter(&format!("{} lost", GAMES_FINISHED)), 1);
+        assert_eq!(values.counter(&format!("{} abandoned", GAMES_FINISHED)), 1);
+        assert_eq!(values.gauge(ACTIVE_SESSIONS), 0.0);
+    }
+
+    #[test]
+    fn records_async_games_alike() {
+        let values = Values::default();
+        metrics::with_local_recorder(&values, || {
+            let code_maker = DeterministicCodeMaker::new("ABCD".parse().unwrap());
+            let rules = Rules::default().with_strict(true);
+            let rounds = NonZeroUsize::new(10).unwrap();
+            for guesses in [vec![], vec!["AAAA".parse().unwrap(); 2]] {
+                let mut code_breaker = ScriptedCodeBreaker::new(guesses.clone());
+                let outcome = Game::new(rounds, &code_maker, &mut code_breaker)
+                    .with_rules(rules)
+                    .play();
+                let mut code_breaker = ScriptedCodeBreaker::new(guesses);
+                let game = Game::new(rounds, &code_maker, &mut code_breaker)
+                    .with_rules(rules)
+                    .play_async();
+                let mut context = Context::from_waker(Waker::noop());
+                let Poll::Ready(async_outcome) = std::pin::pin!(game).poll(&mut context) else {
+                    panic!("scripted players do not wait");
+                };
+                assert_eq!(async_outcome, outcome);
+            }
+        });
+        assert_eq!(values.counter(&format!("{} forfeited", GAMES_FINISHED)), 2);
+        assert_eq!(values.counter(&format!("{} rejected", GAMES_FINISHED)), 2);
+        assert_eq!(values.gauge(ACTIVE_SESSIONS), 0.0);
+    }
+}
@@ -0,0 +1,184 @@
+//! Test doubles for code makers and code breakers, with the `testutil`
+//! feature.
+//!
+//! To record the moves of players, wrap them in `record::Recording`.
+
+use crate::{Code, CodeBreaker, CodeMaker, CodePeg, Score, SIZE};
+
+/// A code maker which always makes the same code.
+pub struct DeterministicCodeMaker {
+    code: Code,
+}
+
+impl DeterministicCodeMaker {
+    pub fn new(code: Code) -> Self {
+        DeterministicCodeMaker { code }
+    }
+}
+
+impl CodeMaker for DeterministicCodeMaker {
+    fn make_code(&self) -> Code {
+        self.code
+    }
+}
+
+/// A code breaker which plays a fixed list of guesses and records what happens.
+///
+/// Once the list is exhausted, the last guess is played again. With an empty
+/// list, it forfeits.
+pub struct ScriptedCodeBreaker {
+    guesses: Vec<Code>,
+    pub scores: Vec<Score>,
+    pub has_won: bool,
+    pub has_lost: bool,
+}
+
+impl ScriptedCodeBreaker {
+    pub fn new(guesses: Vec<Code>) -> Self {
+        ScriptedCodeBreaker {
+            guesses,
+            scores: Vec::new(),
+            has_won: false,
+            has_lost: false,
+        }
+    }
+
+    pub fn num_rounds(&self) -> usize {
+        self.scores.len()
+    }
+}
+
+impl CodeBreaker for ScriptedCodeBreaker {
+    fn guess_code(&self) -> Code {
+        let index = self.scores.len().min(self.guesses.len().saturating_sub(1));
+        match self.guesses.get(index) {
+            Some(&guess) => guess,
+            // Ignored by the game, since the code breaker forfeits.
+            None => Code::new([CodePeg::ALL[0]; SIZE]),
+        }
+    }
+
+    fn set_score(&mut self, score: Score) {
+        self.scores.push(score);
+    }
+
+    fn loses(&mut self) {
+        self.has_lost = true;
+    }
+
+    fn wins(&mut self, _rounds: usize) {
+        self.has_won = true;
+    }
+
+    fn forfeited(&self) -> bool {
+        self.guesses.is_empty()
+    }
+}
+
+/// Scores the guesses of a code breaker with a fixed list of scores,
+/// whatever the guesses, to test how code breakers cope with a code maker
+/// who scores wrong, e.g. contradictorily.
+pub struct ScriptedScorer {
+    scores: Vec<Score>,
+}
+
+impl ScriptedScorer {
+    pub fn new(scores: Vec<Score>) -> Self {
+        ScriptedScorer { scores }
+    }
+
+    /// Gives the scores to `code_breaker` in order, and returns the rounds
+    /// played. It stops after a winning score, once the scores run out, or
+    /// when the code breaker forfeits.
+    pub fn play<U: CodeBreaker>(&self, code_breaker: &mut U) -> Vec<(Code, Score)> {
+        let mut rounds = Vec::new();
+        for &score in &self.scores {
+            let guess = code_breaker.guess_code();
+            if code_breaker.forfeited() {
+                break;
+            }
+            code_breaker.set_score(score);
+            rounds.push((guess, score));
+            if score.matches() == SIZE {
+                code_breaker.wins(rounds.len());
+                break;
+            }
+        }
+        rounds
+    }
+}
+
+/// A player which panics, to test how its host copes with bugs.
+///
+/// As a code maker, it panics whenever it makes a code. As a code breaker,
+/// it plays a script like `ScriptedCodeBreaker`, and panics on the guess
+/// after the last one.
+pub struct FailingPlayer {
+    script: ScriptedCodeBreaker,
+}
+
+impl FailingPlayer {
+    pub fn new(guesses: Vec<Code>) -> Self {
+        FailingPlayer {
+            script: ScriptedCodeBreaker::new(guesses),
+        }
+    }
+
+    /// The script played so far, with its scores.
+    pub fn get_ref(&self) -> &ScriptedCodeBreaker {
+        &self.script
+    }
+}
+
+impl CodeMaker for FailingPlayer {
+    fn make_code(&self) -> Code {
+        panic!("the code maker fails");
+    }
+}
+
+impl CodeBreaker for FailingPlayer {
+    fn guess_code(&self) -> Code {
+        assert!(
+            self.script.num_rounds() < self.script.guesses.len(),
+            "the code breaker fails"
+        );
+        self.script.guess_code()
+    }
+
+    fn set_score(&mut self, score: Score) {
+        self.script.set_score(score);
+    }
+
+    fn loses(&mut self) {
+        self.script.loses();
+    }
+
+    fn wins(&mut self, rounds: usize) {
+        self.script.wins(rounds);
+    }
+}
+
+#[cfg(test)]
+mod test_testutil {
+    use super::*;
+    use crate::solver::Solver;
+
+    #[test]
+    fn scripts_scores() {
+        let score = |matches, presents| Score::from_counts(matches, presents).unwrap();
+        let mut code_breaker = ScriptedCodeBreaker::new(vec!["AABB".parse().unwrap()]);
+        let scorer = ScriptedScorer::new(vec![score(0, 0), score(4, 0), score(1, 0)]);
+        let rounds = scorer.play(&mut code_breaker);
+        assert_eq!(rounds.len(), 2);
+        assert!(code_breaker.has_won);
+
+        // Each guess rules out its colors, until none is left.
+        let mut solver = Solver::new();
+        let opening = solver.guess_code();
+        let scorer = ScriptedScorer::new(vec![score(0, 0); CodePeg::ALL.len() + 1]);
+        let rounds = scorer.play(&mut solver);
+        assert_eq!(rounds[0], (opening, score(0, 0)));
+        assert!(rounds.len() <= CodePeg::ALL.len());
+        assert!(solver.forfeited());
+    }
+}
@@ -0,0 +1,176 @@
+//! Deadlines for the moves of async players, with the `async` feature, so
+//! that servers can contain slow bots.
+//!
+//! Only players which await can be interrupted: a sync player, playing
+//! through the blanket impls, blocks until it returns.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use tokio::time;
+
+use crate::rules::Rules;
+use crate::{AsyncCodeBreaker, AsyncCodeMaker, Code, CodeMaker, RandomCodeMaker, Score};
+
+/// What a code breaker does when it runs out of time for a guess.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Fallback {
+    /// Lose the game.
+    #[default]
+    Forfeit,
+    /// Play a random code allowed by the rules instead.
+    RandomGuess,
+}
+
+/// A player with a deadline for each move.
+///
+/// A code maker which runs out of time plays a random code allowed by the
+/// rules. A code breaker falls back as set by its `Fallback`; it is not told
+/// the scores of the guesses played in its stead. The random codes are
+/// drawn from a seed, so that a game with timeouts can be replayed.
+pub struct WithTimeout<P> {
+    player: P,
+    deadline: Duration,
+    fallback: Fallback,
+    random: RandomCodeMaker,
+    timeouts: AtomicUsize,
+    forfeited: AtomicBool,
+    // Whether the last guess was played in place of the player.
+    replaced: AtomicBool,
+}
+
+impl<P> WithTimeout<P> {
+    pub fn new(player: P, deadline: Duration, fallback: Fallback, rules: Rules, seed: u64) -> Self {
+        WithTimeout {
+            player,
+            deadline,
+            fallback,
+            random: RandomCodeMaker::seeded(rules, seed),
+            timeouts: AtomicUsize::new(0),
+            forfeited: AtomicBool::new(false),
+            replaced: AtomicBool::new(false),
+        }
+    }
+
+    /// The number of moves the player ran out of time for.
+    pub fn timeouts(&self) -> usize {
+        self.timeouts.load(Ordering::Relaxed)
+    }
+
+    pub fn into_inner(self) -> P {
+        self.player
+    }
+
+    fn random_code(&self) -> Code {
+        self.timeouts.fetch_add(1, Ordering::Relaxed);
+        CodeMaker::make_code(&self.random)
+    }
+}
+
+impl<P: AsyncCodeMaker + Sync> AsyncCodeMaker for WithTimeout<P> {
+    async fn make_code(&self) -> Code {
+        match time::timeout(self.deadline, self.player.make_code()).await {
+            Ok(code) => code,
+            Err(_) => self.random_code(),
+        }
+    }
+}
+
+impl<P: AsyncCodeBreaker + Send + Sync> AsyncCodeBreaker for WithTimeout<P> {
+    async fn guess_code(&self) -> Code {
+        let guess = time::timeout(self.deadline, self.player.guess_code()).await;
+        self.replaced.store(guess.is_err(), Ordering::Relaxed);
+        match guess {
+            Ok(guess) => guess,
+            Err(_) => {
+                if self.fallback == Fallback::Forfeit {
+                    self.forfeited.store(true, Ordering::Relaxed);
+                }
+                self.random_code()
+            }
+        }
+    }
+
+    async fn set_score(&mut self, score: Score) {
+        if !*self.replaced.get_mut() {
+            self.player.set_score(score).await;
+        }
+    }
+
+    async fn loses(&mut self) {
+        self.player.loses().await;
+    }
+
+    async fn wins(&mut self, rounds: usize) {
+        self.player.wins(rounds).await;
+    }
+
+    fn forfeited(&self) -> bool {
+        self.forfeited.load(Ordering::Relaxed) || self.player.forfeited()
+    }
+}
+
+#[cfg(test)]
+mod test_timeout {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+    use crate::testutil::{DeterministicCodeMaker, ScriptedCodeBreaker};
+    use crate::{CodeBreaker, Game};
+
+    /// A code breaker which takes a second for each guess.
+    struct SlowCodeBreaker(ScriptedCodeBreaker);
+
+    impl AsyncCodeBreaker for SlowCodeBreaker {
+        async fn guess_code(&self) -> Code {
+            time::sleep(Duration::from_secs(1)).await;
+            CodeBreaker::guess_code(&self.0)
+        }
+
+        async fn set_score(&mut self, score: Score) {
+            CodeBreaker::set_score(&mut self.0, score)
+        }
+
+        async fn loses(&mut self) {
+            CodeBreaker::loses(&mut self.0)
+        }
+    }
+
+    fn play(fallback: Fallback) -> WithTimeout<SlowCodeBreaker> {
+        let code_maker = DeterministicCodeMaker::new("ABCD".parse().unwrap());
+        let code_breaker = SlowCodeBreaker(ScriptedCodeBreaker::new(vec!["ABCD".parse().unwrap()]));
+        let deadline = Duration::from_millis(10);
+        let mut code_breaker =
+            WithTimeout::new(code_breaker, deadline, fallback, Rules::default(), 7);
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(
+                Game::new(
+                    NonZeroUsize::new(3).unwrap(),
+                    &code_maker,
+                    &mut code_breaker,
+                )
+                .play_async(),
+            );
+        code_breaker
+    }
+
+    #[test]
+    fn forfeits() {
+        let code_breaker = play(Fallback::Forfeit);
+        assert_eq!(code_breaker.timeouts(), 1);
+        let code_breaker = code_breaker.into_inner().0;
+        assert!(code_breaker.has_lost);
+        assert!(code_breaker.scores.is_empty());
+    }
+
+    #[test]
+    fn plays_random_guesses() {
+        let code_breaker = play(Fallback::RandomGuess);
+        // None of the guesses drawn from the seed breaks the code.
+        assert_eq!(code_breaker.timeouts(), 3);
+        assert!(code_breaker.into_inner().0.scores.is_empty());
+    }
+}
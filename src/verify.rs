@@ -0,0 +1,236 @@
+//! Invariants every scoring of Mastermind satisfies, to check other scorers
+//! against, for instance in property tests.
+//!
+//! Each check takes a scoring function, called as `score(secret, guess)`,
+//! and a secret and a guess to try it on. `check` runs them all. The
+//! symmetries are checked for one swap and one rotation, of the positions or
+//! of the colors: since these generate every permutation, checking them for
+//! every pair of codes checks every permutation.
+
+use std::fmt;
+
+use crate::{Code, CodePeg, Score, ScorePeg, SIZE};
+
+/// An invariant which a scoring does not satisfy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Violation {
+    /// The winning score was given to different codes, or not to equal ones.
+    Win,
+    /// The score has every peg but one matching, and the last one present,
+    /// which no pair of codes can give.
+    Impossible,
+    /// Swapping the secret and the guess changed the score.
+    Asymmetric,
+    /// Reordering the positions of both codes alike changed the score.
+    Positions,
+    /// Reordering the guess alone changed the number of pegs in common.
+    Common,
+    /// Renaming the colors of both codes alike changed the score.
+    Colors,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::Win => write!(f, "the winning score does not match equal codes"),
+            Violation::Impossible => write!(f, "the score is impossible"),
+            Violation::Asymmetric => write!(f, "the score changes with the roles of the codes"),
+            Violation::Positions => write!(f, "the score changes with the order of the pegs"),
+            Violation::Common => {
+                write!(f, "the pegs in common change with the order of the guess")
+            }
+            Violation::Colors => write!(f, "the score changes with the names of the colors"),
+        }
+    }
+}
+
+impl std::error::Error for Violation {}
+
+/// Checks every invariant for `secret` and `guess`, and returns the first
+/// one which does not hold.
+pub fn check(
+    score: impl Fn(Code, Code) -> Score,
+    secret: Code,
+    guess: Code,
+) -> Result<(), Violation> {
+    wins_only_when_equal(&score, secret, guess)?;
+    is_possible(&score, secret, guess)?;
+    is_symmetric(&score, secret, guess)?;
+    ignores_positions(&score, secret, guess)?;
+    counts_common_pegs(&score, secret, guess)?;
+    ignores_colors(&score, secret, guess)
+}
+
+/// The score has every peg matching if and only if the codes are equal.
+pub fn wins_only_when_equal(
+    score: impl Fn(Code, Code) -> Score,
+    secret: Code,
+    guess: Code,
+) -> Result<(), Violation> {
+    let won = score(secret, guess) == Score::new([Some(ScorePeg::Match); SIZE]);
+    if won != (secret == guess) {
+        return Err(Violation::Win);
+    }
+    Ok(())
+}
+
+/// The score is one which some pair of codes gives: all but one matching
+/// pegs leave no peg to be present.
+pub fn is_possible(
+    score: impl Fn(Code, Code) -> Score,
+    secret: Code,
+    guess: Code,
+) -> Result<(), Violation> {
+    let score = score(secret, guess);
+    if score.matches() + score.presents() > SIZE
+        || (score.matches() == SIZE - 1 && score.presents() > 0)
+    {
+        return Err(Violation::Impossible);
+    }
+    Ok(())
+}
+
+/// The score is the same with the secret and the guess swapped.
+pub fn is_symmetric(
+    score: impl Fn(Code, Code) -> Score,
+    secret: Code,
+    guess: Code,
+) -> Result<(), Violation> {
+    if score(secret, guess) != score(guess, secret) {
+        return Err(Violation::Asymmetric);
+    }
+    Ok(())
+}
+
+/// The score is the same with the positions of both codes reordered alike.
+pub fn ignores_positions(
+    score: impl Fn(Code, Code) -> Score,
+    secret: Code,
+    guess: Code,
+) -> Result<(), Violation> {
+    let expected = score(secret, guess);
+    for reorder in [swap_positions, rotate_positions] {
+        if score(reorder(secret), reorder(guess)) != expected {
+            return Err(Violation::Positions);
+        }
+    }
+    Ok(())
+}
+
+/// The number of pegs in common, matching or present, only depends on the
+/// colors of the codes, not on the order of the guess.
+pub fn counts_common_pegs(
+    score: impl Fn(Code, Code) -> Score,
+    secret: Code,
+    guess: Code,
+) -> Result<(), Violation> {
+    let common = |score: Score| score.matches() + score.presents();
+    let expected = common(score(secret, guess));
+    for reorder in [swap_positions, rotate_positions] {
+        if common(score(secret, reorder(guess))) != expected {
+            return Err(Violation::Common);
+        }
+    }
+    Ok(())
+}
+
+/// The score is the same with the colors of both codes renamed alike.
+pub fn ignores_colors(
+    score: impl Fn(Code, Code) -> Score,
+    secret: Code,
+    guess: Code,
+) -> Result<(), Violation> {
+    let expected = score(secret, guess);
+    for rename in [swap_colors, rotate_colors] {
+        if score(recolor(secret, rename), recolor(guess, rename)) != expected {
+            return Err(Violation::Colors);
+        }
+    }
+    Ok(())
+}
+
+fn swap_positions(code: Code) -> Code {
+    let mut pegs = code.pegs();
+    pegs.swap(0, 1);
+    Code::new(pegs)
+}
+
+fn rotate_positions(code: Code) -> Code {
+    let mut pegs = code.pegs();
+    pegs.rotate_left(1);
+    Code::new(pegs)
+}
+
+fn swap_colors(peg: CodePeg) -> CodePeg {
+    match peg {
+        CodePeg::A => CodePeg::B,
+        CodePeg::B => CodePeg::A,
+        peg => peg,
+    }
+}
+
+fn rotate_colors(peg: CodePeg) -> CodePeg {
+    CodePeg::ALL[(peg as usize + 1) % CodePeg::ALL.len()]
+}
+
+fn recolor(code: Code, rename: fn(CodePeg) -> CodePeg) -> Code {
+    Code::new(code.pegs().map(rename))
+}
+
+#[cfg(test)]
+mod test_verify {
+    use super::*;
+    use crate::Scorer;
+
+    fn scorer(secret: Code, guess: Code) -> Score {
+        Scorer::new(secret).score(guess)
+    }
+
+    #[test]
+    fn holds_for_the_scorer() {
+        let codes = Code::all();
+        for &secret in codes.iter().step_by(5) {
+            for &guess in codes.iter().step_by(7) {
+                assert_eq!(check(scorer, secret, guess), Ok(()), "{} {}", secret, guess);
+            }
+            assert_eq!(check(scorer, secret, secret), Ok(()));
+        }
+    }
+
+    #[test]
+    fn finds_broken_scorers() {
+        let a = Code::new([CodePeg::A, CodePeg::A, CodePeg::B, CodePeg::C]);
+        let b = Code::new([CodePeg::A, CodePeg::B, CodePeg::D, CodePeg::A]);
+        let first_peg = |secret: Code, guess: Code| {
+            let matches = usize::from(secret.pegs()[0] == guess.pegs()[0]);
+            Score::from_counts(matches, 0).unwrap()
+        };
+        assert_eq!(check(first_peg, a, a), Err(Violation::Win));
+
+        // Counts each peg of the guess found anywhere in the secret, so
+        // repeated pegs are counted too often.
+        let greedy = |secret: Code, guess: Code| {
+            let pegs = secret.pegs();
+            let matches = (0..SIZE).filter(|&i| pegs[i] == guess.pegs()[i]).count();
+            let found = guess.pegs().iter().filter(|peg| pegs.contains(peg)).count();
+            Score::from_counts(matches, found - matches).unwrap()
+        };
+        let repeated = Code::new([CodePeg::A; SIZE]);
+        let distinct = Code::new([CodePeg::A, CodePeg::B, CodePeg::C, CodePeg::D]);
+        assert_eq!(
+            check(greedy, distinct, repeated),
+            Err(Violation::Asymmetric)
+        );
+
+        // Ranks colors, which a renaming changes.
+        let ranked = |secret: Code, guess: Code| {
+            let score = scorer(secret, guess);
+            match guess.pegs()[0] {
+                CodePeg::A if secret != guess => Score::from_counts(0, 0).unwrap(),
+                _ => score,
+            }
+        };
+        assert_eq!(check(ranked, b, a), Err(Violation::Colors));
+    }
+}
@@ -0,0 +1,302 @@
+//! Bindings for browsers, with the `wasm` feature, so that a web page can
+//! play entirely on the client side. Build them with
+//! `wasm-pack build --features wasm`.
+//!
+//! Codes are passed as strings such as `"ABCD"`, and errors are thrown as
+//! JavaScript errors. The TypeScript definitions generated along name the
+//! strings each function expects, declared below.
+
+use std::num::NonZeroUsize;
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPES: &str = r#"
+/** One letter per peg, from A for the first color, e.g. "ABCD". */
+export type Code = string;
+export type State = "playing" | "won" | "lost";
+export type StrategyName = "first-candidate" | "minimax";
+"#;
+
+use crate::rules::Rules;
+use crate::solver::{self, Strategy};
+use crate::{session, Code, Scorer};
+
+fn code(s: &str) -> Result<Code, JsError> {
+    s.parse()
+        .map_err(|err: crate::ParseCodeError| JsError::new(&err.to_string()))
+}
+
+fn rules(colors: usize, duplicates: bool) -> Result<Rules, JsError> {
+    Rules::new(colors, duplicates).map_err(|err| JsError::new(&err.to_string()))
+}
+
+fn max_round(max_round: usize) -> Result<NonZeroUsize, JsError> {
+    NonZeroUsize::new(max_round).ok_or_else(|| JsError::new("a game needs at least one round"))
+}
+
+fn strategy(name: &str) -> Result<Strategy, JsError> {
+    match name {
+        "first-candidate" => Ok(Strategy::FirstCandidate),
+        "minimax" => Ok(Strategy::Minimax),
+        _ => Err(JsError::new(&format!("unknown strategy '{}'", name))),
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Score {
+    /// Pegs of the right color in the right place.
+    #[wasm_bindgen(readonly)]
+    pub matched: usize,
+    /// Pegs of the right color in the wrong place.
+    #[wasm_bindgen(readonly)]
+    pub present: usize,
+}
+
+impl From<crate::Score> for Score {
+    fn from(score: crate::Score) -> Self {
+        Score {
+            matched: score.matches(),
+            present: score.presents(),
+        }
+    }
+}
+
+/// The score of `guess` against `secret`.
+#[wasm_bindgen]
+pub fn score(
+    #[wasm_bindgen(unchecked_param_type = "Code")] secret: &str,
+    #[wasm_bindgen(unchecked_param_type = "Code")] guess: &str,
+) -> Result<Score, JsError> {
+    Ok(Scorer::new(code(secret)?).score(code(guess)?).into())
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Round {
+    guess: String,
+    score: Score,
+}
+
+#[wasm_bindgen]
+impl Round {
+    #[wasm_bindgen(getter, unchecked_return_type = "Code")]
+    pub fn guess(&self) -> String {
+        self.guess.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn score(&self) -> Score {
+        self.score
+    }
+}
+
+/// The number of codes still possible, and the guess a solver would play.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hint {
+    remaining: usize,
+    guess: Option<String>,
+}
+
+#[wasm_bindgen]
+impl Hint {
+    #[wasm_bindgen(getter)]
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// `undefined` if the scores contradict each other.
+    #[wasm_bindgen(getter, unchecked_return_type = "Code | undefined")]
+    pub fn guess(&self) -> Option<String> {
+        self.guess.clone()
+    }
+}
+
+/// A game against a random code, played one guess at a time.
+#[wasm_bindgen]
+pub struct GameSession {
+    session: session::GameSession,
+}
+
+#[wasm_bindgen]
+impl GameSession {
+    /// `strict` rules reject a guess played twice. `seed`, if given, draws
+    /// the code, so that the same seed hides the same code.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        colors: usize,
+        duplicates: bool,
+        max_round: usize,
+        strict: Option<bool>,
+        seed: Option<u64>,
+    ) -> Result<GameSession, JsError> {
+        let rules = rules(colors, duplicates)?.with_strict(strict.unwrap_or(false));
+        let max_round = self::max_round(max_round)?;
+        let session = match seed {
+            Some(seed) => session::GameSession::seeded(rules, max_round, seed),
+            None => session::GameSession::random(rules, max_round),
+        };
+        Ok(GameSession { session })
+    }
+
+    /// `undefined` if the game goes on until the code is broken.
+    #[wasm_bindgen(getter, js_name = maxRound)]
+    pub fn max_round(&self) -> Option<usize> {
+        self.session.max_round().map(NonZeroUsize::get)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn colors(&self) -> usize {
+        self.session.rules().colors()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn duplicates(&self) -> bool {
+        self.session.rules().duplicates()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn strict(&self) -> bool {
+        self.session.rules().strict()
+    }
+
+    #[wasm_bindgen(getter, unchecked_return_type = "State")]
+    pub fn state(&self) -> String {
+        match self.session.state() {
+            session::State::Playing => "playing",
+            session::State::Won => "won",
+            session::State::Lost => "lost",
+        }
+        .to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rounds(&self) -> Vec<Round> {
+        self.session
+            .rounds()
+            .iter()
+            .map(|&(guess, score)| Round {
+                guess: guess.to_string(),
+                score: score.into(),
+            })
+            .collect()
+    }
+
+    /// The code, once the game is over.
+    #[wasm_bindgen(getter, unchecked_return_type = "Code | undefined")]
+    pub fn secret(&self) -> Option<String> {
+        self.session.secret().map(|code| code.to_string())
+    }
+
+    /// Plays `guess` and returns its score.
+    pub fn guess(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "Code")] guess: &str,
+    ) -> Result<Score, JsError> {
+        let score = self
+            .session
+            .guess(code(guess)?)
+            .map_err(|err| JsError::new(&err.to_string()))?;
+        Ok(score.into())
+    }
+
+    /// What a solver playing with `strategy` makes of the rounds so far.
+    pub fn hint(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "StrategyName")] strategy: &str,
+    ) -> Result<Hint, JsError> {
+        let mut solver =
+            solver::Solver::with_rules(self.session.rules(), self::strategy(strategy)?);
+        for &(guess, score) in self.session.rounds() {
+            solver.record(guess, score);
+        }
+        Ok(Solver { solver }.hint())
+    }
+}
+
+/// A solver, which breaks codes chosen elsewhere.
+#[wasm_bindgen]
+pub struct Solver {
+    solver: solver::Solver,
+}
+
+#[wasm_bindgen]
+impl Solver {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        colors: usize,
+        duplicates: bool,
+        #[wasm_bindgen(unchecked_param_type = "StrategyName")] strategy: &str,
+    ) -> Result<Solver, JsError> {
+        Ok(Solver {
+            solver: solver::Solver::with_rules(
+                rules(colors, duplicates)?,
+                self::strategy(strategy)?,
+            ),
+        })
+    }
+
+    /// Keeps the codes which would give this score to `guess`.
+    pub fn record(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "Code")] guess: &str,
+        matched: usize,
+        present: usize,
+    ) -> Result<(), JsError> {
+        let score = crate::Score::from_counts(matched, present)
+            .ok_or_else(|| JsError::new("invalid score"))?;
+        self.solver.record(code(guess)?, score);
+        Ok(())
+    }
+
+    /// Up to `limit` of the codes still possible.
+    #[wasm_bindgen(unchecked_return_type = "Code[]")]
+    pub fn candidates(&self, limit: usize) -> Vec<String> {
+        let candidates = self.solver.candidates().iter().take(limit);
+        candidates.map(|code| code.to_string()).collect()
+    }
+
+    pub fn hint(&self) -> Hint {
+        let candidates = self.solver.candidates();
+        Hint {
+            remaining: candidates.len(),
+            guess: self.solver.guess().map(|code| code.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_wasm {
+    use super::*;
+
+    // Errors cannot be built outside of a browser, so only what succeeds is
+    // tested here.
+    #[test]
+    fn plays() {
+        let expected = Score {
+            matched: 1,
+            present: 2,
+        };
+        assert_eq!(score("ABCD", "AFBC").unwrap(), expected);
+
+        let mut session = GameSession::new(1, true, 3, Some(true), None).unwrap();
+        assert!(session.strict());
+        assert_eq!(
+            session.hint("minimax").unwrap().guess().as_deref(),
+            Some("AAAA")
+        );
+        assert_eq!(session.guess("AAAA").unwrap().matched, 4);
+        assert_eq!(session.state(), "won");
+        assert_eq!(session.secret().as_deref(), Some("AAAA"));
+        assert_eq!(session.rounds()[0].guess(), "AAAA");
+
+        let mut solver = Solver::new(6, true, "first-candidate").unwrap();
+        solver.record("AABB", 0, 0).unwrap();
+        let hint = solver.hint();
+        assert_eq!(hint.remaining(), 256);
+        assert_eq!(hint.guess().as_deref(), Some("CCCC"));
+        assert_eq!(solver.candidates(2), ["CCCC", "CCCD"]);
+    }
+}